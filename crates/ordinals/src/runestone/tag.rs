@@ -29,6 +29,8 @@ pub enum Tag {
     Refund = 93,
     ProtoPointer = 91,
     From = 95,
+    Predicate = 85,
+    Unwrap = 87,
 }
 
 impl Tag {
@@ -0,0 +1,181 @@
+use anyhow::{anyhow, Result};
+use bitcoin::{consensus::deserialize, ScriptBuf, Transaction, TxOut};
+use ordinals::{Artifact, Cenotaph, Runestone};
+use protorune::protostone::{Protostone, Protostones};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+const USAGE: &str =
+    "usage: protorune-cli <decode|encode> [--outputs <n>] [<input> | --file <path> | -]
+
+  decode  raw transaction or OP_RETURN script hex in, runestone and protostones as JSON out
+  encode  runestone and protostones as JSON in, OP_RETURN script hex out
+
+the input is read from stdin when it is - or left out. A bare script is decoded as
+the last of --outputs outputs, 2 unless given, since pointers and edicts are only
+valid up to the transaction's output count";
+
+/// Outputs a bare script is assumed to sit among: one to pay to and the OP_RETURN
+const DEFAULT_OUTPUTS: usize = 2;
+
+/// A runestone, or the cenotaph it turned out to be, with the protostones it carries
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Decoded {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    runestone: Option<Runestone>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cenotaph: Option<Cenotaph>,
+    #[serde(default)]
+    protostones: Vec<Protostone>,
+}
+
+/// Deciphers a raw transaction, or a bare OP_RETURN script placed after
+/// `num_outputs - 1` empty outputs
+fn decode(input: &str, num_outputs: usize) -> Result<Decoded> {
+    let bytes = hex::decode(input.trim())?;
+    let transaction = deserialize::<Transaction>(&bytes).unwrap_or_else(|_| {
+        let mut output = vec![TxOut::default(); num_outputs.saturating_sub(1)];
+        output.push(TxOut {
+            value: 0,
+            script_pubkey: ScriptBuf::from_bytes(bytes),
+        });
+        Transaction {
+            version: 2,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![],
+            output,
+        }
+    });
+    match Runestone::decipher(&transaction).ok_or(anyhow!("input carries no runestone"))? {
+        Artifact::Runestone(runestone) => {
+            let mut protostones = Protostone::from_runestone(&runestone)?;
+            protostones.check_outputs(transaction.output.len() as u32);
+            Ok(Decoded {
+                runestone: Some(runestone),
+                protostones,
+                ..Default::default()
+            })
+        }
+        Artifact::Cenotaph(cenotaph) => Ok(Decoded {
+            cenotaph: Some(cenotaph),
+            ..Default::default()
+        }),
+    }
+}
+
+/// Enciphers a runestone into OP_RETURN script hex. Protostones, when given,
+/// replace the runestone's `protocol` field
+fn encode(input: &str) -> Result<String> {
+    let decoded: Decoded = serde_json::from_str(input)?;
+    let mut runestone = decoded.runestone.ok_or(anyhow!(
+        "expected a runestone, a cenotaph can't be enciphered"
+    ))?;
+    if !decoded.protostones.is_empty() {
+        runestone.protocol = Some(decoded.protostones.encipher()?);
+    }
+    Ok(hex::encode(runestone.encipher().as_bytes()))
+}
+
+fn read_input(args: &[String]) -> Result<String> {
+    match args {
+        [] => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            Ok(input)
+        }
+        [value] if value == "-" => read_input(&[]),
+        [flag, path] if flag == "--file" => Ok(std::fs::read_to_string(path)?),
+        [value] => Ok(value.clone()),
+        _ => Err(anyhow!(USAGE)),
+    }
+}
+
+fn run(args: &[String]) -> Result<()> {
+    let (command, mut rest) = args.split_first().ok_or(anyhow!(USAGE))?;
+    let mut num_outputs = DEFAULT_OUTPUTS;
+    if let [flag, n, remaining @ ..] = rest {
+        if flag == "--outputs" {
+            num_outputs = n.parse().map_err(|_| anyhow!(USAGE))?;
+            rest = remaining;
+        }
+    }
+    match command.as_str() {
+        "decode" => println!(
+            "{}",
+            serde_json::to_string_pretty(&decode(&read_input(rest)?, num_outputs)?)?
+        ),
+        "encode" => println!("{}", encode(&read_input(rest)?)?),
+        _ => return Err(anyhow!(USAGE)),
+    }
+    Ok(())
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    if let Err(e) = run(&args) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protorune::flaw::ProtostoneFlaw;
+
+    #[test]
+    fn test_decode_encode_round_trip() {
+        let protostones = vec![Protostone {
+            burn: Some(1),
+            pointer: Some(0),
+            protocol_tag: 13,
+            ..Default::default()
+        }];
+        let runestone = Runestone {
+            pointer: Some(1),
+            protocol: Some(protostones.encipher().unwrap()),
+            ..Default::default()
+        };
+        let script = hex::encode(runestone.encipher().as_bytes());
+        let decoded = decode(&script, DEFAULT_OUTPUTS).unwrap();
+        assert_eq!(decoded.protostones, protostones);
+        let json = serde_json::to_string(&decoded).unwrap();
+        assert_eq!(encode(&json).unwrap(), script);
+        // protostones alone are enough to encode
+        let json = serde_json::json!({
+            "runestone": { "edicts": [], "pointer": 1 },
+            "protostones": [{ "protocol_tag": 13, "burn": 1, "pointer": 0 }],
+        });
+        assert_eq!(encode(&json.to_string()).unwrap(), script);
+    }
+
+    #[test]
+    fn test_decode_flags_targets() {
+        // one output and the OP_RETURN, so the protostones sit at virtual vouts 3 and 4
+        let protostones = vec![
+            Protostone {
+                burn: Some(1),
+                pointer: Some(4),
+                protocol_tag: 13,
+                ..Default::default()
+            },
+            Protostone {
+                refund: Some(5),
+                protocol_tag: 13,
+                ..Default::default()
+            },
+        ];
+        let runestone = Runestone {
+            protocol: Some(protostones.encipher().unwrap()),
+            ..Default::default()
+        };
+        let script = hex::encode(runestone.encipher().as_bytes());
+        let flaws = decode(&script, DEFAULT_OUTPUTS)
+            .unwrap()
+            .protostones
+            .into_iter()
+            .map(|stone| stone.flaw)
+            .collect::<Vec<Option<ProtostoneFlaw>>>();
+        assert_eq!(flaws, vec![None, Some(ProtostoneFlaw::TargetOutput)]);
+    }
+}
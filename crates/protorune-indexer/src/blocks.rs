@@ -0,0 +1,237 @@
+use anyhow::{anyhow, Result};
+use bitcoin::blockdata::block::Header;
+use bitcoin::blockdata::constants::genesis_block;
+use bitcoin::consensus::deserialize;
+use bitcoin::{Block, BlockHash, Network};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Where a block sits in a `blk*.dat` file
+#[derive(Clone, Debug)]
+struct Location {
+    file: usize,
+    offset: u64,
+    size: usize,
+}
+
+/// The blocks of Bitcoin Core's `blocks` directory. Core appends blocks to its
+/// `blk*.dat` files as they arrive, so the files are scanned once for headers and
+/// the longest chain from the genesis block decides which block is at which height
+pub struct BlockFiles {
+    files: Vec<PathBuf>,
+    /// key of the `xor.dat` obfuscation Core applies to block files since v28
+    xor: Option<[u8; 8]>,
+    chain: Vec<Location>,
+}
+
+impl BlockFiles {
+    pub fn open(dir: &Path, network: Network) -> Result<Self> {
+        let mut files = fs::read_dir(dir)?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<Result<Vec<PathBuf>>>()?
+            .into_iter()
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("blk") && name.ends_with(".dat"))
+            })
+            .collect::<Vec<PathBuf>>();
+        files.sort();
+        let xor = match fs::read(dir.join("xor.dat")) {
+            Ok(key) => Some(
+                key.try_into()
+                    .map_err(|_| anyhow!("xor.dat does not hold an 8 byte key"))?,
+            ),
+            Err(_) => None,
+        };
+        let magic = network.magic().to_bytes();
+        let mut headers = HashMap::<BlockHash, (BlockHash, Location)>::new();
+        for (file, path) in files.iter().enumerate() {
+            let mut bytes = fs::read(path)?;
+            unxor(&xor, 0, &mut bytes);
+            let mut pos = 0;
+            // files are preallocated, zeroes follow the last block
+            while pos + 8 <= bytes.len() && bytes[pos..pos + 4] != [0; 4] {
+                if bytes[pos..pos + 4] != magic {
+                    return Err(anyhow!(
+                        "{} holds a block for another network at offset {}",
+                        path.display(),
+                        pos
+                    ));
+                }
+                let size = u32::from_le_bytes(bytes[pos + 4..pos + 8].try_into()?) as usize;
+                let start = pos + 8;
+                if start + size > bytes.len() {
+                    break;
+                }
+                let header: Header = deserialize(&bytes[start..start + 80])?;
+                headers.insert(
+                    header.block_hash(),
+                    (
+                        header.prev_blockhash,
+                        Location {
+                            file,
+                            offset: start as u64,
+                            size,
+                        },
+                    ),
+                );
+                pos = start + size;
+            }
+        }
+        let chain = longest_chain(&headers, genesis_block(network).block_hash())?;
+        Ok(BlockFiles { files, xor, chain })
+    }
+    /// Height of the last block of the longest chain
+    pub fn tip(&self) -> u64 {
+        self.chain.len() as u64 - 1
+    }
+    pub fn block(&self, height: u64) -> Result<Option<Block>> {
+        let Some(location) = self.chain.get(height as usize) else {
+            return Ok(None);
+        };
+        let mut file = fs::File::open(&self.files[location.file])?;
+        file.seek(SeekFrom::Start(location.offset))?;
+        let mut bytes = vec![0u8; location.size];
+        file.read_exact(&mut bytes)?;
+        unxor(&self.xor, location.offset, &mut bytes);
+        Ok(Some(deserialize(&bytes)?))
+    }
+}
+
+/// Undoes the obfuscation of `bytes` read from `offset` into a block file
+fn unxor(key: &Option<[u8; 8]>, offset: u64, bytes: &mut [u8]) {
+    if let Some(key) = key {
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte ^= key[((offset + i as u64) % 8) as usize];
+        }
+    }
+}
+
+/// Locations of the blocks from `genesis` to the highest block descending from it,
+/// by height. Ties go to the block seen first
+fn longest_chain(
+    headers: &HashMap<BlockHash, (BlockHash, Location)>,
+    genesis: BlockHash,
+) -> Result<Vec<Location>> {
+    if !headers.contains_key(&genesis) {
+        return Err(anyhow!("the block files do not hold the genesis block"));
+    }
+    let mut children = HashMap::<BlockHash, Vec<BlockHash>>::new();
+    for (hash, (prev, _)) in headers {
+        children.entry(*prev).or_default().push(*hash);
+    }
+    let mut heights = HashMap::from([(genesis, 0u64)]);
+    let mut tip = (genesis, 0u64);
+    let mut pending = vec![genesis];
+    while let Some(hash) = pending.pop() {
+        let height = heights[&hash];
+        for child in children.get(&hash).into_iter().flatten() {
+            heights.insert(*child, height + 1);
+            if height + 1 > tip.1 {
+                tip = (*child, height + 1);
+            }
+            pending.push(*child);
+        }
+    }
+    let mut chain = vec![];
+    let mut hash = tip.0;
+    loop {
+        let (prev, location) = &headers[&hash];
+        chain.push(location.clone());
+        if hash == genesis {
+            break;
+        }
+        hash = *prev;
+    }
+    chain.reverse();
+    Ok(chain)
+}
+
+/// A directory of `<height>.hex` files, each holding one block as hex
+pub struct HexBlocks {
+    dir: PathBuf,
+}
+
+impl HexBlocks {
+    pub fn open(dir: &Path) -> Result<Self> {
+        if !dir.is_dir() {
+            return Err(anyhow!("{} is not a directory", dir.display()));
+        }
+        Ok(HexBlocks {
+            dir: dir.to_path_buf(),
+        })
+    }
+    pub fn block(&self, height: u64) -> Result<Option<Block>> {
+        let path = self.dir.join(format!("{}.hex", height));
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(deserialize(&hex::decode(
+            fs::read_to_string(path)?.trim(),
+        )?)?))
+    }
+}
+
+/// Blocks by height, from either kind of source
+pub enum BlockSource {
+    Files(BlockFiles),
+    Hex(HexBlocks),
+}
+
+impl BlockSource {
+    /// The block at `height`, or None past the last block the source holds
+    pub fn block(&self, height: u64) -> Result<Option<Block>> {
+        match self {
+            BlockSource::Files(files) => files.block(height),
+            BlockSource::Hex(hex) => hex.block(height),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::consensus::serialize;
+
+    #[test]
+    fn test_block_files_follow_the_longest_chain() {
+        let genesis = genesis_block(Network::Regtest);
+        let child = |parent: &Block, nonce: u32| {
+            let mut block = parent.clone();
+            block.header.prev_blockhash = parent.block_hash();
+            block.header.nonce = nonce;
+            block
+        };
+        let first = child(&genesis, 1);
+        let stale = child(&genesis, 2);
+        let second = child(&first, 3);
+        let key = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut bytes = vec![];
+        // out of order, as blocks arrive
+        for block in [&genesis, &second, &stale, &first] {
+            let block = serialize(block);
+            bytes.extend(Network::Regtest.magic().to_bytes());
+            bytes.extend((block.len() as u32).to_le_bytes());
+            bytes.extend(block);
+        }
+        bytes.extend([0; 16]);
+        unxor(&Some(key), 0, &mut bytes);
+        let dir = std::env::temp_dir().join("protorune-indexer-block-files");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("blk00000.dat"), bytes).unwrap();
+        fs::write(dir.join("xor.dat"), key).unwrap();
+        let files = BlockFiles::open(&dir, Network::Regtest).unwrap();
+        assert_eq!(files.tip(), 2);
+        for (height, block) in [genesis, first, second].iter().enumerate() {
+            assert_eq!(
+                files.block(height as u64).unwrap().unwrap().block_hash(),
+                block.block_hash()
+            );
+        }
+        assert!(files.block(3).unwrap().is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
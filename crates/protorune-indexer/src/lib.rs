@@ -0,0 +1,69 @@
+//! Runs the protorune indexer natively, outside a metashrew host, with the
+//! metashrew mock backend holding state in memory and an optional store file
+//! keeping it between runs
+pub mod blocks;
+pub mod store;
+
+use anyhow::Result;
+use bitcoin::BlockHash;
+use blocks::BlockSource;
+use protorune::registry::ProtocolRegistry;
+use protorune::Protorune;
+use std::time::{Duration, Instant};
+
+/// A block [`index_range`] got through
+#[derive(Clone, Debug)]
+pub struct IndexedBlock {
+    pub height: u64,
+    pub hash: BlockHash,
+    pub transactions: u64,
+    pub elapsed: Duration,
+}
+
+/// What one call to [`index_range`] got through
+#[derive(Clone, Debug, Default)]
+pub struct IndexStats {
+    pub blocks: u64,
+    pub transactions: u64,
+    pub elapsed: Duration,
+    /// height of the last block indexed
+    pub tip: Option<u64>,
+}
+
+/// Indexes the blocks of `source` from `start` up to and including `stop`, or to
+/// the last block the source holds. `on_block` is called after each block
+pub fn index_range(
+    source: &BlockSource,
+    start: u64,
+    stop: Option<u64>,
+    registry: &ProtocolRegistry,
+    mut on_block: impl FnMut(&IndexedBlock) -> Result<()>,
+) -> Result<IndexStats> {
+    let mut stats = IndexStats::default();
+    let started = Instant::now();
+    let mut height = start;
+    loop {
+        if stop.is_some_and(|stop| height > stop) {
+            break;
+        }
+        let Some(block) = source.block(height)? else {
+            break;
+        };
+        let hash = block.block_hash();
+        let transactions = block.txdata.len() as u64;
+        let indexing = Instant::now();
+        Protorune::index_block_with_registry(block, height, registry)?;
+        on_block(&IndexedBlock {
+            height,
+            hash,
+            transactions,
+            elapsed: indexing.elapsed(),
+        })?;
+        stats.blocks += 1;
+        stats.transactions += transactions;
+        stats.tip = Some(height);
+        height += 1;
+    }
+    stats.elapsed = started.elapsed();
+    Ok(stats)
+}
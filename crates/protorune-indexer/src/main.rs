@@ -0,0 +1,148 @@
+use anyhow::{anyhow, Result};
+use bitcoin::Network;
+use metashrew::index_pointer::KeyValuePointer;
+use protorune::registry::ProtocolRegistry;
+use protorune::tables;
+use protorune_indexer::blocks::{BlockFiles, BlockSource, HexBlocks};
+use protorune_indexer::{index_range, store};
+use protorune_support::constants::GENESIS;
+use std::path::PathBuf;
+
+const USAGE: &str = "usage: protorune-indexer (--blocks <dir> | --hex <dir>) [options]
+
+  --blocks <dir>      Bitcoin Core blocks directory holding blk*.dat files
+  --hex <dir>         directory of <height>.hex files, one block each
+  --network <name>    bitcoin, testnet, signet or regtest, bitcoin unless given
+  --start <height>    first block to index, one past the stored tip or 840000 unless given
+  --stop <height>     last block to index, the last one available unless given
+  --db <file>         store loaded before indexing and saved after it
+  --save-every <n>    also save the store every n blocks
+  --import <file>     seed the empty index with a snapshot before indexing
+  --export <file>     write a snapshot of the index after indexing
+  --quiet             print the summary only";
+
+#[derive(Default)]
+struct Options {
+    blocks: Option<PathBuf>,
+    hex: Option<PathBuf>,
+    network: Option<Network>,
+    start: Option<u64>,
+    stop: Option<u64>,
+    db: Option<PathBuf>,
+    save_every: Option<u64>,
+    import: Option<PathBuf>,
+    export: Option<PathBuf>,
+    quiet: bool,
+}
+
+fn parse_options(args: &[String]) -> Result<Options> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        if flag == "--quiet" {
+            options.quiet = true;
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or(anyhow!("{} takes a value\n\n{}", flag, USAGE))?;
+        let number = || -> Result<u64> {
+            value
+                .parse()
+                .map_err(|_| anyhow!("{} takes a number, not {}", flag, value))
+        };
+        match flag.as_str() {
+            "--blocks" => options.blocks = Some(value.into()),
+            "--hex" => options.hex = Some(value.into()),
+            "--network" => options.network = Some(value.parse()?),
+            "--start" => options.start = Some(number()?),
+            "--stop" => options.stop = Some(number()?),
+            "--db" => options.db = Some(value.into()),
+            "--save-every" => options.save_every = Some(number()?).filter(|every| *every != 0),
+            "--import" => options.import = Some(value.into()),
+            "--export" => options.export = Some(value.into()),
+            _ => return Err(anyhow!("unknown option {}\n\n{}", flag, USAGE)),
+        }
+    }
+    Ok(options)
+}
+
+fn run(args: &[String]) -> Result<()> {
+    let options = parse_options(args)?;
+    let source = match (&options.blocks, &options.hex) {
+        (Some(dir), None) => BlockSource::Files(BlockFiles::open(
+            dir,
+            options.network.unwrap_or(Network::Bitcoin),
+        )?),
+        (None, Some(dir)) => BlockSource::Hex(HexBlocks::open(dir)?),
+        _ => return Err(anyhow!(USAGE)),
+    };
+    let stored = match &options.db {
+        Some(db) => store::load(db)?,
+        None => 0,
+    };
+    if let Some(file) = &options.import {
+        let height = store::import_snapshot(file)?;
+        if !options.quiet {
+            eprintln!("imported a snapshot at block {}", height);
+        }
+    }
+    let start = options
+        .start
+        .unwrap_or(if stored == 0 && options.import.is_none() {
+            GENESIS as u64
+        } else {
+            tables::TIP_HEIGHT.get_value::<u64>() + 1
+        });
+    if !options.quiet {
+        eprintln!("loaded {} keys, indexing from block {}", stored, start);
+    }
+    let registry = ProtocolRegistry::new();
+    let stats = index_range(&source, start, options.stop, &registry, |block| {
+        if !options.quiet {
+            println!(
+                "{} {} {} txs {} ms",
+                block.height,
+                block.hash,
+                block.transactions,
+                block.elapsed.as_millis()
+            );
+        }
+        if let (Some(db), Some(every)) = (&options.db, options.save_every) {
+            if (block.height + 1 - start) % every == 0 {
+                store::save(db)?;
+            }
+        }
+        Ok(())
+    })?;
+    if let Some(db) = &options.db {
+        store::save(db)?;
+    }
+    if let Some(file) = &options.export {
+        let height = store::export_snapshot(file, &registry)?;
+        if !options.quiet {
+            eprintln!("exported a snapshot at block {}", height);
+        }
+    }
+    let seconds = stats.elapsed.as_secs_f64();
+    println!(
+        "indexed {} blocks, {} transactions, in {:.2} s ({:.2} blocks/s){}",
+        stats.blocks,
+        stats.transactions,
+        seconds,
+        stats.blocks as f64 / seconds.max(f64::EPSILON),
+        stats
+            .tip
+            .map(|tip| format!(", tip {}", tip))
+            .unwrap_or_default()
+    );
+    Ok(())
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    if let Err(e) = run(&args) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,78 @@
+use anyhow::{anyhow, Result};
+use metashrew::{get_cache, set};
+use protorune::registry::ProtocolRegistry;
+use protorune::snapshot;
+use protorune_support::snapshot::Snapshot;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Loads a store written by [`save`] into the metashrew cache, returning the
+/// number of keys read. A missing file is an empty store
+pub fn load(path: &Path) -> Result<usize> {
+    if !path.exists() {
+        return Ok(0);
+    }
+    let bytes = fs::read(path)?;
+    let mut pos = 0;
+    let take = |pos: &mut usize| -> Result<Vec<u8>> {
+        let length = bytes
+            .get(*pos..*pos + 4)
+            .ok_or(anyhow!("{} is truncated", path.display()))?;
+        let start = *pos + 4;
+        let end = start + u32::from_le_bytes(length.try_into()?) as usize;
+        *pos = end;
+        Ok(bytes
+            .get(start..end)
+            .ok_or(anyhow!("{} is truncated", path.display()))?
+            .to_vec())
+    };
+    let mut count = 0;
+    while pos < bytes.len() {
+        let key = take(&mut pos)?;
+        let value = take(&mut pos)?;
+        set(Arc::new(key), Arc::new(value));
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Writes every key in the metashrew cache to `path` as length-prefixed key and
+/// value pairs, sorted by key. The file is replaced only once fully written
+pub fn save(path: &Path) -> Result<usize> {
+    let cache = get_cache();
+    let mut entries = cache.iter().collect::<Vec<_>>();
+    entries.sort();
+    let partial = path.with_extension("partial");
+    let mut file = BufWriter::new(fs::File::create(&partial)?);
+    for (key, value) in &entries {
+        for bytes in [key.as_slice(), value.as_slice()] {
+            file.write_all(&(bytes.len() as u32).to_le_bytes())?;
+            file.write_all(bytes)?;
+        }
+    }
+    file.flush()?;
+    drop(file);
+    fs::rename(&partial, path)?;
+    Ok(entries.len())
+}
+
+/// Seeds the empty index with the snapshot file at `path`, returning the
+/// height indexing carries on after
+pub fn import_snapshot(path: &Path) -> Result<u64> {
+    let snapshot =
+        Snapshot::from_bytes(&fs::read(path)?).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+    snapshot::import_snapshot(&snapshot)?;
+    Ok(snapshot.height)
+}
+
+/// Writes a snapshot of the index at its tip to `path`, returning its height.
+/// The file is replaced only once fully written
+pub fn export_snapshot(path: &Path, registry: &ProtocolRegistry) -> Result<u64> {
+    let snapshot = snapshot::export_snapshot(registry)?;
+    let partial = path.with_extension("partial");
+    fs::write(&partial, snapshot.to_bytes())?;
+    fs::rename(&partial, path)?;
+    Ok(snapshot.height)
+}
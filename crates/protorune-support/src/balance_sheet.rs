@@ -4,6 +4,7 @@ use anyhow::{anyhow, Result};
 use ordinals::RuneId;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::{fmt, u128};
 
@@ -61,6 +62,19 @@ impl fmt::Display for ProtoruneRuneId {
     }
 }
 
+/// Parses the form [`fmt::Display`] writes, which the index stores base rune ids in
+impl FromStr for ProtoruneRuneId {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let (block, tx) = s
+            .strip_prefix("RuneId { block: ")
+            .and_then(|s| s.strip_suffix(" }"))
+            .and_then(|s| s.split_once(", tx: "))
+            .ok_or(anyhow!("{} is not a rune id", s))?;
+        Ok(ProtoruneRuneId::new(block.parse()?, tx.parse()?))
+    }
+}
+
 impl From<ProtoruneRuneId> for Vec<u8> {
     fn from(rune_id: ProtoruneRuneId) -> Self {
         let mut bytes = Vec::new();
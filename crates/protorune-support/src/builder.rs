@@ -0,0 +1,491 @@
+use crate::balance_sheet::ProtoruneRuneId;
+use crate::constants::PROTOBURN_TAG;
+use crate::protostone::{self, check_edict_output, check_target, ProtostoneFields};
+use anyhow::{anyhow, Result};
+use bitcoin::{OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness};
+use ordinals::{Edict, Etching, RuneId, Runestone};
+
+/// An edict moving protorunes held by a protostone
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProtostoneEdict {
+    pub id: ProtoruneRuneId,
+    pub amount: u128,
+    pub output: u32,
+}
+
+/// One protostone of a transaction put together by a [`TransactionBuilder`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProtostoneBuilder {
+    pub protocol_tag: u128,
+    pub burn: Option<u128>,
+    pub message: Vec<u8>,
+    pub pointer: Option<u32>,
+    pub refund: Option<u32>,
+    pub unwrap: Option<u32>,
+    /// predicate clauses as the indexer's `Predicate::to_integers` writes them
+    pub predicate: Vec<u128>,
+    pub edicts: Vec<ProtostoneEdict>,
+}
+
+impl ProtostoneBuilder {
+    /// A protostone of protocol `protocol_tag` that only moves protorunes
+    pub fn new(protocol_tag: u128) -> Self {
+        ProtostoneBuilder {
+            protocol_tag,
+            ..Default::default()
+        }
+    }
+    /// Burns the runes the runestone sends to the OP_RETURN into protocol `protocol_tag`
+    pub fn protoburn(protocol_tag: u128) -> Self {
+        ProtostoneBuilder {
+            protocol_tag: PROTOBURN_TAG,
+            burn: Some(protocol_tag),
+            ..Default::default()
+        }
+    }
+    /// Calls the message handler of protocol `protocol_tag` with `calldata`
+    pub fn message(protocol_tag: u128, calldata: Vec<u8>) -> Self {
+        ProtostoneBuilder {
+            protocol_tag,
+            message: calldata,
+            ..Default::default()
+        }
+    }
+    pub fn pointer(mut self, pointer: u32) -> Self {
+        self.pointer = Some(pointer);
+        self
+    }
+    pub fn refund(mut self, refund: u32) -> Self {
+        self.refund = Some(refund);
+        self
+    }
+    pub fn edict(mut self, id: ProtoruneRuneId, amount: u128, output: u32) -> Self {
+        self.edicts.push(ProtostoneEdict { id, amount, output });
+        self
+    }
+    /// Sends the runes left over once the message has run out of the protocol,
+    /// to output `vout`
+    pub fn unwrap(mut self, vout: u32) -> Self {
+        self.unwrap = Some(vout);
+        self
+    }
+    /// Reverts the message unless the clauses, given as integers, hold
+    pub fn predicate(mut self, integers: Vec<u128>) -> Self {
+        self.predicate = integers;
+        self
+    }
+    /// The protostone's fields as the indexer enciphers them
+    pub fn fields(&self) -> ProtostoneFields {
+        ProtostoneFields {
+            protocol_tag: self.protocol_tag,
+            burn: self.burn,
+            pointer: self.pointer,
+            refund: self.refund,
+            from: None,
+            unwrap: self.unwrap,
+            message: self.message.clone(),
+            predicate: self.predicate.clone(),
+            edicts: self
+                .edicts
+                .iter()
+                .map(|edict| (edict.id, edict.amount, edict.output.into()))
+                .collect(),
+        }
+    }
+    /// NOTE: This is not LEB encoded
+    pub fn to_integers(&self) -> Result<Vec<u128>> {
+        self.fields().to_integers()
+    }
+}
+
+/// Puts together a transaction carrying a runestone and its protostones. The
+/// OP_RETURN is appended after the outputs added with [`TransactionBuilder::output`],
+/// and the protostones take the virtual vouts past it
+#[derive(Clone, Debug, Default)]
+pub struct TransactionBuilder {
+    pub inputs: Vec<OutPoint>,
+    pub outputs: Vec<TxOut>,
+    pub etching: Option<Etching>,
+    pub mint: Option<RuneId>,
+    pub edicts: Vec<Edict>,
+    pub pointer: Option<u32>,
+    pub protostones: Vec<ProtostoneBuilder>,
+    /// largest OP_RETURN script [`TransactionBuilder::build`] accepts, in bytes
+    pub op_return_limit: Option<usize>,
+}
+
+impl TransactionBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn input(mut self, previous_output: OutPoint) -> Self {
+        self.inputs.push(previous_output);
+        self
+    }
+    pub fn output(mut self, script_pubkey: ScriptBuf, value: u64) -> Self {
+        self.outputs.push(TxOut {
+            value,
+            script_pubkey,
+        });
+        self
+    }
+    pub fn etching(mut self, etching: Etching) -> Self {
+        self.etching = Some(etching);
+        self
+    }
+    pub fn mint(mut self, id: RuneId) -> Self {
+        self.mint = Some(id);
+        self
+    }
+    pub fn edict(mut self, id: RuneId, amount: u128, output: u32) -> Self {
+        self.edicts.push(Edict { id, amount, output });
+        self
+    }
+    pub fn pointer(mut self, pointer: u32) -> Self {
+        self.pointer = Some(pointer);
+        self
+    }
+    pub fn protostone(mut self, protostone: ProtostoneBuilder) -> Self {
+        self.protostones.push(protostone);
+        self
+    }
+    pub fn op_return_limit(mut self, limit: usize) -> Self {
+        self.op_return_limit = Some(limit);
+        self
+    }
+    /// Outputs of the built transaction, the OP_RETURN included
+    pub fn num_outputs(&self) -> u32 {
+        self.outputs.len() as u32 + 1
+    }
+    /// Index of the OP_RETURN, which is where the runestone's pointer sends runes
+    /// to be protoburned
+    pub fn op_return_vout(&self) -> u32 {
+        self.outputs.len() as u32
+    }
+    /// Output the indexer sends unallocated runes to when the runestone has no
+    /// pointer: the first output that is not an OP_RETURN
+    pub fn default_output(&self) -> u32 {
+        self.outputs
+            .iter()
+            .position(|output| !output.script_pubkey.is_op_return())
+            .unwrap_or(0) as u32
+    }
+    /// Virtual vout of the protostone at `index`, one past the edict-spread output
+    pub fn protostone_vout(&self, index: u32) -> u32 {
+        protostone::protostone_vout(self.num_outputs(), index)
+    }
+    /// Fails on the first pointer, refund, edict or unwrap that targets a vout the
+    /// indexer would reject
+    pub fn validate(&self) -> Result<()> {
+        let num_outputs = self.num_outputs();
+        for edict in &self.edicts {
+            if edict.output > num_outputs {
+                return Err(anyhow!("edict targets nonexistent output {}", edict.output));
+            }
+        }
+        if let Some(pointer) = self.pointer {
+            if pointer >= num_outputs {
+                return Err(anyhow!("pointer targets nonexistent output {}", pointer));
+            }
+        }
+        for (i, stone) in self.protostones.iter().enumerate() {
+            let index = i as u32;
+            if stone.protocol_tag == 0 {
+                return Err(anyhow!("protostone {} has protocol tag 0", index));
+            }
+            let num_protostones = self.protostones.len() as u32;
+            for target in stone.pointer.iter().chain(stone.refund.iter()) {
+                check_target(index, *target, num_outputs, num_protostones)?;
+            }
+            for edict in &stone.edicts {
+                check_edict_output(index, edict.output.into(), num_outputs, num_protostones)?;
+            }
+            if let Some(unwrap) = stone.unwrap.filter(|unwrap| *unwrap >= num_outputs) {
+                return Err(anyhow!(
+                    "protostone {} unwraps to nonexistent output {}",
+                    index,
+                    unwrap
+                ));
+            }
+        }
+        Ok(())
+    }
+    /// The protostones as they go in `Runestone.protocol`
+    pub fn encipher_protostones(&self) -> Result<Vec<u128>> {
+        protostone::encipher(
+            &self
+                .protostones
+                .iter()
+                .map(ProtostoneBuilder::fields)
+                .collect::<Vec<_>>(),
+        )
+    }
+    pub fn runestone(&self) -> Result<Runestone> {
+        self.validate()?;
+        Ok(Runestone {
+            etching: self.etching,
+            mint: self.mint,
+            edicts: self.edicts.clone(),
+            pointer: self.pointer,
+            protocol: if self.protostones.is_empty() {
+                None
+            } else {
+                Some(self.encipher_protostones()?)
+            },
+        })
+    }
+    /// The OP_RETURN script carrying the runestone
+    pub fn script(&self) -> Result<ScriptBuf> {
+        Ok(self.runestone()?.encipher())
+    }
+    /// Exact size of the OP_RETURN script, opcodes and pushes included, which is
+    /// what relay policy measures
+    pub fn op_return_size(&self) -> Result<usize> {
+        Ok(self.script()?.len())
+    }
+    /// The same transfers in fewer bytes. Edicts moving the same rune to the same
+    /// output are merged, and pointers and refunds naming the output the indexer
+    /// would default to are dropped
+    pub fn compact(mut self) -> Self {
+        if self.pointer == Some(self.default_output()) {
+            self.pointer = None;
+        }
+        let unallocated_to = self.pointer.unwrap_or(self.default_output());
+        let spread = self.num_outputs();
+        self.edicts = merge_edicts(
+            self.edicts,
+            spread,
+            |edict| edict.id,
+            |edict| edict.output,
+            |edict| &mut edict.amount,
+        );
+        for stone in self.protostones.iter_mut() {
+            if stone.pointer == Some(unallocated_to) {
+                stone.pointer = None;
+            }
+            if stone.refund == Some(unallocated_to) {
+                stone.refund = None;
+            }
+            stone.edicts = merge_edicts(
+                std::mem::take(&mut stone.edicts),
+                spread,
+                |edict| edict.id,
+                |edict| edict.output,
+                |edict| &mut edict.amount,
+            );
+        }
+        self
+    }
+    /// Compacts the transaction and fails unless its OP_RETURN then fits in
+    /// `limit` bytes
+    pub fn fit(self, limit: usize) -> Result<Self> {
+        let builder = self.compact().op_return_limit(limit);
+        builder.check_size(builder.op_return_size()?)?;
+        Ok(builder)
+    }
+    /// Fails if an OP_RETURN of `size` bytes is over `op_return_limit`
+    fn check_size(&self, size: usize) -> Result<()> {
+        match self.op_return_limit {
+            Some(limit) if size > limit => Err(anyhow!(
+                "OP_RETURN of {} bytes exceeds the limit of {} bytes by {}",
+                size,
+                limit,
+                size - limit
+            )),
+            _ => Ok(()),
+        }
+    }
+    /// An unsigned transaction spending the inputs, with the OP_RETURN after the
+    /// outputs. Scripts and witnesses are left for the wallet to fill in, and an
+    /// OP_RETURN over `op_return_limit` is an error
+    pub fn build(&self) -> Result<Transaction> {
+        let script_pubkey = self.script()?;
+        self.check_size(script_pubkey.len())?;
+        let mut output = self.outputs.clone();
+        output.push(TxOut {
+            value: 0,
+            script_pubkey,
+        });
+        Ok(Transaction {
+            version: 2,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: self
+                .inputs
+                .iter()
+                .map(|previous_output| TxIn {
+                    previous_output: *previous_output,
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::MAX,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output,
+        })
+    }
+}
+
+/// Merges edicts that would run back to back once sorted by rune, as they are
+/// enciphered, and move that rune to the same output. Zero amounts move all that
+/// is left of the rune, and edicts to the `spread` output give their amount to
+/// each output in turn, so neither is ever merged
+fn merge_edicts<T, K: Ord + Copy>(
+    mut edicts: Vec<T>,
+    spread: u32,
+    id: impl Fn(&T) -> K,
+    output: impl Fn(&T) -> u32,
+    amount: impl Fn(&mut T) -> &mut u128,
+) -> Vec<T> {
+    edicts.sort_by_key(|edict| id(edict));
+    let mut merged: Vec<T> = vec![];
+    for mut edict in edicts {
+        if let Some(last) = merged.last_mut() {
+            if id(last) == id(&edict) && output(last) == output(&edict) && output(last) != spread {
+                let (total, next) = (*amount(last), *amount(&mut edict));
+                if let Some(sum) = total.checked_add(next).filter(|_| total != 0 && next != 0) {
+                    *amount(last) = sum;
+                    continue;
+                }
+            }
+        }
+        merged.push(edict);
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::byte_utils::ByteUtils;
+    use crate::constants::MAX_STANDARD_OP_RETURN_SIZE;
+    use crate::utils::split_bytes;
+    use ordinals::Artifact;
+
+    #[test]
+    fn test_transaction_builder_round_trip() {
+        let builder = TransactionBuilder::new()
+            .input(OutPoint::null())
+            .output(ScriptBuf::new(), 546)
+            .pointer(1)
+            .protostone(ProtostoneBuilder::protoburn(1).pointer(4))
+            .protostone(
+                ProtostoneBuilder::message(1, vec![7; 30])
+                    .pointer(0)
+                    .refund(0)
+                    .edict(ProtoruneRuneId::new(840000, 1), 100, 0),
+            );
+        assert_eq!(builder.protostone_vout(1), 4);
+        let transaction = builder.build().unwrap();
+        assert_eq!(transaction.output.len(), 2);
+        let runestone = match Runestone::decipher(&transaction) {
+            Some(Artifact::Runestone(runestone)) => runestone,
+            _ => panic!("built transaction carries no runestone"),
+        };
+        assert_eq!(runestone.pointer, Some(1));
+        let protocol = runestone.protocol.unwrap();
+        assert_eq!(protocol, builder.encipher_protostones().unwrap());
+        let bytes = protocol
+            .into_iter()
+            .flat_map(|v| v.snap_to_15_bytes())
+            .collect::<Vec<u8>>();
+        let mut integers = Runestone::integers(&bytes).unwrap();
+        while integers.last() == Some(&0) {
+            integers.pop();
+        }
+        let message = split_bytes(&vec![7; 30]);
+        // the protoburn, then the message with its edict to output 0 last
+        let mut expected = vec![PROTOBURN_TAG, 4, 83, 1, 91, 4, 1, 13, 91, 0, 93, 0];
+        expected.extend([81, message[0], 81, message[1], 0, 840000, 1, 100]);
+        assert_eq!(integers, expected);
+    }
+
+    #[test]
+    fn test_transaction_builder_rejects_bad_targets() {
+        // one output and the OP_RETURN, so protostones sit at virtual vouts 3 and 4
+        let builder = || {
+            TransactionBuilder::new()
+                .output(ScriptBuf::new(), 546)
+                .protostone(ProtostoneBuilder::protoburn(1).pointer(4))
+        };
+        assert!(builder()
+            .protostone(ProtostoneBuilder::new(1).pointer(0))
+            .build()
+            .is_ok());
+        // a pointer back at the protostone itself
+        assert!(builder()
+            .protostone(ProtostoneBuilder::new(1).pointer(4))
+            .build()
+            .is_err());
+        // an edict past the last protostone
+        assert!(builder()
+            .protostone(ProtostoneBuilder::new(1).edict(ProtoruneRuneId::new(840000, 1), 1, 5))
+            .build()
+            .is_err());
+        // an unwrap to the edict-spread output
+        assert!(builder()
+            .protostone(ProtostoneBuilder::new(1).unwrap(2))
+            .build()
+            .is_err());
+        // a runestone pointer past the OP_RETURN
+        assert!(builder().pointer(2).build().is_err());
+        assert!(builder()
+            .edict(RuneId::new(840000, 1).unwrap(), 1, 3)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_transaction_builder_op_return_size() {
+        let rune = RuneId::new(840000, 1).unwrap();
+        let protorune = ProtoruneRuneId::new(840000, 1);
+        // two outputs and the OP_RETURN, so protostones sit at virtual vouts 4 and 5
+        let builder = TransactionBuilder::new()
+            .output(ScriptBuf::new(), 546)
+            .output(ScriptBuf::new(), 546)
+            .pointer(0)
+            .edict(rune, 5, 1)
+            .edict(rune, 5, 1)
+            .protostone(ProtostoneBuilder::protoburn(1).pointer(5))
+            .protostone(
+                ProtostoneBuilder::message(1, vec![7; 30])
+                    .pointer(0)
+                    .refund(0)
+                    .edict(protorune, 5, 0)
+                    .edict(protorune, 5, 0)
+                    .edict(protorune, 0, 1)
+                    .edict(protorune, 0, 1),
+            );
+        let size = builder.op_return_size().unwrap();
+        assert_eq!(size, builder.build().unwrap().output[2].script_pubkey.len());
+        let compacted = builder.clone().compact();
+        let compacted_size = compacted.op_return_size().unwrap();
+        assert!(compacted_size < size);
+        assert_eq!(compacted.pointer, None);
+        assert_eq!(
+            compacted.edicts,
+            vec![Edict {
+                id: rune,
+                amount: 10,
+                output: 1
+            }]
+        );
+        let message = &compacted.protostones[1];
+        assert_eq!((message.pointer, message.refund), (None, None));
+        // zero amounts move whatever is left, so they stay apart
+        assert_eq!(
+            message
+                .edicts
+                .iter()
+                .map(|edict| (edict.amount, edict.output))
+                .collect::<Vec<(u128, u32)>>(),
+            vec![(10, 0), (0, 1), (0, 1)]
+        );
+        assert!(builder.clone().fit(compacted_size).is_ok());
+        assert!(builder.clone().fit(compacted_size - 1).is_err());
+        assert!(builder.clone().op_return_limit(size - 1).build().is_err());
+        assert!(TransactionBuilder::new()
+            .output(ScriptBuf::new(), 546)
+            .protostone(ProtostoneBuilder::message(1, vec![7; 90]))
+            .fit(MAX_STANDARD_OP_RETURN_SIZE)
+            .is_err());
+    }
+}
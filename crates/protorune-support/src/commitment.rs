@@ -0,0 +1,243 @@
+use crate::balance_sheet::{BalanceSheet, ProtoruneRuneId};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::OutPoint;
+
+/// Protocol tag the base runes are committed under, which no protocol can use
+pub const RUNES_TAG: u128 = 0;
+
+/// What a leaf of a block's state commitment describes, the first byte of its key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum LeafKind {
+    /// balances of an outpoint in the base table or a protocol table
+    Outpoint = 0,
+    /// terms and mint counter of a base rune etched or minted in the block
+    Rune = 1,
+    /// etching sequence, name count and runtime balance of a protocol
+    Protocol = 2,
+    /// how much of a rune is burned into, and was unwrapped out of, a protocol
+    Burned = 3,
+    /// name row of a protorune in a protocol's table, etched or burned in
+    Etching = 4,
+    /// what a message minted of the protorunes its protocol etched
+    Mint = 5,
+    /// a key of a protocol's state written in the block
+    State = 6,
+}
+
+fn push_rune(key: &mut Vec<u8>, rune: &ProtoruneRuneId) {
+    key.extend(rune.block.to_le_bytes());
+    key.extend(rune.tx.to_le_bytes());
+}
+
+pub fn outpoint_key(protocol_tag: u128, outpoint: &OutPoint) -> Vec<u8> {
+    let mut key = vec![LeafKind::Outpoint as u8];
+    key.extend(protocol_tag.to_le_bytes());
+    key.extend(outpoint.txid.as_byte_array());
+    key.extend(outpoint.vout.to_le_bytes());
+    key
+}
+
+pub fn rune_key(rune: &ProtoruneRuneId) -> Vec<u8> {
+    let mut key = vec![LeafKind::Rune as u8];
+    push_rune(&mut key, rune);
+    key
+}
+
+pub fn protocol_key(protocol_tag: u128) -> Vec<u8> {
+    let mut key = vec![LeafKind::Protocol as u8];
+    key.extend(protocol_tag.to_le_bytes());
+    key
+}
+
+pub fn burned_key(protocol_tag: u128, rune: &ProtoruneRuneId) -> Vec<u8> {
+    let mut key = vec![LeafKind::Burned as u8];
+    key.extend(protocol_tag.to_le_bytes());
+    push_rune(&mut key, rune);
+    key
+}
+
+pub fn etching_key(protocol_tag: u128, rune: &ProtoruneRuneId) -> Vec<u8> {
+    let mut key = vec![LeafKind::Etching as u8];
+    key.extend(protocol_tag.to_le_bytes());
+    push_rune(&mut key, rune);
+    key
+}
+
+pub fn mint_key(protocol_tag: u128, message: &OutPoint) -> Vec<u8> {
+    let mut key = vec![LeafKind::Mint as u8];
+    key.extend(protocol_tag.to_le_bytes());
+    key.extend(message.txid.as_byte_array());
+    key.extend(message.vout.to_le_bytes());
+    key
+}
+
+pub fn state_key(protocol_tag: u128, state: &[u8]) -> Vec<u8> {
+    let mut key = vec![LeafKind::State as u8];
+    key.extend(protocol_tag.to_le_bytes());
+    key.extend(state);
+    key
+}
+
+/// The nonzero balances of `sheet` sorted by rune, each as its rune id and
+/// amount in little endian u128s, so equal sheets encode equally whatever the
+/// order of their map
+pub fn encode_sheet(sheet: &BalanceSheet) -> Vec<u8> {
+    let mut balances = sheet
+        .balances
+        .iter()
+        .filter(|(_, balance)| **balance != 0)
+        .collect::<Vec<(&ProtoruneRuneId, &u128)>>();
+    balances.sort();
+    let mut bytes = Vec::with_capacity(balances.len() * 48);
+    for (rune, balance) in balances {
+        push_rune(&mut bytes, rune);
+        bytes.extend(balance.to_le_bytes());
+    }
+    bytes
+}
+
+/// Hash of a leaf. The key length is committed so keys and values can't be
+/// shifted into each other, and leaves are domain separated from nodes
+pub fn leaf_hash(key: &[u8], value: &[u8]) -> [u8; 32] {
+    let mut data = vec![0u8];
+    data.extend((key.len() as u32).to_le_bytes());
+    data.extend(key);
+    data.extend(value);
+    sha256::Hash::hash(&data).to_byte_array()
+}
+
+pub fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut data = vec![1u8];
+    data.extend(left);
+    data.extend(right);
+    sha256::Hash::hash(&data).to_byte_array()
+}
+
+/// Merkle root of leaf hashes, an odd node out moving up a level unhashed. No
+/// leaves make the zero hash
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return [0; 32];
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => node_hash(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    level[0]
+}
+
+/// State root of a block, chaining the root of its leaves to the state root of
+/// the block before
+pub fn chain_root(previous: &[u8; 32], block_root: &[u8; 32]) -> [u8; 32] {
+    let mut data = previous.to_vec();
+    data.extend(block_root);
+    sha256::Hash::hash(&data).to_byte_array()
+}
+
+/// Path from a leaf up to the root of a block's leaves
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MerkleProof {
+    /// position of the leaf
+    pub index: u64,
+    /// number of leaves of the block
+    pub leaves: u64,
+    /// hashes to combine with, from the leaf up, skipping levels where the
+    /// node is the odd one out
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl MerkleProof {
+    /// Proof for the leaf at `index` of `leaves`, the path [`merkle_root`] takes
+    pub fn new(leaves: &[[u8; 32]], index: usize) -> Option<MerkleProof> {
+        if index >= leaves.len() {
+            return None;
+        }
+        let mut siblings = vec![];
+        let mut level = leaves.to_vec();
+        let mut position = index;
+        while level.len() > 1 {
+            if let Some(sibling) = level.get(position ^ 1) {
+                siblings.push(*sibling);
+            }
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => node_hash(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            position /= 2;
+        }
+        Some(MerkleProof {
+            index: index as u64,
+            leaves: leaves.len() as u64,
+            siblings,
+        })
+    }
+    /// Root reached from `leaf` along the path, None when the path doesn't fit
+    /// the number of leaves
+    pub fn root(&self, leaf: &[u8; 32]) -> Option<[u8; 32]> {
+        if self.index >= self.leaves {
+            return None;
+        }
+        let mut siblings = self.siblings.iter();
+        let mut hash = *leaf;
+        let mut position = self.index;
+        let mut width = self.leaves;
+        while width > 1 {
+            if position ^ 1 < width {
+                let sibling = siblings.next()?;
+                hash = if position & 1 == 0 {
+                    node_hash(&hash, sibling)
+                } else {
+                    node_hash(sibling, &hash)
+                };
+            }
+            position /= 2;
+            width = width.div_ceil(2);
+        }
+        match siblings.next() {
+            Some(_) => None,
+            None => Some(hash),
+        }
+    }
+}
+
+/// Inclusion of an outpoint's balances in the state root of the block that
+/// created the outpoint
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OutpointProof {
+    pub height: u64,
+    /// state root of the block before
+    pub previous: [u8; 32],
+    pub proof: MerkleProof,
+}
+
+/// Checks that `outpoint` holds exactly `sheet` under `protocol_tag`, or under
+/// [`RUNES_TAG`] for base runes, against the state root of the block at
+/// `proof.height`, without access to an indexer. A block only commits to the
+/// outpoints it creates, so this shows the outpoint was created with `sheet` at
+/// that height, not that it is still unspent: spentness has to be checked
+/// against the chain
+pub fn verify_outpoint(
+    protocol_tag: u128,
+    outpoint: &OutPoint,
+    sheet: &BalanceSheet,
+    proof: &OutpointProof,
+    state_root: &[u8; 32],
+) -> bool {
+    let leaf = leaf_hash(&outpoint_key(protocol_tag, outpoint), &encode_sheet(sheet));
+    proof
+        .proof
+        .root(&leaf)
+        .is_some_and(|root| chain_root(&proof.previous, &root) == *state_root)
+}
@@ -12,3 +12,10 @@ pub const SUBSIDY_HALVING_INTERVAL: u64 = 210_000;
 pub const HEIGHT_INTERVAL: u64 = 17_500;
 
 pub const MAX_BYTES_LEB128_INT: usize = 18;
+
+/// Protocol tag a protostone carries when it only protoburns
+pub const PROTOBURN_TAG: u128 = 13;
+
+/// Largest OP_RETURN script, in bytes, relayed under the long-standing default
+/// `-datacarriersize` policy
+pub const MAX_STANDARD_OP_RETURN_SIZE: usize = 83;
@@ -1,5 +1,9 @@
 pub mod balance_sheet;
+pub mod builder;
 pub mod byte_utils;
+pub mod commitment;
 pub mod constants;
+pub mod protostone;
 pub mod utils;
 pub mod rune_transfer;
+pub mod snapshot;
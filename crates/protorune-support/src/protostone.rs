@@ -0,0 +1,149 @@
+use crate::balance_sheet::ProtoruneRuneId;
+use crate::utils::{encode_varint_list, split_bytes};
+use anyhow::{anyhow, Result};
+use ordinals::runestone::tag::Tag;
+
+/// The fields of a protostone as they are enciphered. The indexer's `Protostone`
+/// and the [`ProtostoneBuilder`](crate::builder::ProtostoneBuilder) both go
+/// through it, so a built protostone decodes to what was put in
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProtostoneFields {
+    pub protocol_tag: u128,
+    pub burn: Option<u128>,
+    pub pointer: Option<u32>,
+    pub refund: Option<u32>,
+    pub from: Option<u32>,
+    pub unwrap: Option<u32>,
+    pub message: Vec<u8>,
+    /// predicate clauses as sets of four integers: target, block, tx, amount
+    pub predicate: Vec<u128>,
+    /// edicts as rune, amount and output
+    pub edicts: Vec<(ProtoruneRuneId, u128, u128)>,
+}
+
+impl ProtostoneFields {
+    /// Enciphers the protostone into a vector of u128s
+    /// NOTE: This is not LEB encoded
+    pub fn to_integers(&self) -> Result<Vec<u128>> {
+        let mut payload = Vec::<u128>::new();
+        let tagged = [
+            (Tag::Burn, self.burn),
+            (Tag::ProtoPointer, self.pointer.map(u128::from)),
+            (Tag::Refund, self.refund.map(u128::from)),
+            (Tag::From, self.from.map(u128::from)),
+            (Tag::Unwrap, self.unwrap.map(u128::from)),
+        ];
+        for (tag, value) in tagged {
+            if let Some(value) = value {
+                payload.push(tag.into());
+                payload.push(value);
+            }
+        }
+        for item in split_bytes(&self.message) {
+            payload.push(Tag::Message.into());
+            payload.push(item);
+        }
+        for item in &self.predicate {
+            payload.push(Tag::Predicate.into());
+            payload.push(*item);
+        }
+        if !self.edicts.is_empty() {
+            payload.push(Tag::Body.into());
+            let mut edicts = self.edicts.clone();
+            edicts.sort_by_key(|(id, _, _)| *id);
+            let mut previous = ProtoruneRuneId::default();
+            for (id, amount, output) in edicts {
+                let (block, tx) = previous.delta(id).ok_or(anyhow!("invalid delta"))?;
+                payload.extend([block, tx, amount, output]);
+                previous = id;
+            }
+        }
+        Ok(payload)
+    }
+}
+
+/// The protostones as they go in `Runestone.protocol`: each one's protocol tag,
+/// the number of its integers and the integers, varint encoded and split into
+/// 15 byte chunks
+pub fn encipher(stones: &[ProtostoneFields]) -> Result<Vec<u128>> {
+    let mut values = Vec::<u128>::new();
+    for stone in stones {
+        let varints = stone.to_integers()?;
+        values.push(stone.protocol_tag);
+        values.push(varints.len() as u128);
+        values.extend(varints);
+    }
+    Ok(split_bytes(&encode_varint_list(&values)))
+}
+
+/// Returns the virtual vout addressing the protostone at `index`. Virtual vouts
+/// start one past the edict-spread output (`num_outputs`) so that pointers,
+/// refunds and edicts can feed one protostone into the next.
+pub fn protostone_vout(num_outputs: u32, index: u32) -> u32 {
+    num_outputs + 1 + index
+}
+
+/// Checks that `target` is a real output or the virtual vout of a protostone
+/// after the one at `index`, so runes can only flow forward through the chain
+/// of protostones
+pub fn check_target(index: u32, target: u32, num_outputs: u32, num_protostones: u32) -> Result<()> {
+    if target < num_outputs {
+        return Ok(());
+    }
+    let first = protostone_vout(num_outputs, 0);
+    if target < first || target >= first + num_protostones {
+        return Err(anyhow!(
+            "protostone {} references nonexistent vout {}",
+            index,
+            target
+        ));
+    }
+    if target <= protostone_vout(num_outputs, index) {
+        return Err(anyhow!(
+            "protostone {} references vout {} of an earlier protostone",
+            index,
+            target
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that an edict of the protostone at `index` outputs to a real output,
+/// the edict-spread output or the virtual vout of a later protostone, the bounds
+/// `ordinals` puts on runestone edicts
+pub fn check_edict_output(
+    index: u32,
+    output: u128,
+    num_outputs: u32,
+    num_protostones: u32,
+) -> Result<()> {
+    if output == num_outputs as u128 {
+        return Ok(());
+    }
+    let output = u32::try_from(output)
+        .map_err(|_| anyhow!("protostone {} edict outputs to {}", index, output))?;
+    check_target(index, output, num_outputs, num_protostones)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protostone_check_target() {
+        // two outputs, three protostones at virtual vouts 3, 4 and 5
+        assert!(check_target(0, 1, 2, 3).is_ok());
+        assert!(check_target(0, 4, 2, 3).is_ok());
+        assert!(check_target(1, 5, 2, 3).is_ok());
+        // the edict spread output is not addressable by pointers
+        assert!(check_target(0, 2, 2, 3).is_err());
+        // a protostone cannot target itself or an earlier protostone
+        assert!(check_target(1, 4, 2, 3).is_err());
+        assert!(check_target(1, 3, 2, 3).is_err());
+        // there is no fourth protostone
+        assert!(check_target(0, 6, 2, 3).is_err());
+        // but edicts can spread over the outputs
+        assert!(check_edict_output(0, 2, 2, 3).is_ok());
+        assert!(check_edict_output(0, u32::MAX as u128 + 1, 2, 3).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::balance_sheet::{BalanceSheet, ProtoruneRuneId};
 use anyhow::{anyhow, Result};
@@ -21,6 +21,53 @@ impl RuneTransfer {
     }
 }
 
+/// What a message handler does with the runes it was sent
+#[derive(Clone, Default)]
+pub struct MessageResult {
+    /// runes sent to the protostone's pointer
+    pub transfers: Vec<RuneTransfer>,
+    /// runes sent to other real outputs or to the virtual vouts of later protostones
+    pub allocations: Vec<(u32, RuneTransfer)>,
+    /// the protocol's runtime balance once the message has run
+    pub runtime: BalanceSheet,
+    /// opaque records the protocol emits for views
+    pub events: Vec<Vec<u8>>,
+}
+
+impl MessageResult {
+    /// Every output the result sends runes to, the pointer included
+    pub fn destinations(&self, pointer: u32) -> BTreeMap<u32, BalanceSheet> {
+        let mut destinations = BTreeMap::<u32, BalanceSheet>::new();
+        for transfer in &self.transfers {
+            destinations
+                .entry(pointer)
+                .or_default()
+                .increase(&transfer.id, transfer.value);
+        }
+        for (vout, transfer) in &self.allocations {
+            destinations
+                .entry(*vout)
+                .or_default()
+                .increase(&transfer.id, transfer.value);
+        }
+        destinations
+    }
+    /// Everything sent out of the message, across all destinations
+    pub fn outgoing(&self) -> BalanceSheet {
+        BalanceSheet::concat(self.destinations(0).into_values().collect())
+    }
+}
+
+impl From<(Vec<RuneTransfer>, BalanceSheet)> for MessageResult {
+    fn from(v: (Vec<RuneTransfer>, BalanceSheet)) -> MessageResult {
+        MessageResult {
+            transfers: v.0,
+            runtime: v.1,
+            ..Default::default()
+        }
+    }
+}
+
 pub trait OutgoingRunes {
     fn reconcile(
         &self,
@@ -30,7 +77,10 @@ pub trait OutgoingRunes {
     ) -> Result<()>;
 }
 
-impl OutgoingRunes for (Vec<RuneTransfer>, BalanceSheet) {
+impl OutgoingRunes for MessageResult {
+    /// Moves the runes held at `vout` to the destinations of the result. What
+    /// came in, together with the runtime balance before the message, has to
+    /// equal what goes out plus the runtime balance after it
     fn reconcile(
         &self,
         balances_by_output: &mut HashMap<u32, BalanceSheet>,
@@ -39,19 +89,44 @@ impl OutgoingRunes for (Vec<RuneTransfer>, BalanceSheet) {
     ) -> Result<()> {
         let runtime_initial = balances_by_output
             .get(&u32::MAX)
-            .map(|_| BalanceSheet::default())
-            .unwrap_or_else(|| BalanceSheet::default());
+            .cloned()
+            .unwrap_or_default();
         let incoming_initial = balances_by_output
             .get(&vout)
             .ok_or("")
             .map_err(|_| anyhow!("balance sheet not found"))?
             .clone();
-        let mut initial = BalanceSheet::merge(&incoming_initial, &runtime_initial);
-        let outgoing: BalanceSheet = self.0.clone().into();
-        initial.debit(&outgoing)?;
-        self.1.clone().debit(&initial)?;
-        balances_by_output.insert(u32::MAX, self.1.clone());
-        balances_by_output.insert(pointer, initial);
+        let mut available = BalanceSheet::merge(&incoming_initial, &runtime_initial);
+        let destinations = self.destinations(pointer);
+        for sheet in destinations.values() {
+            available.debit(sheet)?;
+        }
+        available.debit(&self.runtime)?;
+        if let Some((rune, balance)) = available.balances.iter().find(|(_, v)| **v != 0) {
+            return Err(anyhow!(
+                "message result does not account for {} of {}",
+                balance,
+                rune
+            ));
+        }
+        balances_by_output.remove(&vout);
+        balances_by_output.insert(u32::MAX, self.runtime.clone());
+        // a destination may be the virtual vout of a later protostone which
+        // already holds runes, so merge rather than overwrite
+        for (target, sheet) in destinations {
+            sheet.pipe(balances_by_output.entry(target).or_default());
+        }
         Ok(())
     }
 }
+
+impl OutgoingRunes for (Vec<RuneTransfer>, BalanceSheet) {
+    fn reconcile(
+        &self,
+        balances_by_output: &mut HashMap<u32, BalanceSheet>,
+        vout: u32,
+        pointer: u32,
+    ) -> Result<()> {
+        MessageResult::from(self.clone()).reconcile(balances_by_output, vout, pointer)
+    }
+}
@@ -0,0 +1,405 @@
+use crate::balance_sheet::{BalanceSheet, ProtoruneRuneId};
+use crate::commitment::encode_sheet;
+use anyhow::{anyhow, Result};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::{OutPoint, Txid};
+
+/// First bytes of a snapshot file
+pub const SNAPSHOT_MAGIC: [u8; 8] = *b"PRSNAPSH";
+/// Version of the snapshot format, the only one read back
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Etching, terms and mint counter of a base rune
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RuneEntry {
+    pub id: ProtoruneRuneId,
+    /// the rune's number, its name being `field_to_name` of it
+    pub number: u128,
+    pub divisibility: u8,
+    pub premine: u128,
+    pub amount: u128,
+    pub cap: u128,
+    pub mints_remaining: u128,
+    pub height_start: u64,
+    pub height_end: u64,
+    pub offset_start: u64,
+    pub offset_end: u64,
+    pub symbol: u32,
+    pub spacers: u32,
+}
+
+/// A protorune in a protocol's tables, etched by the protocol itself or burned in
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProtocolRuneEntry {
+    pub id: ProtoruneRuneId,
+    /// whether the protocol etched it and can mint it
+    pub internal: bool,
+    pub name: Vec<u8>,
+    pub divisibility: u8,
+    pub symbol: u32,
+    pub spacers: u32,
+}
+
+/// A key of a protocol's state, with its value and the items appended under it
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateEntry {
+    /// the key below the protocol's state root
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    pub items: Vec<Vec<u8>>,
+}
+
+/// What a protocol holds besides the balances of outpoints
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProtocolEntry {
+    pub protocol_tag: u128,
+    /// number of protorunes the protocol etched
+    pub sequence: u128,
+    /// protorunes by name
+    pub etchings: Vec<ProtocolRuneEntry>,
+    pub runtime: BalanceSheet,
+    /// base runes burned into the protocol
+    pub burned: BalanceSheet,
+    /// base runes unwrapped back out of it
+    pub unwrapped: BalanceSheet,
+    /// what its handlers keep in its state, by key
+    pub state: Vec<StateEntry>,
+}
+
+/// An unspent outpoint holding runes or protorunes
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OutpointEntry {
+    pub outpoint: OutPoint,
+    /// height of the block that created it
+    pub height: u64,
+    /// index of its transaction in that block
+    pub txindex: u32,
+    pub script: Vec<u8>,
+    pub value: u64,
+    /// balances by protocol tag, `RUNES_TAG` holding the base runes
+    pub balances: Vec<(u128, BalanceSheet)>,
+}
+
+/// The rune state of an index as of `height`, enough to seed an empty index and
+/// carry on indexing from the block after it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    pub height: u64,
+    /// state root committed at `height`, which the next block chains to
+    pub state_root: [u8; 32],
+    pub runes: Vec<RuneEntry>,
+    pub protocols: Vec<ProtocolEntry>,
+    pub outpoints: Vec<OutpointEntry>,
+}
+
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+    fn u32(&mut self, v: u32) {
+        self.0.extend(v.to_le_bytes());
+    }
+    fn u64(&mut self, v: u64) {
+        self.0.extend(v.to_le_bytes());
+    }
+    fn u128(&mut self, v: u128) {
+        self.0.extend(v.to_le_bytes());
+    }
+    fn bytes(&mut self, v: &[u8]) {
+        self.u32(v.len() as u32);
+        self.0.extend(v);
+    }
+    fn rune(&mut self, v: &ProtoruneRuneId) {
+        self.u128(v.block);
+        self.u128(v.tx);
+    }
+    fn sheet(&mut self, v: &BalanceSheet) {
+        self.bytes(&encode_sheet(v));
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .ok_or(anyhow!("snapshot is truncated"))?;
+        let taken = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(anyhow!("snapshot is truncated"))?;
+        self.pos = end;
+        Ok(taken)
+    }
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+    fn u128(&mut self) -> Result<u128> {
+        Ok(u128::from_le_bytes(self.take(16)?.try_into()?))
+    }
+    fn hash(&mut self) -> Result<[u8; 32]> {
+        Ok(self.take(32)?.try_into()?)
+    }
+    fn bytes(&mut self) -> Result<&'a [u8]> {
+        let length = self.u32()? as usize;
+        self.take(length)
+    }
+    fn rune(&mut self) -> Result<ProtoruneRuneId> {
+        Ok(ProtoruneRuneId::new(self.u128()?, self.u128()?))
+    }
+    fn sheet(&mut self) -> Result<BalanceSheet> {
+        let mut entries = Reader {
+            bytes: self.bytes()?,
+            pos: 0,
+        };
+        let mut sheet = BalanceSheet::default();
+        while entries.pos < entries.bytes.len() {
+            let rune = entries.rune()?;
+            sheet.set(&rune, entries.u128()?);
+        }
+        Ok(sheet)
+    }
+}
+
+impl Snapshot {
+    /// The state in a canonical encoding, every list sorted, so equal states
+    /// encode equally whatever order they were collected in
+    pub fn encode_state(&self) -> Vec<u8> {
+        let mut w = Writer::default();
+        w.u64(self.height);
+        w.0.extend(self.state_root);
+        let mut runes = self.runes.iter().collect::<Vec<&RuneEntry>>();
+        runes.sort_by_key(|rune| rune.id);
+        w.u32(runes.len() as u32);
+        for rune in runes {
+            w.rune(&rune.id);
+            w.u128(rune.number);
+            w.u8(rune.divisibility);
+            for v in [rune.premine, rune.amount, rune.cap, rune.mints_remaining] {
+                w.u128(v);
+            }
+            for v in [
+                rune.height_start,
+                rune.height_end,
+                rune.offset_start,
+                rune.offset_end,
+            ] {
+                w.u64(v);
+            }
+            w.u32(rune.symbol);
+            w.u32(rune.spacers);
+        }
+        let mut protocols = self.protocols.iter().collect::<Vec<&ProtocolEntry>>();
+        protocols.sort_by_key(|protocol| protocol.protocol_tag);
+        w.u32(protocols.len() as u32);
+        for protocol in protocols {
+            w.u128(protocol.protocol_tag);
+            w.u128(protocol.sequence);
+            let mut etchings = protocol
+                .etchings
+                .iter()
+                .collect::<Vec<&ProtocolRuneEntry>>();
+            etchings.sort_by(|a, b| (a.id, &a.name).cmp(&(b.id, &b.name)));
+            w.u32(etchings.len() as u32);
+            for etching in etchings {
+                w.rune(&etching.id);
+                w.u8(etching.internal as u8);
+                w.bytes(&etching.name);
+                w.u8(etching.divisibility);
+                w.u32(etching.symbol);
+                w.u32(etching.spacers);
+            }
+            w.sheet(&protocol.runtime);
+            w.sheet(&protocol.burned);
+            w.sheet(&protocol.unwrapped);
+            let mut state = protocol.state.iter().collect::<Vec<&StateEntry>>();
+            state.sort_by(|a, b| a.key.cmp(&b.key));
+            w.u32(state.len() as u32);
+            for entry in state {
+                w.bytes(&entry.key);
+                w.bytes(&entry.value);
+                w.u32(entry.items.len() as u32);
+                for item in &entry.items {
+                    w.bytes(item);
+                }
+            }
+        }
+        let mut outpoints = self.outpoints.iter().collect::<Vec<&OutpointEntry>>();
+        outpoints.sort_by_key(|entry| entry.outpoint);
+        w.u32(outpoints.len() as u32);
+        for entry in outpoints {
+            w.0.extend(entry.outpoint.txid.as_byte_array());
+            w.u32(entry.outpoint.vout);
+            w.u64(entry.height);
+            w.u32(entry.txindex);
+            w.bytes(&entry.script);
+            w.u64(entry.value);
+            let mut balances = entry
+                .balances
+                .iter()
+                .filter(|(_, sheet)| !encode_sheet(sheet).is_empty())
+                .collect::<Vec<&(u128, BalanceSheet)>>();
+            balances.sort_by_key(|(tag, _)| *tag);
+            w.u32(balances.len() as u32);
+            for (tag, sheet) in balances {
+                w.u128(*tag);
+                w.sheet(sheet);
+            }
+        }
+        w.0
+    }
+
+    fn decode_state(bytes: &[u8]) -> Result<Snapshot> {
+        let mut r = Reader { bytes, pos: 0 };
+        let mut snapshot = Snapshot {
+            height: r.u64()?,
+            state_root: r.hash()?,
+            ..Default::default()
+        };
+        for _ in 0..r.u32()? {
+            snapshot.runes.push(RuneEntry {
+                id: r.rune()?,
+                number: r.u128()?,
+                divisibility: r.u8()?,
+                premine: r.u128()?,
+                amount: r.u128()?,
+                cap: r.u128()?,
+                mints_remaining: r.u128()?,
+                height_start: r.u64()?,
+                height_end: r.u64()?,
+                offset_start: r.u64()?,
+                offset_end: r.u64()?,
+                symbol: r.u32()?,
+                spacers: r.u32()?,
+            });
+        }
+        for _ in 0..r.u32()? {
+            let mut protocol = ProtocolEntry {
+                protocol_tag: r.u128()?,
+                sequence: r.u128()?,
+                ..Default::default()
+            };
+            for _ in 0..r.u32()? {
+                protocol.etchings.push(ProtocolRuneEntry {
+                    id: r.rune()?,
+                    internal: r.u8()? != 0,
+                    name: r.bytes()?.to_vec(),
+                    divisibility: r.u8()?,
+                    symbol: r.u32()?,
+                    spacers: r.u32()?,
+                });
+            }
+            protocol.runtime = r.sheet()?;
+            protocol.burned = r.sheet()?;
+            protocol.unwrapped = r.sheet()?;
+            for _ in 0..r.u32()? {
+                let mut entry = StateEntry {
+                    key: r.bytes()?.to_vec(),
+                    value: r.bytes()?.to_vec(),
+                    items: vec![],
+                };
+                for _ in 0..r.u32()? {
+                    entry.items.push(r.bytes()?.to_vec());
+                }
+                protocol.state.push(entry);
+            }
+            snapshot.protocols.push(protocol);
+        }
+        for _ in 0..r.u32()? {
+            let mut entry = OutpointEntry {
+                outpoint: OutPoint::new(Txid::from_slice(r.take(32)?)?, r.u32()?),
+                height: r.u64()?,
+                txindex: r.u32()?,
+                script: r.bytes()?.to_vec(),
+                value: r.u64()?,
+                balances: vec![],
+            };
+            for _ in 0..r.u32()? {
+                entry.balances.push((r.u128()?, r.sheet()?));
+            }
+            snapshot.outpoints.push(entry);
+        }
+        if r.pos != bytes.len() {
+            return Err(anyhow!(
+                "snapshot has {} trailing bytes",
+                bytes.len() - r.pos
+            ));
+        }
+        Ok(snapshot)
+    }
+
+    /// Hash of [`Snapshot::encode_state`], which an import checks the state it
+    /// read and the state it wrote against
+    pub fn content_hash(&self) -> [u8; 32] {
+        sha256::Hash::hash(&self.encode_state()).to_byte_array()
+    }
+
+    /// The snapshot as a file: magic, version, content hash, the length of the
+    /// state and the state, then a checksum over everything before it
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let state = self.encode_state();
+        let mut w = Writer::default();
+        w.0.extend(SNAPSHOT_MAGIC);
+        w.u32(SNAPSHOT_VERSION);
+        w.0.extend(sha256::Hash::hash(&state).to_byte_array());
+        w.u64(state.len() as u64);
+        w.0.extend(state);
+        let checksum = sha256::Hash::hash(&w.0).to_byte_array();
+        w.0.extend(checksum);
+        w.0
+    }
+
+    /// Reads a file written by [`Snapshot::to_bytes`], failing on another format
+    /// or version, a bad checksum or a state that doesn't hash to the content
+    /// hash it was written with
+    pub fn from_bytes(bytes: &[u8]) -> Result<Snapshot> {
+        let (file, checksum) = bytes
+            .split_at_checked(bytes.len().saturating_sub(32))
+            .filter(|(file, _)| file.len() >= SNAPSHOT_MAGIC.len())
+            .ok_or(anyhow!("snapshot is truncated"))?;
+        let mut r = Reader {
+            bytes: file,
+            pos: 0,
+        };
+        if r.take(SNAPSHOT_MAGIC.len())? != SNAPSHOT_MAGIC {
+            return Err(anyhow!("not a protorune snapshot"));
+        }
+        let version = r.u32()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(anyhow!("snapshot version {} is not supported", version));
+        }
+        if sha256::Hash::hash(file).as_byte_array() != checksum {
+            return Err(anyhow!(
+                "snapshot checksum does not match, the file is corrupt"
+            ));
+        }
+        let content_hash = r.hash()?;
+        let length = r.u64()? as usize;
+        let state = r.take(length)?;
+        if r.pos != file.len() {
+            return Err(anyhow!(
+                "snapshot has {} trailing bytes",
+                file.len() - r.pos
+            ));
+        }
+        let snapshot = Snapshot::decode_state(state)?;
+        if snapshot.content_hash() != content_hash {
+            return Err(anyhow!("snapshot state does not match its content hash"));
+        }
+        Ok(snapshot)
+    }
+}
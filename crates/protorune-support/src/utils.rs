@@ -38,6 +38,31 @@ pub fn encode_varint_list(values: &Vec<u128>) -> Vec<u8> {
     result
 }
 
+/// This takes in an arbituary amount of bytes, and
+/// converts it in a list of u128s, making sure we don't
+/// write to the 16th byte of the u128.
+///
+/// To ensure the range of bytearrays does not exclude
+/// any bitfields within its terminal bytes, we choose a maximum length f
+/// or a u128 value within a u128[] intended for interpretation as a u8[] to 15 bytes.
+/// This allows us to safely model an arbitrary bytearray within the Runestone paradigm.
+pub fn split_bytes(v: &Vec<u8>) -> Vec<u128> {
+    let mut result: Vec<Vec<u8>> = vec![];
+    v.iter().enumerate().for_each(|(i, b)| {
+        if i % 15 == 0 {
+            result.push(Vec::<u8>::new());
+        }
+        result.last_mut().unwrap().push(*b);
+    });
+    result
+        .iter_mut()
+        .map(|v| {
+            v.resize(std::mem::size_of::<u128>(), 0u8);
+            return u128::from_le_bytes((&v[0..16]).try_into().unwrap());
+        })
+        .collect::<Vec<u128>>()
+}
+
 pub fn field_to_name(data: &u128) -> String {
     let mut v = data + 1; // Increment by 1
     let mut result = String::new();
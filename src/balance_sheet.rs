@@ -7,7 +7,10 @@ pub trait PersistentRecord {
         let runes_ptr = ptr.keyword("/runes");
         let balances_ptr = ptr.keyword("/balances");
 
-        for (rune, balance) in self.balances() {
+        // sorted, so every indexer stores the same list whatever the map's order
+        let mut balances = self.balances().iter().collect::<Vec<_>>();
+        balances.sort();
+        for (rune, balance) in balances {
             if *balance != 0u128 && !is_cenotaph {
                 runes_ptr.append((*rune).into());
 
@@ -0,0 +1,261 @@
+use crate::etching::{load_minted, PROTOCOL_ETCHING_BLOCK};
+use crate::fuel::CALL_FUEL;
+use crate::message::{resolve_inputs, MessageContextParcel};
+use crate::tables::RuneTable;
+use anyhow::{anyhow, Result};
+use bitcoin::OutPoint;
+use metashrew::index_pointer::{AtomicPointer, IndexPointer, KeyValuePointer};
+use protorune_support::balance_sheet::{BalanceSheet, ProtoruneRuneId};
+use protorune_support::rune_transfer::{MessageResult, OutgoingRunes, RuneTransfer};
+use protorune_support::utils::consensus_encode;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// How deep calls between protocols may nest below the message's own handler
+pub const MAX_CALL_DEPTH: usize = 8;
+
+/// A call one protocol made into another while a message was handled
+#[derive(Clone)]
+pub struct ProtocolCall {
+    pub protocol_tag: u128,
+    /// 1 for a call made by the message's own handler, 2 for a call made by
+    /// that callee, and so on
+    pub depth: usize,
+    /// protorunes the caller moved into the callee's table
+    pub sent: BalanceSheet,
+    /// the callee's transfers go back to the caller, its allocations and
+    /// runtime balance stay in the callee's table
+    pub result: MessageResult,
+}
+
+impl ProtocolCall {
+    /// What the callee sent back to its caller
+    pub fn returned(&self) -> BalanceSheet {
+        Self::returned_by(&self.result)
+    }
+    fn returned_by(result: &MessageResult) -> BalanceSheet {
+        let mut sheet = BalanceSheet::default();
+        for transfer in &result.transfers {
+            sheet.increase(&transfer.id, transfer.value);
+        }
+        sheet
+    }
+}
+
+/// The calls made under one frame, in the order they completed. Clones of a
+/// parcel share it, a callee gets a ledger of its own which is folded into its
+/// caller's once the call succeeds
+pub type CallLedger = Arc<Mutex<Vec<ProtocolCall>>>;
+
+impl MessageContextParcel {
+    /// Dispatches `calldata` to the handler of protocol `protocol_tag`, moving
+    /// `runes`, and the protoburns backing them, out of the calling protocol's
+    /// table and into the callee's. Protorunes a protocol etched itself are only
+    /// known to its own table and can't be sent. The call runs in a checkpoint
+    /// of its own, so a failed call leaves nothing behind and the caller is free
+    /// to carry on without it
+    pub fn call(
+        &self,
+        protocol_tag: u128,
+        calldata: Vec<u8>,
+        runes: Vec<RuneTransfer>,
+    ) -> Result<MessageResult> {
+        self.fuel.consume(CALL_FUEL)?;
+        if protocol_tag == self.protocol_tag || self.call_stack.contains(&protocol_tag) {
+            return Err(anyhow!(
+                "protocol {} is already handling this message",
+                protocol_tag
+            ));
+        }
+        if self.call_stack.len() >= MAX_CALL_DEPTH {
+            return Err(anyhow!("calls nest deeper than {}", MAX_CALL_DEPTH));
+        }
+        check_transferable(runes.iter().map(|transfer| &transfer.id))?;
+        let (handler, version) = self
+            .registry
+            .handler(&protocol_tag, self.height)
+            .ok_or(anyhow!("protocol {} is not active", protocol_tag))?;
+        let mut sent = BalanceSheet::default();
+        for transfer in &runes {
+            sent.increase(&transfer.id, transfer.value);
+        }
+        let runtimes = self.current_runtimes();
+        let runtime = runtimes.get(&protocol_tag).cloned().unwrap_or_default();
+        let mut atomic = self.atomic.derive(&IndexPointer::default());
+        let inputs = resolve_inputs(&atomic, &self.transaction, protocol_tag)?;
+        atomic.checkpoint();
+        let mut call_stack = self.call_stack.clone();
+        call_stack.push(self.protocol_tag);
+        let callee = MessageContextParcel {
+            atomic: atomic.derive(&IndexPointer::default()),
+            protocol_tag,
+            version,
+            runes: RuneTransfer::from_balance_sheet(sent.clone()),
+            inputs,
+            calldata,
+            runtime_balances: Box::new(runtime.clone()),
+            calls: CallLedger::default(),
+            call_stack,
+            runtimes,
+            ..self.clone()
+        };
+        // mints of an earlier call into the same protocol are already accounted for
+        let minted = load_minted(&callee)?;
+        match handler(&callee).and_then(|result| {
+            check_transferable(result.transfers.iter().map(|transfer| &transfer.id))?;
+            callee.settle(sent.clone(), runtime, &minted, &result)?;
+            // the callee is backed for what it keeps, the caller for what came back
+            move_burned(&callee.atomic, &sent, self.protocol_tag, protocol_tag)?;
+            move_burned(
+                &callee.atomic,
+                &ProtocolCall::returned_by(&result),
+                protocol_tag,
+                self.protocol_tag,
+            )?;
+            Ok(result)
+        }) {
+            Ok(result) => {
+                let message = OutPoint::new(self.transaction.txid(), self.vout);
+                let events = callee.atomic.derive(
+                    &RuneTable::for_protocol(protocol_tag)
+                        .EVENTS
+                        .select(&consensus_encode(&message)?),
+                );
+                for event in &result.events {
+                    events.append(Arc::new(event.clone()));
+                }
+                atomic.commit();
+                let mut calls = self.calls.lock().unwrap();
+                calls.push(ProtocolCall {
+                    protocol_tag,
+                    depth: self.call_stack.len() + 1,
+                    sent,
+                    result: result.clone(),
+                });
+                calls.extend(callee.calls.lock().unwrap().iter().cloned());
+                Ok(result)
+            }
+            Err(e) => {
+                atomic.rollback();
+                Err(e)
+            }
+        }
+    }
+    /// Runtime balances of the other protocols as this frame sees them, after
+    /// the calls it has made so far
+    fn current_runtimes(&self) -> HashMap<u128, BalanceSheet> {
+        let mut runtimes = self.runtimes.clone();
+        for call in self.calls.lock().unwrap().iter() {
+            runtimes.insert(call.protocol_tag, call.result.runtime.clone());
+        }
+        runtimes
+    }
+    /// Checks that a callee accounts for everything it was sent, minted or got
+    /// back from its own calls, the way a message result is reconciled
+    fn settle(
+        &self,
+        sent: BalanceSheet,
+        runtime: BalanceSheet,
+        minted_before: &BalanceSheet,
+        result: &MessageResult,
+    ) -> Result<()> {
+        if self.fuel.is_exhausted() {
+            return Err(anyhow!("call ran out of fuel"));
+        }
+        let mut incoming = sent;
+        let mut minted = load_minted(self)?;
+        minted.debit(minted_before)?;
+        minted.pipe(&mut incoming);
+        let mut runtime = runtime;
+        self.settle_calls(&mut incoming, &mut runtime)?;
+        let mut frame = HashMap::from([(self.vout, incoming), (u32::MAX, runtime)]);
+        // transfers land back on the frame's vout, which is the caller's side
+        result.reconcile(&mut frame, self.vout, self.vout)
+    }
+    /// Credits `incoming` with what the frame's own calls sent back and debits
+    /// what they were sent, drawing on `runtime` for what `incoming` can't cover
+    pub(crate) fn settle_calls(
+        &self,
+        incoming: &mut BalanceSheet,
+        runtime: &mut BalanceSheet,
+    ) -> Result<()> {
+        let depth = self.call_stack.len() + 1;
+        let calls = self.calls.lock().unwrap();
+        let direct = calls
+            .iter()
+            .filter(|call| call.depth == depth)
+            .collect::<Vec<&ProtocolCall>>();
+        for call in &direct {
+            call.returned().pipe(incoming);
+        }
+        for call in &direct {
+            for (rune, amount) in call.sent.balances.iter() {
+                let from_incoming = std::cmp::min(incoming.get(rune), *amount);
+                incoming.decrease(rune, from_incoming);
+                if !runtime.decrease(rune, amount - from_incoming) {
+                    return Err(anyhow!(
+                        "call to protocol {} sends {} of {} the caller does not hold",
+                        call.protocol_tag,
+                        amount,
+                        rune
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Refuses protorunes etched by a protocol, whose ids mean another rune in
+/// every other protocol's table
+fn check_transferable<'a>(mut runes: impl Iterator<Item = &'a ProtoruneRuneId>) -> Result<()> {
+    match runes.find(|rune| rune.block == PROTOCOL_ETCHING_BLOCK) {
+        Some(rune) => Err(anyhow!(
+            "{} was etched by a protocol and can't be sent to another",
+            rune
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Moves the protoburns backing `sheet` from protocol `from`'s table to `to`'s
+fn move_burned(atomic: &AtomicPointer, sheet: &BalanceSheet, from: u128, to: u128) -> Result<()> {
+    for (rune, amount) in sheet.balances.iter() {
+        let runeid: Arc<Vec<u8>> = (*rune).into();
+        let mut source = atomic.derive(&RuneTable::for_protocol(from).BURNED.select(&runeid));
+        let burned = source.get_value::<u128>();
+        if burned < *amount {
+            return Err(anyhow!(
+                "protocol {} moves {} of {} but only {} is burned into it",
+                from,
+                amount,
+                rune,
+                burned
+            ));
+        }
+        source.set_value::<u128>(burned - amount);
+        let mut target = atomic.derive(&RuneTable::for_protocol(to).BURNED.select(&runeid));
+        target.set_value::<u128>(target.get_value::<u128>() + amount);
+    }
+    Ok(())
+}
+
+/// Moves what every call in `calls` allocated into its callee's table, and
+/// leaves each callee with the runtime balance its last call ended on
+pub(crate) fn apply_calls(
+    calls: &[ProtocolCall],
+    proto_balances_by_output: &mut HashMap<u128, HashMap<u32, BalanceSheet>>,
+) {
+    for call in calls {
+        let balances = proto_balances_by_output
+            .entry(call.protocol_tag)
+            .or_default();
+        for (target, transfer) in &call.result.allocations {
+            balances
+                .entry(*target)
+                .or_default()
+                .increase(&transfer.id, transfer.value);
+        }
+        balances.insert(u32::MAX, call.result.runtime.clone());
+    }
+}
@@ -0,0 +1,450 @@
+use crate::balance_sheet::load_sheet;
+use crate::conservation::check_conservation;
+use crate::message::MessageContext;
+use crate::registry::ProtocolRegistry;
+use crate::tables::{self, RuneTable};
+use crate::test_helpers::{create_block_with_txs, create_coinbase_transaction};
+use crate::Protorune;
+use anyhow::{anyhow, Result};
+use bitcoin::{Address, Block, OutPoint, ScriptBuf, Transaction};
+use metashrew::index_pointer::KeyValuePointer;
+use ordinals::{Etching, Rune, RuneId};
+use protorune_support::balance_sheet::{BalanceSheet, ProtoruneRuneId};
+use protorune_support::builder::{ProtostoneBuilder, TransactionBuilder};
+use protorune_support::constants::{GENESIS, NETWORK};
+use protorune_support::utils::consensus_encode;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+/// Value of every output a step pays to an address
+const DUST: u64 = 546;
+
+/// A multi-block history for tests, written a step at a time:
+///
+/// ```ignore
+/// let mut chain = ChainBuilder::new();
+/// chain
+///     .register::<MyMessageContext>()?
+///     .etch("TESTTESTTEST", 1000, ADDRESS1)?
+///     .protoburn(ADDRESS1, MyMessageContext::protocol_tag(), ADDRESS2)?
+///     .message(ADDRESS2, MyMessageContext::protocol_tag(), vec![1], ADDRESS1)?;
+/// chain.assert_address_balance(None, ADDRESS2, &chain.rune("TESTTESTTEST")?, 0);
+/// ```
+///
+/// Every step mines one block, holding a coinbase and the step's transaction, at
+/// the next height and indexes it with the registered protocols. Steps spending
+/// `from` spend every output the chain has paid to that address and not spent
+/// since, so balances are followed by address rather than by outpoint
+pub struct ChainBuilder {
+    pub registry: ProtocolRegistry,
+    height: u64,
+    blocks: Vec<Block>,
+    /// runes etched by the chain, by name
+    runes: HashMap<String, RuneId>,
+    /// outputs paid to each address, oldest first
+    utxos: HashMap<String, Vec<OutPoint>>,
+    spent: HashSet<OutPoint>,
+}
+
+impl Default for ChainBuilder {
+    fn default() -> Self {
+        ChainBuilder::new()
+    }
+}
+
+impl ChainBuilder {
+    /// An empty chain whose first block is mined at the runes genesis height
+    pub fn new() -> Self {
+        ChainBuilder::at_height(GENESIS as u64)
+    }
+    pub fn at_height(height: u64) -> Self {
+        ChainBuilder {
+            registry: ProtocolRegistry::new(),
+            height,
+            blocks: vec![],
+            runes: HashMap::new(),
+            utxos: HashMap::new(),
+            spent: HashSet::new(),
+        }
+    }
+    pub fn register<T: MessageContext>(&mut self) -> Result<&mut Self> {
+        self.registry.register::<T>()?;
+        Ok(self)
+    }
+    /// Height the next block is mined at
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+    /// Transaction of the last step
+    pub fn last(&self) -> Result<&Transaction> {
+        self.blocks
+            .last()
+            .and_then(|block| block.txdata.get(1))
+            .ok_or(anyhow!("no step has been taken"))
+    }
+    /// Output `vout` of the last step's transaction
+    pub fn outpoint(&self, vout: u32) -> Result<OutPoint> {
+        Ok(OutPoint::new(self.last()?.txid(), vout))
+    }
+    /// Id of a rune the chain etched
+    pub fn rune(&self, name: &str) -> Result<ProtoruneRuneId> {
+        Ok(self.rune_id(name)?.into())
+    }
+    fn rune_id(&self, name: &str) -> Result<RuneId> {
+        self.runes
+            .get(&Rune::from_str(name)?.to_string())
+            .copied()
+            .ok_or(anyhow!("{} has not been etched", name))
+    }
+    /// Outputs `address` holds that the chain has not spent
+    pub fn unspent(&self, address: &str) -> Vec<OutPoint> {
+        self.utxos.get(address).cloned().unwrap_or_default()
+    }
+
+    /// Mines a block holding only its coinbase
+    pub fn mine(&mut self) -> Result<&mut Self> {
+        self.mine_block(vec![])
+    }
+    /// Etches `premine` of the rune `name` to `to`
+    pub fn etch(&mut self, name: &str, premine: u128, to: &str) -> Result<&mut Self> {
+        self.etch_with(
+            Etching {
+                divisibility: Some(0),
+                premine: Some(premine),
+                rune: Some(Rune::from_str(name)?),
+                spacers: None,
+                symbol: None,
+                terms: None,
+                turbo: true,
+            },
+            to,
+        )
+    }
+    /// Etches a rune with all of `etching`, its terms included, sending the premine
+    /// to `to`
+    pub fn etch_with(&mut self, etching: Etching, to: &str) -> Result<&mut Self> {
+        let rune = etching
+            .rune
+            .ok_or(anyhow!("an etching needs a rune name"))?;
+        let builder = self
+            .funded()
+            .output(script(to)?, DUST)
+            .etching(etching)
+            .pointer(0);
+        // the step's transaction comes right after the coinbase
+        let id = RuneId::new(self.height, 1).ok_or(anyhow!("invalid rune id"))?;
+        self.mine_transaction(builder)?;
+        self.runes.insert(rune.to_string(), id);
+        Ok(self)
+    }
+    /// Mints the rune `name` to `to`
+    pub fn mint(&mut self, name: &str, to: &str) -> Result<&mut Self> {
+        let builder = self
+            .funded()
+            .output(script(to)?, DUST)
+            .mint(self.rune_id(name)?)
+            .pointer(0);
+        self.mine_transaction(builder)
+    }
+    /// Sends `amount` of the rune `name` held by `from` to `to`, and the rest of
+    /// what `from` holds back to it
+    pub fn transfer(
+        &mut self,
+        from: &str,
+        name: &str,
+        amount: u128,
+        to: &str,
+    ) -> Result<&mut Self> {
+        let builder = self
+            .spending(from)?
+            .output(script(to)?, DUST)
+            .output(script(from)?, DUST)
+            .edict(self.rune_id(name)?, amount, 0)
+            .pointer(1);
+        self.mine_transaction(builder)
+    }
+    /// Sends `amount` of the protorune `id` of protocol `protocol_tag` held by
+    /// `from` to `to`, and the rest of what `from` holds back to it
+    pub fn protorune_transfer(
+        &mut self,
+        from: &str,
+        protocol_tag: u128,
+        id: ProtoruneRuneId,
+        amount: u128,
+        to: &str,
+    ) -> Result<&mut Self> {
+        let builder = self
+            .spending(from)?
+            .output(script(to)?, DUST)
+            .output(script(from)?, DUST)
+            .pointer(1)
+            .protostone(
+                ProtostoneBuilder::new(protocol_tag)
+                    .edict(id, amount, 0)
+                    .pointer(1),
+            );
+        self.mine_transaction(builder)
+    }
+    /// Protoburns every rune `from` holds into protocol `protocol_tag`, to `to`.
+    /// Protorunes `from` already holds go to `to` as well
+    pub fn protoburn(&mut self, from: &str, protocol_tag: u128, to: &str) -> Result<&mut Self> {
+        let held = self.held(&tables::RUNES, &self.unspent(from))?;
+        if held.balances.is_empty() {
+            return Err(anyhow!("{} holds no runes to protoburn", from));
+        }
+        let mut builder = self.spending(from)?.output(script(to)?, DUST);
+        let op_return = builder.op_return_vout();
+        for (id, amount) in held.balances.iter() {
+            builder = builder.edict(rune_id(id)?, *amount, op_return);
+        }
+        self.mine_transaction(
+            builder.protostone(ProtostoneBuilder::protoburn(protocol_tag).pointer(0)),
+        )
+    }
+    /// Calls protocol `protocol_tag` with `calldata`, passing it every protorune of
+    /// the protocol `from` holds. What the message sends on goes to `to` and a
+    /// refund goes back to `from`, as do the runes `from` holds
+    pub fn message(
+        &mut self,
+        from: &str,
+        protocol_tag: u128,
+        calldata: Vec<u8>,
+        to: &str,
+    ) -> Result<&mut Self> {
+        let held = self.held(&RuneTable::for_protocol(protocol_tag), &self.unspent(from))?;
+        let mut builder = self
+            .spending(from)?
+            .output(script(to)?, DUST)
+            .output(script(from)?, DUST)
+            .pointer(1);
+        if !held.balances.is_empty() {
+            // a first protostone hands the protorunes to the message
+            let message_vout = builder.protostone_vout(1);
+            let mut stone = ProtostoneBuilder::new(protocol_tag).pointer(1);
+            for id in held.balances.keys() {
+                stone = stone.edict(*id, 0, message_vout);
+            }
+            builder = builder.protostone(stone);
+        }
+        self.mine_transaction(
+            builder.protostone(
+                ProtostoneBuilder::message(protocol_tag, calldata)
+                    .pointer(0)
+                    .refund(1),
+            ),
+        )
+    }
+    /// Mines a block holding `transaction` as it is
+    pub fn transaction(&mut self, transaction: Transaction) -> Result<&mut Self> {
+        self.mine_block(vec![transaction])
+    }
+    /// Sends everything `from` holds, runes and protorunes, to `to`
+    pub fn spend(&mut self, from: &str, to: &str) -> Result<&mut Self> {
+        let builder = self.spending(from)?.output(script(to)?, DUST).pointer(0);
+        self.mine_transaction(builder)
+    }
+
+    /// Balance of `id` on `outpoint`, as protorunes of `protocol_tag` or as
+    /// runes when it is None
+    pub fn outpoint_balance(
+        &self,
+        protocol_tag: Option<u128>,
+        outpoint: &OutPoint,
+        id: &ProtoruneRuneId,
+    ) -> Result<u128> {
+        Ok(self.held(&table(protocol_tag), &[*outpoint])?.get(id))
+    }
+    /// Balance of `id` over the outputs the index holds for `address` and the
+    /// chain has not spent
+    pub fn address_balance(
+        &self,
+        protocol_tag: Option<u128>,
+        address: &str,
+        id: &ProtoruneRuneId,
+    ) -> Result<u128> {
+        let outpoints = tables::OUTPOINTS_FOR_ADDRESS
+            .select(&address.as_bytes().to_vec())
+            .get_list()
+            .into_iter()
+            .map(|bytes| {
+                bitcoin::consensus::deserialize::<OutPoint>(&bytes).map_err(|e| anyhow!(e))
+            })
+            .collect::<Result<Vec<OutPoint>>>()?
+            .into_iter()
+            .filter(|outpoint| !self.spent.contains(outpoint))
+            .collect::<Vec<OutPoint>>();
+        Ok(self.held(&table(protocol_tag), &outpoints)?.get(id))
+    }
+    /// Balance of `id` protocol `protocol_tag` holds for itself
+    pub fn runtime_balance(&self, protocol_tag: u128, id: &ProtoruneRuneId) -> u128 {
+        load_sheet(&RuneTable::for_protocol(protocol_tag).RUNTIME_BALANCE).get(id)
+    }
+    pub fn assert_outpoint_balance(
+        &self,
+        protocol_tag: Option<u128>,
+        outpoint: &OutPoint,
+        id: &ProtoruneRuneId,
+        expected: u128,
+    ) -> &Self {
+        assert_eq!(
+            self.outpoint_balance(protocol_tag, outpoint, id).unwrap(),
+            expected,
+            "balance of {} on {} in {}",
+            id,
+            outpoint,
+            describe(protocol_tag)
+        );
+        self
+    }
+    pub fn assert_address_balance(
+        &self,
+        protocol_tag: Option<u128>,
+        address: &str,
+        id: &ProtoruneRuneId,
+        expected: u128,
+    ) -> &Self {
+        assert_eq!(
+            self.address_balance(protocol_tag, address, id).unwrap(),
+            expected,
+            "balance of {} held by {} in {}",
+            id,
+            address,
+            describe(protocol_tag)
+        );
+        self
+    }
+    pub fn assert_runtime_balance(
+        &self,
+        protocol_tag: u128,
+        id: &ProtoruneRuneId,
+        expected: u128,
+    ) -> &Self {
+        assert_eq!(
+            self.runtime_balance(protocol_tag, id),
+            expected,
+            "balance of {} held by protocol {}",
+            id,
+            protocol_tag
+        );
+        self
+    }
+
+    /// Checks that every rune and protorune of the registered protocols is
+    /// conserved across the chain, see [`check_conservation`]
+    pub fn assert_conserved(&self) -> &Self {
+        let report = check_conservation(&self.registry).unwrap();
+        assert!(
+            report.is_conserved(),
+            "{}",
+            report
+                .violations
+                .iter()
+                .map(|violation| violation.to_string())
+                .collect::<Vec<String>>()
+                .join("\n")
+        );
+        self
+    }
+
+    /// A transaction paying its fee from the coinbase of the block before, which
+    /// keeps the txids of otherwise identical steps apart
+    fn funded(&self) -> TransactionBuilder {
+        TransactionBuilder::new().input(OutPoint::new(
+            create_coinbase_transaction(self.height as u32 - 1).txid(),
+            0,
+        ))
+    }
+    /// A funded transaction spending every output held by `from`
+    fn spending(&self, from: &str) -> Result<TransactionBuilder> {
+        let utxos = self.unspent(from);
+        if utxos.is_empty() {
+            return Err(anyhow!("{} holds no outputs to spend", from));
+        }
+        Ok(utxos
+            .into_iter()
+            .fold(self.funded(), |builder, outpoint| builder.input(outpoint)))
+    }
+    fn held(&self, table: &RuneTable, outpoints: &[OutPoint]) -> Result<BalanceSheet> {
+        Ok(BalanceSheet::concat(
+            outpoints
+                .iter()
+                .map(|outpoint| {
+                    Ok(load_sheet(
+                        &table.OUTPOINT_TO_RUNES.select(&consensus_encode(outpoint)?),
+                    ))
+                })
+                .collect::<Result<Vec<BalanceSheet>>>()?,
+        ))
+    }
+    fn mine_transaction(&mut self, mut builder: TransactionBuilder) -> Result<&mut Self> {
+        // protorunes only move along with a protostone, so one is added to carry
+        // them to the runestone's pointer
+        if builder.protostones.is_empty() {
+            if let Some(tag) = self.registry.tags().into_iter().next() {
+                builder = builder.protostone(ProtostoneBuilder::new(tag));
+            }
+        }
+        let transaction = builder.build()?;
+        self.mine_block(vec![transaction])
+    }
+    fn mine_block(&mut self, transactions: Vec<Transaction>) -> Result<&mut Self> {
+        let mut txdata = vec![create_coinbase_transaction(self.height as u32)];
+        txdata.extend(transactions);
+        let mut block = create_block_with_txs(txdata);
+        if let Some(previous) = self.blocks.last() {
+            block.header.prev_blockhash = previous.block_hash();
+        }
+        block.header.merkle_root = block
+            .compute_merkle_root()
+            .ok_or(anyhow!("block has no transactions"))?;
+        Protorune::index_block_with_registry(block.clone(), self.height, &self.registry)?;
+        for transaction in &block.txdata[1..] {
+            for input in &transaction.input {
+                self.spent.insert(input.previous_output);
+            }
+            for utxos in self.utxos.values_mut() {
+                utxos.retain(|outpoint| !self.spent.contains(outpoint));
+            }
+            for (vout, output) in transaction.output.iter().enumerate() {
+                if let Ok(address) = Address::from_script(&output.script_pubkey, NETWORK) {
+                    self.utxos
+                        .entry(address.to_string())
+                        .or_default()
+                        .push(OutPoint::new(transaction.txid(), vout as u32));
+                }
+            }
+        }
+        self.blocks.push(block);
+        self.height += 1;
+        Ok(self)
+    }
+}
+
+/// Table of protocol `protocol_tag`, or of the runes when it is None
+fn table(protocol_tag: Option<u128>) -> RuneTable {
+    match protocol_tag {
+        Some(tag) => RuneTable::for_protocol(tag),
+        None => tables::RUNES.clone(),
+    }
+}
+
+fn describe(protocol_tag: Option<u128>) -> String {
+    match protocol_tag {
+        Some(tag) => format!("protocol {}", tag),
+        None => String::from("runes"),
+    }
+}
+
+fn rune_id(id: &ProtoruneRuneId) -> Result<RuneId> {
+    RuneId::new(u64::try_from(id.block)?, u32::try_from(id.tx)?)
+        .ok_or(anyhow!("invalid rune id {}", id))
+}
+
+fn script(address: &str) -> Result<ScriptBuf> {
+    Ok(Address::from_str(address)?
+        .require_network(NETWORK)?
+        .script_pubkey())
+}
@@ -0,0 +1,297 @@
+use crate::balance_sheet::load_sheet;
+use crate::etching::minted_at;
+use crate::protostone::{protostone_vout, Protostone};
+use crate::registry::ProtocolRegistry;
+use crate::tables::{self, RuneTable};
+use crate::Protorune;
+use anyhow::{anyhow, Result};
+use bitcoin::hashes::Hash;
+use bitcoin::{Block, OutPoint};
+use metashrew::index_pointer::{IndexPointer, KeyValuePointer};
+use ordinals::{Artifact, Runestone};
+use protorune_support::balance_sheet::{BalanceSheet, ProtoruneRuneId};
+use protorune_support::commitment::{
+    burned_key, chain_root, encode_sheet, etching_key, leaf_hash, merkle_root, mint_key,
+    outpoint_key, protocol_key, rune_key, state_key, MerkleProof, OutpointProof, RUNES_TAG,
+};
+use protorune_support::utils::consensus_encode;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+/// Number, premine, amount, cap and remaining mints of the base rune `rune`
+fn rune_row(rune: &ProtoruneRuneId) -> Option<Vec<u8>> {
+    let name = Protorune::rune_name(rune)?;
+    let mut row = name.clone();
+    for field in [
+        &tables::RUNES.PREMINE,
+        &tables::RUNES.AMOUNT,
+        &tables::RUNES.CAP,
+        &tables::RUNES.MINTS_REMAINING,
+    ] {
+        row.extend(field.select(&name).get_value::<u128>().to_le_bytes());
+    }
+    Some(row)
+}
+
+/// Name, divisibility, symbol and spacers of `rune` in a protocol's `table`,
+/// and whether the protocol etched it
+fn etching_row(table: &RuneTable, rune: &ProtoruneRuneId) -> Option<Vec<u8>> {
+    let runeid: Arc<Vec<u8>> = (*rune).into();
+    let name = table.RUNE_ID_TO_ETCHING.select(&runeid).get();
+    if name.is_empty() {
+        return None;
+    }
+    let mut row = name.to_vec();
+    row.push(table.DIVISIBILITY.select(&name).get_value::<u8>());
+    row.extend(table.SYMBOL.select(&name).get_value::<u32>().to_le_bytes());
+    row.extend(table.SPACERS.select(&name).get_value::<u32>().to_le_bytes());
+    row.push(table.INTERNAL_MINT.select(&runeid).get_value::<u8>());
+    Some(row)
+}
+
+/// Value of a protocol state key and the items appended under it, each length
+/// prefixed
+fn state_row(state: &IndexPointer) -> Vec<u8> {
+    let mut row = vec![];
+    for value in std::iter::once(state.get()).chain(state.get_list()) {
+        row.extend((value.len() as u32).to_le_bytes());
+        row.extend(value.as_ref());
+    }
+    row
+}
+
+/// Keys and values of the leaves committing to what indexing `block` at `height`
+/// left in the rune tables, sorted by key: the balances of every output of the
+/// block in the base table and the tables of the protocols registered or burned
+/// into, the rows of runes the block etched or minted, and for each of those
+/// protocols its etching sequence, name count and runtime balance, what the
+/// block's messages minted, the state keys its handlers wrote, and the name rows
+/// and what is burned into it of the runes the block moved, minted or the
+/// protocol holds
+pub fn block_leaves(
+    registry: &ProtocolRegistry,
+    block: &Block,
+    height: u64,
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let tags = registry.indexed_tags();
+    let mut tables = vec![(RUNES_TAG, tables::RUNES.clone())];
+    tables.extend(tags.iter().map(|tag| (*tag, RuneTable::for_protocol(*tag))));
+    let mut leaves = BTreeMap::<Vec<u8>, Vec<u8>>::new();
+    let mut moved = BTreeSet::<ProtoruneRuneId>::new();
+    let mut messages = Vec::<OutPoint>::new();
+    for (index, tx) in block.txdata.iter().enumerate() {
+        let txid = tx.txid();
+        for vout in 0..tx.output.len() as u32 {
+            let outpoint = OutPoint::new(txid, vout);
+            let key = consensus_encode(&outpoint)?;
+            for (tag, table) in &tables {
+                let sheet = load_sheet(&table.OUTPOINT_TO_RUNES.select(&key));
+                let value = encode_sheet(&sheet);
+                if !value.is_empty() {
+                    moved.extend(sheet.balances.keys());
+                    leaves.insert(outpoint_key(*tag, &outpoint), value);
+                }
+            }
+        }
+        let mut runes = vec![ProtoruneRuneId::new(height as u128, index as u128)];
+        if let Some(Artifact::Runestone(runestone)) = Runestone::decipher(tx) {
+            runes.extend(runestone.mint.map(ProtoruneRuneId::from));
+            let num_outputs = tx.output.len() as u32;
+            let stones = Protostone::from_runestone(&runestone).map_or(0, |stones| stones.len());
+            messages.extend(
+                (0..stones as u32).map(|i| OutPoint::new(txid, protostone_vout(num_outputs, i))),
+            );
+        }
+        for rune in runes {
+            if let Some(row) = rune_row(&rune) {
+                leaves.insert(rune_key(&rune), row);
+            }
+        }
+    }
+    for tag in &tags {
+        let table = RuneTable::for_protocol(*tag);
+        let runtime = load_sheet(&table.RUNTIME_BALANCE);
+        let mut value = table.SEQUENCE.get_value::<u128>().to_le_bytes().to_vec();
+        value.extend(table.ETCHINGS.length().to_le_bytes());
+        value.extend(encode_sheet(&runtime));
+        leaves.insert(protocol_key(*tag), value);
+        let mut runes = moved.clone();
+        runes.extend(runtime.balances.keys());
+        for message in &messages {
+            let minted = minted_at(&table, message)?;
+            let value = encode_sheet(&minted);
+            if !value.is_empty() {
+                runes.extend(minted.balances.keys());
+                leaves.insert(mint_key(*tag, message), value);
+            }
+        }
+        for rune in &runes {
+            if let Some(row) = etching_row(&table, rune) {
+                leaves.insert(etching_key(*tag, rune), row);
+            }
+            let runeid: Arc<Vec<u8>> = (*rune).into();
+            let burned = table.BURNED.select(&runeid).get_value::<u128>();
+            let unwrapped = table.UNWRAPPED.select(&runeid).get_value::<u128>();
+            if burned != 0 || unwrapped != 0 {
+                let mut value = burned.to_le_bytes().to_vec();
+                value.extend(unwrapped.to_le_bytes());
+                leaves.insert(burned_key(*tag, rune), value);
+            }
+        }
+        let written = table
+            .STATE_WRITES
+            .select_value::<u64>(height)
+            .get_list()
+            .into_iter()
+            .collect::<BTreeSet<Arc<Vec<u8>>>>();
+        for key in written {
+            leaves.insert(state_key(*tag, &key), state_row(&table.STATE.select(&key)));
+        }
+    }
+    Ok(leaves.into_iter().collect())
+}
+
+/// State root stored for `height`, if it was indexed
+pub fn state_root(height: u64) -> Option<[u8; 32]> {
+    tables::STATE_ROOT
+        .select_value::<u64>(height)
+        .get()
+        .as_slice()
+        .try_into()
+        .ok()
+}
+
+/// Leaf hashes committed at `height`, in order
+pub fn state_leaves(height: u64) -> Vec<[u8; 32]> {
+    tables::STATE_LEAVES
+        .select_value::<u64>(height)
+        .get()
+        .chunks_exact(32)
+        .map(|hash| hash.try_into().unwrap())
+        .collect()
+}
+
+/// Commits to the leaves of `block`, chained to the state root of the block
+/// before, or to the zero hash for the first block indexed, and stores the
+/// result as the state root of `height`, keeping the leaf hashes to prove
+/// outpoint balances against it
+pub fn commit_block(registry: &ProtocolRegistry, block: &Block, height: u64) -> Result<[u8; 32]> {
+    let hashes = block_leaves(registry, block, height)?
+        .iter()
+        .map(|(key, value)| leaf_hash(key, value))
+        .collect::<Vec<[u8; 32]>>();
+    let previous = height
+        .checked_sub(1)
+        .and_then(state_root)
+        .unwrap_or_default();
+    let root = chain_root(&previous, &merkle_root(&hashes));
+    tables::STATE_ROOT
+        .select_value::<u64>(height)
+        .set(Arc::new(root.to_vec()));
+    tables::STATE_LEAVES
+        .select_value::<u64>(height)
+        .set(Arc::new(hashes.concat()));
+    Ok(root)
+}
+
+/// Balances of `outpoint` under `protocol_tag`, or [`RUNES_TAG`] for base
+/// runes, with the proof of their inclusion in the state root of the block that
+/// created the outpoint. Outpoints holding nothing have no leaf to prove. The
+/// proof says nothing of whether the outpoint has been spent since
+pub fn outpoint_proof(
+    protocol_tag: u128,
+    outpoint: &OutPoint,
+) -> Result<(BalanceSheet, OutpointProof)> {
+    let created = tables::RUNES
+        .OUTPOINT_TO_HEIGHT
+        .select(&outpoint.txid.as_byte_array().to_vec())
+        .select_value::<u32>(outpoint.vout);
+    if created.get().is_empty() {
+        return Err(anyhow!("{} is not indexed", outpoint));
+    }
+    let height = created.get_value::<u64>();
+    let table = match protocol_tag {
+        RUNES_TAG => tables::RUNES.clone(),
+        tag => RuneTable::for_protocol(tag),
+    };
+    let sheet = load_sheet(&table.OUTPOINT_TO_RUNES.select(&consensus_encode(outpoint)?));
+    let value = encode_sheet(&sheet);
+    if value.is_empty() {
+        return Err(anyhow!(
+            "{} holds nothing under protocol {}",
+            outpoint,
+            protocol_tag
+        ));
+    }
+    let leaf = leaf_hash(&outpoint_key(protocol_tag, outpoint), &value);
+    let leaves = state_leaves(height);
+    let proof = leaves
+        .iter()
+        .position(|hash| *hash == leaf)
+        .and_then(|index| MerkleProof::new(&leaves, index))
+        .ok_or_else(|| anyhow!("{} is not committed at height {}", outpoint, height))?;
+    let previous = height
+        .checked_sub(1)
+        .and_then(state_root)
+        .unwrap_or_default();
+    Ok((
+        sheet,
+        OutpointProof {
+            height,
+            previous,
+            proof,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commitment_ignores_sheet_order() {
+        let runes = (1..20)
+            .map(|tx| ProtoruneRuneId::new(840000, tx))
+            .collect::<Vec<ProtoruneRuneId>>();
+        let amounts = (1..20).collect::<Vec<u128>>();
+        let mut reversed = runes.clone();
+        reversed.reverse();
+        let mut reversed_amounts = amounts.clone();
+        reversed_amounts.reverse();
+        let sheet = BalanceSheet::from_pairs(runes, amounts);
+        assert_eq!(
+            encode_sheet(&sheet),
+            encode_sheet(&BalanceSheet::from_pairs(reversed, reversed_amounts))
+        );
+        let mut zero = sheet.clone();
+        zero.set(&ProtoruneRuneId::new(840001, 1), 0);
+        assert_eq!(encode_sheet(&sheet), encode_sheet(&zero));
+        // three leaves, the third moving up unhashed
+        let leaves = [[1u8; 32], [2u8; 32], [3u8; 32]];
+        assert_eq!(
+            merkle_root(&leaves),
+            protorune_support::commitment::node_hash(
+                &protorune_support::commitment::node_hash(&leaves[0], &leaves[1]),
+                &leaves[2]
+            )
+        );
+        assert_eq!(merkle_root(&[]), [0; 32]);
+    }
+
+    #[test]
+    fn test_merkle_proof() {
+        for count in 1..10u8 {
+            let leaves = (0..count).map(|i| [i; 32]).collect::<Vec<[u8; 32]>>();
+            let root = merkle_root(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = MerkleProof::new(&leaves, index).unwrap();
+                assert_eq!(proof.root(leaf), Some(root));
+                assert_ne!(proof.root(&[count; 32]), Some(root));
+                let mut moved = proof.clone();
+                moved.index = (moved.index + 1) % moved.leaves;
+                assert!(count == 1 || moved.root(leaf) != Some(root));
+            }
+            assert_eq!(MerkleProof::new(&leaves, count as usize), None);
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use crate::balance_sheet::load_sheet;
+use crate::etching::PROTOCOL_ETCHING_BLOCK;
+use crate::proto::protorune::Output;
+use crate::registry::ProtocolRegistry;
+use crate::snapshot::seeded_txindex;
+use crate::tables::{self, RuneTable};
+use crate::Protorune;
+use anyhow::Result;
+use bitcoin::hashes::Hash;
+use bitcoin::{OutPoint, ScriptBuf, Txid};
+use metashrew::index_pointer::KeyValuePointer;
+use protobuf::Message;
+use protorune_support::balance_sheet::ProtoruneRuneId;
+use protorune_support::utils::{consensus_decode, consensus_encode};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::io::Cursor;
+use std::sync::Arc;
+
+/// A way the index disagrees with itself
+#[derive(Clone, Debug, PartialEq)]
+pub enum Violation {
+    /// An outpoint holds a rune no indexed transaction etched
+    Unetched {
+        rune: ProtoruneRuneId,
+        outpoint: OutPoint,
+    },
+    /// The rune on unspent outputs and burned on OP_RETURNs, protoburns included,
+    /// is not what was premined, minted and unwrapped back out of protocols
+    Supply {
+        rune: ProtoruneRuneId,
+        issued: u128,
+        found: u128,
+        outpoints: Vec<OutPoint>,
+    },
+    /// A protocol holds more of a rune, on unspent outputs and in its runtime
+    /// balance, than is burned into it
+    Overissued {
+        protocol_tag: u128,
+        rune: ProtoruneRuneId,
+        burned: u128,
+        found: u128,
+        outpoints: Vec<OutPoint>,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::Unetched { rune, outpoint } => {
+                write!(f, "{} holds {}, which was never etched", outpoint, rune)
+            }
+            Violation::Supply {
+                rune,
+                issued,
+                found,
+                outpoints,
+            } => write!(
+                f,
+                "{} issued {} but {} is found on {} outpoints",
+                rune,
+                issued,
+                found,
+                outpoints.len()
+            ),
+            Violation::Overissued {
+                protocol_tag,
+                rune,
+                burned,
+                found,
+                outpoints,
+            } => write!(
+                f,
+                "protocol {} holds {} of {} on {} outpoints and its runtime, but only {} is burned in",
+                protocol_tag,
+                found,
+                rune,
+                outpoints.len(),
+                burned
+            ),
+        }
+    }
+}
+
+/// What [`check_conservation`] walked and what it found wrong
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConservationReport {
+    pub blocks: u64,
+    pub outpoints: u64,
+    pub runes: u64,
+    pub violations: Vec<Violation>,
+}
+
+impl ConservationReport {
+    pub fn is_conserved(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Amount of a rune found, and where
+#[derive(Default)]
+struct Holdings {
+    found: u128,
+    outpoints: Vec<OutPoint>,
+}
+
+impl Holdings {
+    fn add(&mut self, outpoint: OutPoint, amount: u128) {
+        self.found += amount;
+        self.outpoints.push(outpoint);
+    }
+}
+
+/// Issued supply of the base rune `rune`: its premine, what was minted of its cap
+/// and what protocols unwrapped, which returns protoburned runes to circulation
+/// while the burn stays on its OP_RETURN
+fn issued(rune: &ProtoruneRuneId, tags: &[u128]) -> Option<u128> {
+    let name = Protorune::rune_name(rune)?;
+    let premine = tables::RUNES.PREMINE.select(&name).get_value::<u128>();
+    let cap = tables::RUNES.CAP.select(&name).get_value::<u128>();
+    let remaining = tables::RUNES
+        .MINTS_REMAINING
+        .select(&name)
+        .get_value::<u128>();
+    let amount = tables::RUNES.AMOUNT.select(&name).get_value::<u128>();
+    let runeid: Arc<Vec<u8>> = (*rune).into();
+    let unwrapped = tags
+        .iter()
+        .map(|tag| {
+            RuneTable::for_protocol(*tag)
+                .UNWRAPPED
+                .select(&runeid)
+                .get_value::<u128>()
+        })
+        .sum::<u128>();
+    Some(premine + cap.saturating_sub(remaining) * amount + unwrapped)
+}
+
+/// Base runes and protorunes found so far, by rune and by protocol and rune
+#[derive(Default)]
+struct Found {
+    runes: BTreeMap<ProtoruneRuneId, Holdings>,
+    protorunes: BTreeMap<(u128, ProtoruneRuneId), Holdings>,
+}
+
+impl Found {
+    /// Notes the rune the transaction at `index` in the block at `height`
+    /// etched, if it etched one, so a rune no output holds is still checked
+    fn etched(&mut self, height: u64, index: u32) {
+        let etched = Protorune::build_rune_id(height, index);
+        if !tables::RUNES
+            .RUNE_ID_TO_ETCHING
+            .select(&etched)
+            .get()
+            .is_empty()
+        {
+            self.runes
+                .entry(ProtoruneRuneId::new(height as u128, index as u128))
+                .or_default();
+        }
+    }
+
+    /// Adds what `outpoint` holds unless it is spent, returning whether the
+    /// output is indexed at all
+    fn add(
+        &mut self,
+        outpoint: OutPoint,
+        tags: &[u128],
+        report: &mut ConservationReport,
+    ) -> Result<bool> {
+        let key = consensus_encode(&outpoint)?;
+        let output = tables::OUTPOINT_TO_OUTPUT.select(&key).get();
+        if output.is_empty() {
+            return Ok(false);
+        }
+        report.outpoints += 1;
+        // an OP_RETURN is never spent, what it holds is burned
+        let burned =
+            ScriptBuf::from_bytes(Output::parse_from_bytes(&output)?.script).is_op_return();
+        if !burned && !tables::OUTPOINT_SPENT_BY.select(&key).get().is_empty() {
+            return Ok(true);
+        }
+        let sheet = load_sheet(&tables::RUNES.OUTPOINT_TO_RUNES.select(&key));
+        for (rune, balance) in sheet.balances {
+            if balance != 0 {
+                self.runes.entry(rune).or_default().add(outpoint, balance);
+            }
+        }
+        for tag in tags {
+            let table = RuneTable::for_protocol(*tag);
+            let sheet = load_sheet(&table.OUTPOINT_TO_RUNES.select(&key));
+            for (rune, balance) in sheet.balances {
+                if balance != 0 && rune.block != PROTOCOL_ETCHING_BLOCK {
+                    self.protorunes
+                        .entry((*tag, rune))
+                        .or_default()
+                        .add(outpoint, balance);
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Walks every output of the blocks indexed up to the tip, in the base table and
+/// the table of each registered protocol and each protocol runes were burned
+/// into, and checks that runes are conserved: for every base rune, what unspent
+/// outputs hold plus what OP_RETURNs burned, protoburns included, equals its
+/// premine, its mints times their amount and what protocols unwrapped. Protorunes burned into a protocol, counting its
+/// runtime balance, never exceed what the protocol holds as burned. Protorunes a
+/// protocol etched itself are not burned in and are left out. The walk goes
+/// down from the tip until a height without indexed transactions, then takes
+/// the outpoints a snapshot import seeded, which are not listed under heights
+pub fn check_conservation(registry: &ProtocolRegistry) -> Result<ConservationReport> {
+    let tags = registry.indexed_tags();
+    let mut report = ConservationReport::default();
+    let mut found = Found::default();
+    let mut height = tables::TIP_HEIGHT.get_value::<u64>();
+    loop {
+        let txids = tables::RUNES
+            .HEIGHT_TO_TRANSACTION_IDS
+            .select_value::<u64>(height)
+            .get_list();
+        if txids.is_empty() {
+            break;
+        }
+        report.blocks += 1;
+        // indexing a height again appends its txids again
+        let mut seen = HashSet::<Arc<Vec<u8>>>::new();
+        let txids = txids.into_iter().filter(|txid| seen.insert(txid.clone()));
+        for (index, txid) in txids.enumerate() {
+            found.etched(height, index as u32);
+            let txid = Txid::from_slice(&txid)?;
+            for vout in 0.. {
+                if !found.add(OutPoint::new(txid, vout), &tags, &mut report)? {
+                    break;
+                }
+            }
+        }
+        if height == 0 {
+            break;
+        }
+        height -= 1;
+    }
+    for key in tables::SNAPSHOT_OUTPOINTS.get_list() {
+        let outpoint = consensus_decode::<OutPoint>(&mut Cursor::new(key.to_vec()))?;
+        if let Some(index) = seeded_txindex(&outpoint.txid) {
+            let height = tables::RUNES
+                .OUTPOINT_TO_HEIGHT
+                .select(&outpoint.txid.as_byte_array().to_vec())
+                .select_value::<u32>(outpoint.vout)
+                .get_value::<u64>();
+            found.etched(height, index);
+        }
+        found.add(outpoint, &tags, &mut report)?;
+    }
+    let Found {
+        runes,
+        mut protorunes,
+    } = found;
+    for tag in &tags {
+        let runtime = load_sheet(&RuneTable::for_protocol(*tag).RUNTIME_BALANCE);
+        for (rune, balance) in runtime.balances {
+            if rune.block != PROTOCOL_ETCHING_BLOCK {
+                protorunes.entry((*tag, rune)).or_default().found += balance;
+            }
+        }
+    }
+    report.runes = runes.len() as u64;
+    for (rune, holdings) in runes {
+        let Some(issued) = issued(&rune, &tags) else {
+            report.violations.extend(
+                holdings
+                    .outpoints
+                    .into_iter()
+                    .map(|outpoint| Violation::Unetched { rune, outpoint }),
+            );
+            continue;
+        };
+        if holdings.found != issued {
+            report.violations.push(Violation::Supply {
+                rune,
+                issued,
+                found: holdings.found,
+                outpoints: holdings.outpoints,
+            });
+        }
+    }
+    for ((protocol_tag, rune), holdings) in protorunes {
+        let runeid: Arc<Vec<u8>> = rune.into();
+        let burned = RuneTable::for_protocol(protocol_tag)
+            .BURNED
+            .select(&runeid)
+            .get_value::<u128>();
+        if holdings.found > burned {
+            report.violations.push(Violation::Overissued {
+                protocol_tag,
+                rune,
+                burned,
+                found: holdings.found,
+                outpoints: holdings.outpoints,
+            });
+        }
+    }
+    Ok(report)
+}
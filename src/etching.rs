@@ -0,0 +1,205 @@
+use crate::fuel::{READ_FUEL, WRITE_FUEL};
+use crate::message::MessageContextParcel;
+use crate::tables::RuneTable;
+use anyhow::{anyhow, Result};
+use bitcoin::OutPoint;
+use metashrew::index_pointer::{AtomicPointer, IndexPointer, KeyValuePointer};
+use protorune_support::balance_sheet::{BalanceSheet, ProtoruneRuneId};
+use protorune_support::rune_transfer::RuneTransfer;
+use protorune_support::utils::consensus_encode;
+use std::sync::Arc;
+
+/// Protorunes etched by a protocol are numbered under this block, which no
+/// base rune is etched at, so they never collide with burned-in runes
+pub const PROTOCOL_ETCHING_BLOCK: u128 = 0;
+
+/// A protorune issued by a protocol inside its own namespace
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct ProtoruneEtching {
+    pub name: String,
+    pub divisibility: u8,
+    pub symbol: Option<char>,
+    pub spacers: u32,
+}
+
+fn mints_for(table: &RuneTable, parcel: &MessageContextParcel) -> Result<IndexPointer> {
+    Ok(table.MINTS.select(&consensus_encode(&OutPoint::new(
+        parcel.transaction.txid(),
+        parcel.vout,
+    ))?))
+}
+
+/// Registers `etching` in the tables of the message's protocol and flags it
+/// as internal, returning the id of the new protorune
+pub fn etch(parcel: &MessageContextParcel, etching: &ProtoruneEtching) -> Result<ProtoruneRuneId> {
+    // an etching writes eight entries
+    parcel.fuel.consume(WRITE_FUEL * 8)?;
+    let atomic = parcel.atomic.derive(&IndexPointer::default());
+    let protocol_tag = parcel.protocol_tag;
+    let table = RuneTable::for_protocol(protocol_tag);
+    let name = etching.name.as_bytes().to_vec();
+    if name.is_empty() {
+        return Err(anyhow!("protorune etching has no name"));
+    }
+    if !atomic
+        .derive(&table.ETCHING_TO_RUNE_ID.select(&name))
+        .get()
+        .is_empty()
+    {
+        return Err(anyhow!(
+            "{} is already etched in protocol {}",
+            etching.name,
+            protocol_tag
+        ));
+    }
+    let mut sequence = atomic.derive(&table.SEQUENCE);
+    // sequence numbers start at 1 so no etched id equals the default id
+    let next = sequence.get_value::<u128>() + 1;
+    sequence.set_value::<u128>(next);
+    let rune = ProtoruneRuneId::new(PROTOCOL_ETCHING_BLOCK, next);
+    let runeid: Arc<Vec<u8>> = rune.into();
+    atomic
+        .derive(&table.RUNE_ID_TO_ETCHING.select(&runeid))
+        .set(Arc::new(name.clone()));
+    atomic
+        .derive(&table.ETCHING_TO_RUNE_ID.select(&name))
+        .set(runeid.clone());
+    atomic
+        .derive(&table.DIVISIBILITY.select(&name))
+        .set_value::<u8>(etching.divisibility);
+    atomic
+        .derive(&table.SPACERS.select(&name))
+        .set_value::<u32>(etching.spacers);
+    if let Some(symbol) = etching.symbol {
+        atomic
+            .derive(&table.SYMBOL.select(&name))
+            .set_value::<u32>(symbol as u32);
+    }
+    atomic
+        .derive(&table.INTERNAL_MINT.select(&runeid))
+        .set_value::<u8>(1);
+    atomic.derive(&table.ETCHINGS).append(Arc::new(name));
+    Ok(rune)
+}
+
+pub fn is_internal(atomic: &AtomicPointer, protocol_tag: u128, rune: &ProtoruneRuneId) -> bool {
+    let runeid: Arc<Vec<u8>> = (*rune).into();
+    atomic
+        .derive(
+            &RuneTable::for_protocol(protocol_tag)
+                .INTERNAL_MINT
+                .select(&runeid),
+        )
+        .get_value::<u8>()
+        == 1
+}
+
+/// Mints `amount` of a protorune the protocol etched itself. The minted amount
+/// is credited to the message before its result is reconciled, so the handler
+/// sends it on or keeps it in its runtime balance
+pub fn mint(
+    parcel: &MessageContextParcel,
+    rune: &ProtoruneRuneId,
+    amount: u128,
+) -> Result<RuneTransfer> {
+    parcel.fuel.consume(READ_FUEL + WRITE_FUEL * 2)?;
+    let atomic = parcel.atomic.derive(&IndexPointer::default());
+    let protocol_tag = parcel.protocol_tag;
+    if !is_internal(&atomic, protocol_tag, rune) {
+        return Err(anyhow!(
+            "{} was not etched by protocol {}",
+            rune,
+            protocol_tag
+        ));
+    }
+    let runeid: Arc<Vec<u8>> = (*rune).into();
+    let mints = mints_for(&RuneTable::for_protocol(protocol_tag), parcel)?;
+    let mut minted = atomic.derive(&mints.select(&runeid));
+    let previous = minted.get_value::<u128>();
+    if previous == 0 {
+        atomic.derive(&mints.keyword("/ids")).append(runeid);
+    }
+    minted.set_value::<u128>(
+        previous
+            .checked_add(amount)
+            .ok_or(anyhow!("mint of {} overflows", rune))?,
+    );
+    Ok(RuneTransfer {
+        id: *rune,
+        value: amount,
+    })
+}
+
+/// Everything the message behind `parcel` minted
+pub fn load_minted(parcel: &MessageContextParcel) -> Result<BalanceSheet> {
+    let atomic = &parcel.atomic;
+    let mints = mints_for(&RuneTable::for_protocol(parcel.protocol_tag), parcel)?;
+    let mut sheet = BalanceSheet::default();
+    for runeid in atomic.derive(&mints.keyword("/ids")).get_list() {
+        let amount = atomic.derive(&mints.select(&runeid)).get_value::<u128>();
+        sheet.increase(&runeid.into(), amount);
+    }
+    Ok(sheet)
+}
+
+/// Everything the message at `message` minted in the protocol of `table`, once
+/// its block is indexed
+pub fn minted_at(table: &RuneTable, message: &OutPoint) -> Result<BalanceSheet> {
+    let mints = table.MINTS.select(&consensus_encode(message)?);
+    let mut sheet = BalanceSheet::default();
+    for runeid in mints.keyword("/ids").get_list() {
+        let amount = mints.select(&runeid).get_value::<u128>();
+        sheet.increase(&runeid.into(), amount);
+    }
+    Ok(sheet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metashrew::clear;
+
+    fn lp_token() -> ProtoruneEtching {
+        ProtoruneEtching {
+            name: String::from("LPTOKEN"),
+            divisibility: 8,
+            symbol: Some('L'),
+            spacers: 0,
+        }
+    }
+
+    fn parcel_for(protocol_tag: u128, atomic: &AtomicPointer) -> MessageContextParcel {
+        MessageContextParcel {
+            atomic: atomic.clone(),
+            protocol_tag,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_etch_and_mint() {
+        clear();
+        let atomic = AtomicPointer::default();
+        let parcel = parcel_for(122, &atomic);
+        let rune = etch(&parcel, &lp_token()).unwrap();
+        assert_eq!(rune, ProtoruneRuneId::new(PROTOCOL_ETCHING_BLOCK, 1));
+        assert!(is_internal(&atomic, 122, &rune));
+        // the name is only taken within its own protocol
+        assert!(etch(&parcel, &lp_token()).is_err());
+        assert!(etch(&parcel_for(123, &atomic), &lp_token()).is_ok());
+
+        mint(&parcel, &rune, 400).unwrap();
+        mint(&parcel, &rune, 600).unwrap();
+        assert_eq!(load_minted(&parcel).unwrap().get(&rune), 1000);
+    }
+
+    #[test]
+    fn test_mint_requires_internal_etching() {
+        clear();
+        let atomic = AtomicPointer::default();
+        let parcel = parcel_for(122, &atomic);
+        let rune = etch(&parcel, &lp_token()).unwrap();
+        assert!(mint(&parcel_for(123, &atomic), &rune, 1).is_err());
+        assert!(mint(&parcel, &ProtoruneRuneId::new(840000, 1), 1).is_err());
+    }
+}
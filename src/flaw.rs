@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// What makes a protostone malformed, after `ordinals::Flaw`. A flawed
+/// protostone is recorded by the indexer and otherwise left unexecuted
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProtostoneFlaw {
+    EdictOutput = 1,
+    EdictRuneId = 2,
+    EdictSet = 3,
+    TrailingIntegers = 4,
+    TruncatedField = 5,
+    UnrecognizedEvenTag = 6,
+    TargetOutput = 7,
+    Predicate = 8,
+    FieldOverflow = 9,
+}
+
+impl Display for ProtostoneFlaw {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EdictOutput => write!(f, "edict output out of range"),
+            Self::EdictRuneId => write!(f, "invalid rune ID in edict"),
+            Self::EdictSet => write!(f, "edict values not in sets of four"),
+            Self::TrailingIntegers => write!(f, "trailing integers after the last protostone"),
+            Self::TruncatedField => write!(f, "field or protostone with missing values"),
+            Self::UnrecognizedEvenTag => write!(f, "unrecognized even tag"),
+            Self::TargetOutput => write!(f, "pointer, refund or unwrap output out of range"),
+            Self::Predicate => write!(f, "malformed predicate clauses"),
+            Self::FieldOverflow => write!(f, "field value too large for its type"),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::{anyhow, Result};
+use bitcoin::Transaction;
+use std::sync::{Arc, Mutex};
+
+/// fuel a message gets per byte of its transaction, unless its protocol sets a limit
+pub const FUEL_PER_BYTE: u64 = 1_000;
+pub const READ_FUEL: u64 = 100;
+pub const WRITE_FUEL: u64 = 200;
+/// charged on top of `WRITE_FUEL` for every byte written
+pub const BYTE_FUEL: u64 = 1;
+/// charged for every call into another protocol
+pub const CALL_FUEL: u64 = 1_000;
+
+#[derive(Debug)]
+struct FuelState {
+    remaining: u64,
+    exhausted: bool,
+}
+
+/// The fuel left to one message. Clones share the same tank, so every storage
+/// handle derived from a parcel draws on the message's budget
+#[derive(Clone, Debug)]
+pub struct FuelMeter {
+    state: Arc<Mutex<FuelState>>,
+}
+
+impl Default for FuelMeter {
+    /// an unmetered tank, for parcels built outside of indexing
+    fn default() -> Self {
+        FuelMeter::new(u64::MAX)
+    }
+}
+
+impl FuelMeter {
+    pub fn new(budget: u64) -> Self {
+        FuelMeter {
+            state: Arc::new(Mutex::new(FuelState {
+                remaining: budget,
+                exhausted: false,
+            })),
+        }
+    }
+    /// The budget for a message in `transaction`, `limit` being the one its
+    /// protocol configured
+    pub fn for_transaction(transaction: &Transaction, limit: Option<u64>) -> Self {
+        FuelMeter::new(
+            limit.unwrap_or_else(|| (transaction.size() as u64).saturating_mul(FUEL_PER_BYTE)),
+        )
+    }
+    /// Burns `amount`. Running dry marks the tank exhausted for good, so the
+    /// message fails even if the handler ignores the error
+    pub fn consume(&self, amount: u64) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.exhausted || state.remaining < amount {
+            state.remaining = 0;
+            state.exhausted = true;
+            return Err(anyhow!("out of fuel"));
+        }
+        state.remaining -= amount;
+        Ok(())
+    }
+    pub fn remaining(&self) -> u64 {
+        self.state.lock().unwrap().remaining
+    }
+    pub fn is_exhausted(&self) -> bool {
+        self.state.lock().unwrap().exhausted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuel_exhaustion_is_sticky() {
+        let fuel = FuelMeter::new(300);
+        let shared = fuel.clone();
+        assert!(fuel.consume(READ_FUEL).is_ok());
+        assert!(shared.consume(WRITE_FUEL).is_ok());
+        assert_eq!(fuel.remaining(), 0);
+        assert!(!fuel.is_exhausted());
+        assert!(shared.consume(1).is_err());
+        assert!(fuel.is_exhausted());
+        assert!(fuel.consume(0).is_err());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::message::MessageContext;
 use crate::protostone::ProtostoneEdict;
+use crate::registry::ProtocolRegistry;
 use crate::tables::RuneTable;
 use protorune_support::constants;
 use anyhow::{anyhow, Ok, Result};
@@ -15,26 +16,38 @@ use metashrew_support::{
 };
 use ordinals::{Artifact, Runestone};
 use ordinals::{Edict, Etching};
-use proto::protorune::{Output, RunesResponse, WalletResponse};
+use proto::protorune::{
+    ConservationResponse, Output, OutpointProofResponse, RunesResponse, SimulateResponse,
+    StateRootResponse, WalletResponse,
+};
 use protobuf::{Message, SpecialFields};
 use protorune_support::balance_sheet::{BalanceSheet, ProtoruneRuneId};
 use crate::balance_sheet::{load_sheet, PersistentRecord};
 use protorune_support::utils::consensus_encode;
 use protorune_support::utils::field_to_name;
-use protostone::{
-    add_to_indexable_protocols, initialized_protocol_index, into_protostone_edicts, Protostone,
-    Protostones,
-};
+use protostone::{into_protostone_edicts, protostone_vout, Protostone, Protostones};
 use std::collections::HashMap;
 use std::io::Cursor;
 use std::ops::Sub;
 use std::sync::Arc;
 
 pub mod balance_sheet;
+pub mod call;
+#[cfg(feature = "test_utils")]
+pub mod chain_builder;
+pub mod commitment;
+pub mod conservation;
+pub mod etching;
+pub mod flaw;
+pub mod fuel;
 pub mod message;
+pub mod predicate;
 pub mod proto;
 pub mod protoburn;
 pub mod protostone;
+pub mod registry;
+pub mod snapshot;
+pub mod storage;
 pub mod tables;
 #[cfg(feature = "test_utils")]
 pub mod test_helpers;
@@ -104,8 +117,74 @@ pub fn runesbyheight() -> i32 {
     to_ptr(&mut to_arraybuffer_layout::<&[u8]>(buffer.as_ref())) + 4
 }
 
+#[no_mangle]
+pub fn stateroot() -> i32 {
+    let mut data: Cursor<Vec<u8>> = Cursor::new(input());
+    let result: StateRootResponse =
+        view::state_root(&consume_to_end(&mut data).unwrap()).unwrap();
+    to_ptr(&mut to_arraybuffer_layout::<&[u8]>(&result.write_to_bytes().unwrap())) + 4
+}
+
+#[no_mangle]
+pub fn outpointproof() -> i32 {
+    let mut data: Cursor<Vec<u8>> = Cursor::new(input());
+    let result: OutpointProofResponse =
+        view::outpoint_proof(&consume_to_end(&mut data).unwrap()).unwrap();
+    to_ptr(&mut to_arraybuffer_layout::<&[u8]>(&result.write_to_bytes().unwrap())) + 4
+}
+
+/// Reads a `ProtocolViewRequest` from the host and answers it with the view
+/// handler registered for its protocol, behind `declare_protocol_views!`
+pub fn export_protocol_view(registry: &ProtocolRegistry) -> i32 {
+    let mut data: Cursor<Vec<u8>> = Cursor::new(input());
+    let result: Vec<u8> =
+        view::protocol_view(registry, &consume_to_end(&mut data).unwrap()).unwrap();
+    to_ptr(&mut to_arraybuffer_layout::<&[u8]>(result.as_ref())) + 4
+}
+
+/// Reads a `SimulateRequest` from the host and returns what the transaction
+/// would do, behind `declare_protocol_views!`
+pub fn export_simulate(registry: &ProtocolRegistry) -> i32 {
+    let mut data: Cursor<Vec<u8>> = Cursor::new(input());
+    let result: SimulateResponse =
+        view::simulate(registry, &consume_to_end(&mut data).unwrap()).unwrap();
+    to_ptr(&mut to_arraybuffer_layout::<&[u8]>(&result.write_to_bytes().unwrap())) + 4
+}
+
+/// Walks the index for rune and protorune conservation across the protocols in
+/// `registry`, behind `declare_protocol_views!`
+pub fn export_conservation(registry: &ProtocolRegistry) -> i32 {
+    let result: ConservationResponse = view::conservation(registry).unwrap();
+    to_ptr(&mut to_arraybuffer_layout::<&[u8]>(&result.write_to_bytes().unwrap())) + 4
+}
+
+/// Exports `protocolview`, the one entry point for the views of every protocol
+/// in the registry `$registry` evaluates to, `simulate`, which previews a
+/// transaction against those protocols, and `conservation`, which checks the
+/// index against them
+#[macro_export]
+macro_rules! declare_protocol_views {
+    ($registry:expr) => {
+        #[no_mangle]
+        pub fn protocolview() -> i32 {
+            $crate::export_protocol_view(&$registry)
+        }
+
+        #[no_mangle]
+        pub fn simulate() -> i32 {
+            $crate::export_simulate(&$registry)
+        }
+
+        #[no_mangle]
+        pub fn conservation() -> i32 {
+            $crate::export_conservation(&$registry)
+        }
+    };
+}
+
 impl Protorune {
-    pub fn index_runestone<T: MessageContext>(
+    pub fn index_runestone(
+        registry: &ProtocolRegistry,
         atomic: &mut AtomicPointer,
         tx: &Transaction,
         runestone: &Runestone,
@@ -145,7 +224,7 @@ impl Protorune {
         }
         if let Some(mint) = runestone.mint {
             if !mint.to_string().is_empty() {
-                Self::index_mint(&mint.into(), height, &mut balance_sheet)?;
+                Self::index_mint(atomic, &mint.into(), height, &mut balance_sheet)?;
             }
         }
         Self::process_edicts(
@@ -156,6 +235,20 @@ impl Protorune {
             &tx.output,
         )?;
         Self::handle_leftover_runes(&mut balance_sheet, &mut balances_by_output, unallocated_to)?;
+        // protostones may unwrap protorunes into base runes, so base balances are
+        // saved once they have run
+        Self::index_protostones(
+            registry,
+            atomic,
+            tx,
+            index,
+            block,
+            height,
+            runestone,
+            runestone_output_index,
+            &mut balances_by_output,
+            unallocated_to,
+        )?;
         for (vout, sheet) in balances_by_output.clone() {
             let outpoint = OutPoint::new(tx.txid(), vout);
             sheet.save(
@@ -167,17 +260,6 @@ impl Protorune {
                 false,
             );
         }
-        Self::index_protostones::<T>(
-            atomic,
-            tx,
-            index,
-            block,
-            height,
-            runestone,
-            runestone_output_index,
-            &mut balances_by_output,
-            unallocated_to,
-        )?;
         Ok(())
     }
     pub fn update_balances_for_edict(
@@ -210,63 +292,62 @@ impl Protorune {
         balances: &mut BalanceSheet,
         _outs: &Vec<TxOut>,
     ) -> Result<()> {
-        if edict.id.block == 0 && edict.id.tx != 0 {
-            Err(anyhow!("invalid edict"))
-        } else {
-            if (edict.output as usize) == tx.output.len() {
-                if edict.amount == 0 {
-                    let count = num_non_op_return_outputs(tx) as u128;
-                    if count != 0 {
-                        let max = balances.get(&edict.id.into());
-                        let mut spread: u128 = 0;
-                        for i in 0..tx.output.len() as u32 {
-                            if tx.output[i as usize].script_pubkey.is_op_return() {
-                                continue;
-                            }
-                            let rem: u128 = if (max % (count as u128)) - spread != 0 {
-                                1
-                            } else {
-                                0
-                            };
-                            spread = spread + rem;
-                            Self::update_balances_for_edict(
-                                balances_by_output,
-                                balances,
-                                max / count + rem,
-                                i,
-                                &edict.id.into(),
-                            )?;
+        // no base rune is etched at block 0, runestones with such edicts decipher as
+        // cenotaphs, so only protostone edicts reach here naming protorunes the
+        // protocol etched, which are numbered under PROTOCOL_ETCHING_BLOCK
+        if (edict.output as usize) == tx.output.len() {
+            if edict.amount == 0 {
+                let count = num_non_op_return_outputs(tx) as u128;
+                if count != 0 {
+                    let max = balances.get(&edict.id.into());
+                    let mut spread: u128 = 0;
+                    for i in 0..tx.output.len() as u32 {
+                        if tx.output[i as usize].script_pubkey.is_op_return() {
+                            continue;
                         }
+                        let rem: u128 = if (max % (count as u128)) - spread != 0 {
+                            1
+                        } else {
+                            0
+                        };
+                        spread = spread + rem;
+                        Self::update_balances_for_edict(
+                            balances_by_output,
+                            balances,
+                            max / count + rem,
+                            i,
+                            &edict.id.into(),
+                        )?;
                     }
-                } else {
-                    let count = num_non_op_return_outputs(tx) as u128;
-                    if count != 0 {
-                        let amount = edict.amount;
-                        for i in 0..tx.output.len() as u32 {
-                            if tx.output[i as usize].script_pubkey.is_op_return() {
-                                continue;
-                            }
-                            Self::update_balances_for_edict(
-                                balances_by_output,
-                                balances,
-                                amount,
-                                i,
-                                &edict.id.into(),
-                            )?;
+                }
+            } else {
+                let count = num_non_op_return_outputs(tx) as u128;
+                if count != 0 {
+                    let amount = edict.amount;
+                    for i in 0..tx.output.len() as u32 {
+                        if tx.output[i as usize].script_pubkey.is_op_return() {
+                            continue;
                         }
+                        Self::update_balances_for_edict(
+                            balances_by_output,
+                            balances,
+                            amount,
+                            i,
+                            &edict.id.into(),
+                        )?;
                     }
                 }
-            } else {
-                Self::update_balances_for_edict(
-                    balances_by_output,
-                    balances,
-                    edict.amount,
-                    edict.output as u32,
-                    &edict.id.into(),
-                )?;
             }
-            Ok(())
+        } else {
+            Self::update_balances_for_edict(
+                balances_by_output,
+                balances,
+                edict.amount,
+                edict.output as u32,
+                &edict.id.into(),
+            )?;
         }
+        Ok(())
     }
     pub fn process_edicts(
         tx: &Transaction,
@@ -294,31 +375,48 @@ impl Protorune {
         Ok(())
     }
     pub fn index_mint(
+        atomic: &mut AtomicPointer,
         mint: &ProtoruneRuneId,
         height: u64,
         balance_sheet: &mut BalanceSheet,
     ) -> Result<()> {
-        let name = tables::RUNES
-            .RUNE_ID_TO_ETCHING
-            .select(&mint.to_string().into_bytes())
+        let runeid = mint.to_string().into_bytes();
+        // etchings are stored by the rune's number, their terms by its name
+        let number = atomic
+            .derive(&tables::RUNES.RUNE_ID_TO_ETCHING.select(&runeid))
             .get();
-        let remaining: u128 = tables::RUNES.MINTS_REMAINING.select(&name).get_value();
-        let amount: u128 = tables::RUNES.AMOUNT.select(&name).get_value();
+        let Some(number) = String::from_utf8_lossy(&number).parse::<u128>().ok() else {
+            return Ok(());
+        };
+        let name = field_to_name(&number).into_bytes();
+        let remaining: u128 = atomic
+            .derive(&tables::RUNES.MINTS_REMAINING.select(&name))
+            .get_value();
+        let amount: u128 = atomic.derive(&tables::RUNES.AMOUNT.select(&name)).get_value();
         if remaining != 0 {
-            let height_start: u64 = tables::RUNES.HEIGHTSTART.select(&name).get_value();
-            let height_end: u64 = tables::RUNES.HEIGHTEND.select(&name).get_value();
-            let offset_start: u64 = tables::RUNES.OFFSETSTART.select(&name).get_value();
-            let offset_end: u64 = tables::RUNES.OFFSETEND.select(&name).get_value();
-            let etching_height: u64 = tables::RUNES.RUNE_ID_TO_HEIGHT.select(&name).get_value();
+            let height_start: u64 = atomic
+                .derive(&tables::RUNES.HEIGHTSTART.select(&name))
+                .get_value();
+            let height_end: u64 = atomic
+                .derive(&tables::RUNES.HEIGHTEND.select(&name))
+                .get_value();
+            let offset_start: u64 = atomic
+                .derive(&tables::RUNES.OFFSETSTART.select(&name))
+                .get_value();
+            let offset_end: u64 = atomic
+                .derive(&tables::RUNES.OFFSETEND.select(&name))
+                .get_value();
+            let etching_height: u64 = atomic
+                .derive(&tables::RUNES.RUNE_ID_TO_HEIGHT.select(&runeid))
+                .get_value();
 
             if (height_start == 0 || height >= height_start)
                 && (height_end == 0 || height < height_end)
                 && (offset_start == 0 || height >= offset_start + etching_height)
                 && (offset_end == 0 || height < etching_height + offset_end)
             {
-                tables::RUNES
-                    .MINTS_REMAINING
-                    .select(&name)
+                atomic
+                    .derive(&tables::RUNES.MINTS_REMAINING.select(&name))
                     .set_value(remaining.sub(1));
                 balance_sheet.increase(
                     &(ProtoruneRuneId {
@@ -459,6 +557,15 @@ impl Protorune {
             .into_bytes();
         return Arc::new(rune_id);
     }
+    /// Name of the base rune `rune`, as its terms are keyed, if it was etched
+    pub fn rune_name(rune: &ProtoruneRuneId) -> Option<Vec<u8>> {
+        let number = tables::RUNES
+            .RUNE_ID_TO_ETCHING
+            .select(&rune.to_string().into_bytes())
+            .get();
+        let number = String::from_utf8_lossy(&number).parse::<u128>().ok()?;
+        Some(field_to_name(&number).into_bytes())
+    }
 
     pub fn get_runestone_output_index(transaction: &Transaction) -> Result<u32> {
         // search transaction outputs for payload
@@ -477,12 +584,17 @@ impl Protorune {
         Err(anyhow!("did not find a output index"))
     }
 
-    pub fn index_unspendables<T: MessageContext>(block: &Block, height: u64) -> Result<()> {
+    pub fn index_unspendables(
+        registry: &ProtocolRegistry,
+        block: &Block,
+        height: u64,
+    ) -> Result<()> {
         for (index, tx) in block.txdata.iter().enumerate() {
             if let Some(Artifact::Runestone(ref runestone)) = Runestone::decipher(tx) {
                 let mut atomic = AtomicPointer::default();
                 let runestone_output_index: u32 = Self::get_runestone_output_index(tx)?;
-                match Self::index_runestone::<T>(
+                match Self::index_runestone(
+                    registry,
                     &mut atomic,
                     tx,
                     runestone,
@@ -502,9 +614,20 @@ impl Protorune {
         }
         Ok(())
     }
-    pub fn index_spendables(txdata: &Vec<Transaction>) -> Result<()> {
+    pub fn index_spendables(txdata: &Vec<Transaction>, height: u64) -> Result<()> {
         for (_txindex, transaction) in txdata.iter().enumerate() {
             let tx_id = transaction.txid();
+            for input in &transaction.input {
+                if !input.previous_output.is_null() {
+                    let spent = consensus_encode(&input.previous_output)?;
+                    tables::OUTPOINT_SPENT_BY
+                        .select(&spent)
+                        .set(Arc::new(tx_id.as_byte_array().to_vec()));
+                    tables::OUTPOINT_SPENT_AT
+                        .select(&spent)
+                        .set_value::<u64>(height);
+                }
+            }
 
             for (index, output) in transaction.output.iter().enumerate() {
                 let outpoint = OutPoint {
@@ -578,7 +701,7 @@ impl Protorune {
         tx: &Transaction,
         map: &HashMap<u32, BalanceSheet>,
     ) -> Result<()> {
-        for i in 0..tx.output.len() - 1 {
+        for i in 0..tx.output.len() {
             let sheet = map
                 .get(&(i as u32))
                 .map(|v| v.clone())
@@ -602,7 +725,43 @@ impl Protorune {
         Ok(())
     }
 
-    pub fn index_protostones<T: MessageContext>(
+    /// Releases the protorunes in `sheet` as base runes on `output`. Every rune is
+    /// bounded by what was burned into the protocol and has not been unwrapped
+    /// since, otherwise nothing is released
+    pub fn unwrap_protorunes(
+        atomic: &mut AtomicPointer,
+        table: &RuneTable,
+        sheet: &BalanceSheet,
+        output: u32,
+        balances_by_output: &mut HashMap<u32, BalanceSheet>,
+    ) -> Result<()> {
+        for (rune, balance) in sheet.balances.iter() {
+            let runeid: Arc<Vec<u8>> = (*rune).into();
+            let burned = atomic
+                .derive(&table.BURNED.select(&runeid))
+                .get_value::<u128>();
+            if *balance > burned {
+                return Err(anyhow!(
+                    "cannot unwrap {} of {}, only {} is burned in",
+                    balance,
+                    rune,
+                    burned
+                ));
+            }
+        }
+        for (rune, balance) in sheet.balances.iter() {
+            let runeid: Arc<Vec<u8>> = (*rune).into();
+            let mut burned = atomic.derive(&table.BURNED.select(&runeid));
+            burned.set_value::<u128>(burned.get_value::<u128>() - balance);
+            let mut unwrapped = atomic.derive(&table.UNWRAPPED.select(&runeid));
+            unwrapped.set_value::<u128>(unwrapped.get_value::<u128>() + balance);
+        }
+        sheet.pipe(balances_by_output.entry(output).or_default());
+        Ok(())
+    }
+
+    pub fn index_protostones(
+        registry: &ProtocolRegistry,
         atomic: &mut AtomicPointer,
         tx: &Transaction,
         txindex: u32,
@@ -613,24 +772,69 @@ impl Protorune {
         balances_by_output: &mut HashMap<u32, BalanceSheet>,
         unallocated_to: u32,
     ) -> Result<()> {
-        let protostones = Protostone::from_runestone(runestone)?;
+        let mut protostones = Protostone::from_runestone(runestone)?;
         if protostones.len() != 0 {
-            let mut proto_balances_by_output = HashMap::<u32, BalanceSheet>::new();
-            let table = tables::RuneTable::for_protocol(T::protocol_tag());
-            let sheets: Vec<BalanceSheet> = tx
-                .input
-                .iter()
-                .map(|input| {
-                    Ok(load_sheet(
-                        &mut atomic.derive(
-                            &table
-                                .OUTPOINT_TO_RUNES
-                                .select(&consensus_encode(&input.previous_output)?),
-                        ),
-                    ))
-                })
-                .collect::<Result<Vec<BalanceSheet>>>()?;
-            let mut balance_sheet = BalanceSheet::concat(sheets);
+            let num_outputs = tx.output.len() as u32;
+            let num_protostones = protostones.len() as u32;
+            protostones.check_outputs(num_outputs);
+            for (i, stone) in protostones.iter().enumerate() {
+                if let Some(flaw) = stone.flaw {
+                    atomic
+                        .derive(
+                            &tables::RuneTable::for_protocol(stone.protocol_tag)
+                                .FLAWS
+                                .select(&consensus_encode(&OutPoint::new(
+                                    tx.txid(),
+                                    protostone_vout(num_outputs, i as u32),
+                                ))?),
+                        )
+                        .set_value::<u8>(flaw as u8);
+                }
+            }
+            let protostones = protostones
+                .into_iter()
+                .map(|stone| stone.inert_if_flawed().at_height(registry, height))
+                .collect::<Vec<Protostone>>();
+            // protorunes held by the inputs, for every registered protocol and every
+            // protocol addressed by this transaction
+            let mut input_sheets = HashMap::<u128, BalanceSheet>::new();
+            for tag in registry
+                .tags()
+                .into_iter()
+                .chain(protostones.iter().map(|stone| stone.protocol_tag))
+            {
+                if input_sheets.contains_key(&tag) {
+                    continue;
+                }
+                let table = tables::RuneTable::for_protocol(tag);
+                let sheets: Vec<BalanceSheet> = tx
+                    .input
+                    .iter()
+                    .map(|input| {
+                        Ok(load_sheet(
+                            &mut atomic.derive(
+                                &table
+                                    .OUTPOINT_TO_RUNES
+                                    .select(&consensus_encode(&input.previous_output)?),
+                            ),
+                        ))
+                    })
+                    .collect::<Result<Vec<BalanceSheet>>>()?;
+                input_sheets.insert(tag, BalanceSheet::concat(sheets));
+            }
+            let mut proto_balances_by_output = HashMap::<u128, HashMap<u32, BalanceSheet>>::new();
+            // handlers carry on from the runtime balance earlier transactions left
+            for tag in input_sheets.keys() {
+                let runtime = load_sheet(
+                    &atomic.derive(&tables::RuneTable::for_protocol(*tag).RUNTIME_BALANCE),
+                );
+                if !runtime.balances.is_empty() {
+                    proto_balances_by_output
+                        .entry(*tag)
+                        .or_default()
+                        .insert(u32::MAX, runtime);
+                }
+            }
             protostones.process_burns(
                 &mut atomic.derive(&IndexPointer::default()),
                 runestone,
@@ -644,60 +848,99 @@ impl Protorune {
                 .into_iter()
                 .enumerate()
                 .map(|(i, stone)| {
+                    let vout = protostone_vout(num_outputs, i as u32);
+                    let balances = proto_balances_by_output
+                        .entry(stone.protocol_tag)
+                        .or_default();
                     if !stone.edicts.is_empty() {
                         Self::process_edicts(
                             tx,
                             &stone.edicts.clone().into(),
-                            &mut proto_balances_by_output,
-                            &mut balance_sheet,
+                            balances,
+                            input_sheets.entry(stone.protocol_tag).or_default(),
                             &tx.output,
                         )?;
-                        Self::handle_leftover_runes(
-                            &mut balance_sheet,
-                            &mut proto_balances_by_output.clone(),
-                            unallocated_to,
-                        )?;
-                        for (vout, sheet) in balances_by_output.clone() {
-                            let outpoint = OutPoint::new(tx.txid(), vout);
-                            sheet.save(
-                                &mut atomic.derive(
-                                    &table
-                                        .OUTPOINT_TO_RUNES
-                                        .select(&consensus_encode(&outpoint)?),
-                                ),
-                                false,
-                            );
-                        }
                     }
                     if stone.is_message() {
-                        stone.process_message::<T>(
+                        stone.process_message(
+                            registry,
                             &mut atomic.derive(&IndexPointer::default()),
                             tx,
                             txindex,
                             block,
                             height,
                             runestone_output_index,
-                            (tx.output.len() as u32) + 1 + (i as u32),
+                            vout,
+                            num_protostones,
                             &mut proto_balances_by_output,
                             unallocated_to,
                         )?;
                     }
+                    if let Some(output) = stone.unwrap {
+                        let balances = proto_balances_by_output
+                            .entry(stone.protocol_tag)
+                            .or_default();
+                        if let Some(sheet) = balances.remove(&vout) {
+                            // a failed unwrap leaves the protorunes to follow the pointer
+                            if Self::unwrap_protorunes(
+                                &mut atomic.derive(&IndexPointer::default()),
+                                &tables::RuneTable::for_protocol(stone.protocol_tag),
+                                &sheet,
+                                output,
+                                balances_by_output,
+                            )
+                            .is_err()
+                            {
+                                balances.insert(vout, sheet);
+                            }
+                        }
+                    }
+                    // anything still held by this protostone, in any protocol, moves on to
+                    // its pointer, which is either a real output or a later protostone
+                    for balances in proto_balances_by_output.values_mut() {
+                        if let Some(mut sheet) = balances.remove(&vout) {
+                            Self::handle_leftover_runes(
+                                &mut sheet,
+                                balances,
+                                stone.pointer.unwrap_or(unallocated_to),
+                            )?;
+                        }
+                    }
                     Ok(())
                 })
                 .collect::<Result<()>>()?;
-            Self::save_balances(
-                &mut atomic.derive(&IndexPointer::default()),
-                &table,
-                tx,
-                &mut proto_balances_by_output,
-            )?;
+            for (tag, mut sheet) in input_sheets {
+                Self::handle_leftover_runes(
+                    &mut sheet,
+                    proto_balances_by_output.entry(tag).or_default(),
+                    unallocated_to,
+                )?;
+            }
+            for (tag, balances) in proto_balances_by_output {
+                Self::save_balances(
+                    &mut atomic.derive(&IndexPointer::default()),
+                    &tables::RuneTable::for_protocol(tag),
+                    tx,
+                    &balances,
+                )?;
+            }
         }
         Ok(())
     }
 
     pub fn index_block<T: MessageContext>(block: Block, height: u64) -> Result<()> {
-        initialized_protocol_index().map_err(|e| anyhow!(e.to_string()))?;
-        add_to_indexable_protocols(T::protocol_tag()).map_err(|e| anyhow!(e.to_string()))?;
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<T>()?;
+        Self::index_block_with_registry(block, height, &registry)
+    }
+
+    /// Indexes a block for every subprotocol in `registry`, routing each
+    /// protostone to the handler registered for its protocol tag
+    pub fn index_block_with_registry(
+        block: Block,
+        height: u64,
+        registry: &ProtocolRegistry,
+    ) -> Result<()> {
         tables::RUNES
             .HEIGHT_TO_BLOCKHASH
             .select_value::<u64>(height)
@@ -706,10 +949,12 @@ impl Protorune {
             .BLOCKHASH_TO_HEIGHT
             .select(&consensus_encode(&block.block_hash())?)
             .set_value::<u64>(height);
-        Self::index_spendables(&block.txdata)?;
+        tables::TIP_HEIGHT.clone().set_value::<u64>(height);
+        Self::index_spendables(&block.txdata, height)?;
         Self::index_transaction_ids(&block, height)?;
         Self::index_outpoints(&block, height)?;
-        Self::index_unspendables::<T>(&block, height)?;
+        Self::index_unspendables(registry, &block, height)?;
+        commitment::commit_block(registry, &block, height)?;
         flush();
         Ok(())
     }
@@ -1,35 +1,146 @@
+use crate::balance_sheet::load_sheet;
+use crate::call::CallLedger;
+use crate::fuel::{FuelMeter, READ_FUEL};
+use crate::proto::protorune::Output;
+use crate::registry::ProtocolRegistry;
+use crate::storage::ProtocolStorage;
+use crate::tables::{self, RuneTable};
 use protorune_support::balance_sheet::BalanceSheet;
-use protorune_support::rune_transfer::RuneTransfer;
+use protorune_support::rune_transfer::{MessageResult, RuneTransfer};
 use protorune_support::utils::consensus_encode;
 use anyhow::Result;
-use bitcoin::{Block, OutPoint, Transaction};
-use metashrew::index_pointer::AtomicPointer;
+use bitcoin::{Block, OutPoint, ScriptBuf, Transaction};
+use metashrew::index_pointer::{AtomicPointer, IndexPointer, KeyValuePointer};
+use protobuf::Message;
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::u128;
 
+/// How a dispatched message ended, recorded per message in its protocol's tables
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MessageOutcome {
+    Success = 1,
+    /// the handler or its result failed, everything it was sent was refunded
+    Reverted = 2,
+    /// the message ran out of fuel and was refunded
+    OutOfFuel = 3,
+}
+
 pub trait MessageContext {
-    fn handle(parcel: &MessageContextParcel) -> Result<(Vec<RuneTransfer>, BalanceSheet)>;
+    fn handle(parcel: &MessageContextParcel) -> Result<MessageResult>;
     fn protocol_tag() -> u128;
 }
 
+/// A transaction input with its prevout looked up in the index
+#[derive(Clone, Default, Debug)]
+pub struct ResolvedInput {
+    pub outpoint: OutPoint,
+    pub script: ScriptBuf,
+    pub value: u64,
+    /// the address the prevout pays to, if its script has one
+    pub spendable_by: Option<String>,
+    /// runes held by the prevout
+    pub runes: BalanceSheet,
+    /// protorunes of the message's protocol held by the prevout
+    pub protorunes: BalanceSheet,
+}
+
+/// Resolves every input of `transaction` for a message in `protocol_tag`.
+/// Prevouts the index has not seen resolve to an empty script and no value
+pub fn resolve_inputs(
+    atomic: &AtomicPointer,
+    transaction: &Transaction,
+    protocol_tag: u128,
+) -> Result<Vec<ResolvedInput>> {
+    let table = RuneTable::for_protocol(protocol_tag);
+    transaction
+        .input
+        .iter()
+        .map(|input| {
+            let outpoint_bytes = consensus_encode(&input.previous_output)?;
+            let output = Output::parse_from_bytes(
+                &atomic
+                    .derive(&tables::OUTPOINT_TO_OUTPUT.select(&outpoint_bytes))
+                    .get(),
+            )?;
+            let spendable_by = atomic
+                .derive(&tables::OUTPOINT_SPENDABLE_BY.select(&outpoint_bytes))
+                .get();
+            Ok(ResolvedInput {
+                outpoint: input.previous_output,
+                script: ScriptBuf::from_bytes(output.script),
+                value: output.value,
+                spendable_by: if spendable_by.is_empty() {
+                    None
+                } else {
+                    Some(String::from_utf8(spendable_by.as_ref().clone())?)
+                },
+                runes: load_sheet(
+                    &atomic.derive(&tables::RUNES.OUTPOINT_TO_RUNES.select(&outpoint_bytes)),
+                ),
+                protorunes: load_sheet(
+                    &atomic.derive(&table.OUTPOINT_TO_RUNES.select(&outpoint_bytes)),
+                ),
+            })
+        })
+        .collect()
+}
+
 #[derive(Clone)]
 pub struct MessageContextParcel {
-    pub atomic: AtomicPointer,
+    /// handlers reach storage through `storage` and `read` only, so a protocol
+    /// can't write to the core tables or to another protocol's state. This was
+    /// public before, see the README for moving handlers off of it
+    pub(crate) atomic: AtomicPointer,
+    pub protocol_tag: u128,
+    /// what the message may still spend on storage, and on its own work
+    pub fuel: FuelMeter,
     pub runes: Vec<RuneTransfer>,
     pub transaction: Transaction,
+    /// the inputs of `transaction`, resolved for the message's protocol
+    pub inputs: Vec<ResolvedInput>,
     pub block: Block,
     pub height: u64,
+    /// the rule version the protocol registry assigns to `height`
+    pub version: u32,
+    /// the virtual vout of the protostone carrying the message
+    pub vout: u32,
     pub pointer: u32,
     pub refund_pointer: u32,
     pub calldata: Vec<u8>,
     pub sheets: Box<BalanceSheet>,
     pub txindex: u32,
     pub runtime_balances: Box<BalanceSheet>,
+    /// the protocols `call` can dispatch to
+    pub(crate) registry: Arc<ProtocolRegistry>,
+    /// calls made so far by this frame and the calls nested under them
+    pub(crate) calls: CallLedger,
+    /// the protocols whose handlers are waiting on this one, the message's own first
+    pub(crate) call_stack: Vec<u128>,
+    /// runtime balances of the other protocols when this frame started
+    pub(crate) runtimes: HashMap<u128, BalanceSheet>,
 }
 
 pub trait ToBytes {
     fn try_to_bytes(&self) -> Result<Vec<u8>>;
 }
 
+impl MessageContextParcel {
+    /// Storage private to the message's protocol
+    pub fn storage(&self) -> ProtocolStorage {
+        ProtocolStorage::new(&self.atomic, self.protocol_tag, self.height, &self.fuel)
+    }
+    /// Reads any key of the index, including the core rune tables and what
+    /// earlier messages in the block have written, in place of reading through
+    /// `atomic`
+    pub fn read(&self, pointer: &IndexPointer) -> Arc<Vec<u8>> {
+        match self.fuel.consume(READ_FUEL) {
+            Ok(_) => self.atomic.derive(pointer).get(),
+            Err(_) => Arc::new(vec![]),
+        }
+    }
+}
+
 impl ToBytes for OutPoint {
     fn try_to_bytes(&self) -> Result<Vec<u8>> {
         Ok(consensus_encode(self)?)
@@ -41,16 +152,25 @@ impl Default for MessageContextParcel {
         let block = bitcoin::constants::genesis_block(bitcoin::Network::Bitcoin);
         MessageContextParcel {
             atomic: AtomicPointer::default(),
+            protocol_tag: 0,
+            fuel: FuelMeter::default(),
             runes: Vec::<RuneTransfer>::default(),
             transaction: block.txdata[0].clone(),
+            inputs: vec![],
             block: block.clone(),
             height: 0,
+            version: 0,
+            vout: 0,
             pointer: 0,
             refund_pointer: 0,
             calldata: Vec::<u8>::default(),
             txindex: 0,
             runtime_balances: Box::new(BalanceSheet::default()),
             sheets: Box::new(BalanceSheet::default()),
+            registry: Arc::new(ProtocolRegistry::default()),
+            calls: CallLedger::default(),
+            call_stack: vec![],
+            runtimes: HashMap::new(),
         }
     }
 }
@@ -0,0 +1,144 @@
+use anyhow::{anyhow, Result};
+use protorune_support::balance_sheet::{BalanceSheet, ProtoruneRuneId};
+use serde::{Deserialize, Serialize};
+
+/// Which side of a protomessage a clause is checked against
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ClauseTarget {
+    /// the runes transferred into the message
+    Incoming = 0,
+    /// the runes the message handler sends on to the pointer
+    Outgoing = 1,
+}
+
+impl TryFrom<u128> for ClauseTarget {
+    type Error = anyhow::Error;
+    fn try_from(v: u128) -> Result<Self> {
+        match v {
+            0 => Ok(ClauseTarget::Incoming),
+            1 => Ok(ClauseTarget::Outgoing),
+            _ => Err(anyhow!("unknown predicate clause target {}", v)),
+        }
+    }
+}
+
+/// Requires at least `amount` of rune `id` on the `target` side of a message
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Clause {
+    pub target: ClauseTarget,
+    pub id: ProtoruneRuneId,
+    pub amount: u128,
+}
+
+#[derive(Clone, Default, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Predicate {
+    pub clauses: Vec<Clause>,
+}
+
+pub fn make_clause_set_size_error() -> anyhow::Error {
+    anyhow!("predicate values did not appear in sets of four")
+}
+
+impl Predicate {
+    /// Clauses are encoded as sets of four integers: target, block, tx, amount
+    pub fn from_integers(v: &[u128]) -> Result<Self> {
+        let mut clauses: Vec<Clause> = vec![];
+        for chunk in v.chunks(4) {
+            match chunk {
+                [target, block, tx, amount] => {
+                    clauses.push(Clause {
+                        target: (*target).try_into()?,
+                        id: ProtoruneRuneId::new(*block, *tx),
+                        amount: *amount,
+                    });
+                }
+                _ => {
+                    return Err(make_clause_set_size_error());
+                }
+            }
+        }
+        Ok(Predicate { clauses })
+    }
+    pub fn to_integers(&self) -> Vec<u128> {
+        let mut payload = Vec::<u128>::new();
+        for clause in &self.clauses {
+            payload.push(clause.target as u128);
+            payload.push(clause.id.block);
+            payload.push(clause.id.tx);
+            payload.push(clause.amount);
+        }
+        payload
+    }
+    /// Fails with the first clause for `target` that `sheet` does not satisfy
+    pub fn check(&self, target: ClauseTarget, sheet: &BalanceSheet) -> Result<()> {
+        for clause in self.clauses.iter().filter(|c| c.target == target) {
+            let balance = sheet.get(&clause.id);
+            if balance < clause.amount {
+                return Err(anyhow!(
+                    "predicate not satisfied: {} has {} but requires {}",
+                    clause.id,
+                    balance,
+                    clause.amount
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_predicate_integers_round_trip() {
+        let predicate = Predicate {
+            clauses: vec![
+                Clause {
+                    target: ClauseTarget::Incoming,
+                    id: ProtoruneRuneId::new(840000, 1),
+                    amount: 100,
+                },
+                Clause {
+                    target: ClauseTarget::Outgoing,
+                    id: ProtoruneRuneId::new(840000, 2),
+                    amount: 5,
+                },
+            ],
+        };
+        assert_eq!(
+            Predicate::from_integers(&predicate.to_integers()).unwrap(),
+            predicate
+        );
+    }
+
+    #[test]
+    fn test_predicate_malformed() {
+        assert!(Predicate::from_integers(&[0, 840000, 1]).is_err());
+        assert!(Predicate::from_integers(&[2, 840000, 1, 100]).is_err());
+    }
+
+    #[test]
+    fn test_predicate_check() {
+        let rune = ProtoruneRuneId::new(840000, 1);
+        let predicate = Predicate {
+            clauses: vec![Clause {
+                target: ClauseTarget::Outgoing,
+                id: rune,
+                amount: 400,
+            }],
+        };
+        let sheet = BalanceSheet::from_pairs(vec![rune], vec![500]);
+        assert!(predicate.check(ClauseTarget::Outgoing, &sheet).is_ok());
+        // clauses for the other side are ignored
+        assert!(predicate
+            .check(ClauseTarget::Incoming, &BalanceSheet::default())
+            .is_ok());
+        assert!(predicate
+            .check(
+                ClauseTarget::Outgoing,
+                &BalanceSheet::from_pairs(vec![rune], vec![399])
+            )
+            .is_err());
+    }
+}
@@ -1,5 +1,5 @@
-// This file is generated by rust-protobuf 3.7.1. Do not edit
-// .proto file is parsed by protoc 28.2
+// This file is generated by rust-protobuf 3.7.2. Do not edit
+// .proto file is parsed by protoc 36.2
 // @generated
 
 // https://github.com/rust-lang/rust-clippy/issues/702
@@ -22,7 +22,7 @@
 
 /// Generated files are compatible only with the same version
 /// of protobuf runtime.
-const _PROTOBUF_VERSION_CHECK: () = ::protobuf::VERSION_3_7_1;
+const _PROTOBUF_VERSION_CHECK: () = ::protobuf::VERSION_3_7_2;
 
 // @@protoc_insertion_point(message:protorune.RuneId)
 #[derive(PartialEq,Clone,Default,Debug)]
@@ -1404,6 +1404,8 @@ pub struct WalletRequest {
     // message fields
     // @@protoc_insertion_point(field:protorune.WalletRequest.wallet)
     pub wallet: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:protorune.WalletRequest.height)
+    pub height: ::std::option::Option<u64>,
     // special fields
     // @@protoc_insertion_point(special_field:protorune.WalletRequest.special_fields)
     pub special_fields: ::protobuf::SpecialFields,
@@ -1421,13 +1423,18 @@ impl WalletRequest {
     }
 
     fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
-        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut fields = ::std::vec::Vec::with_capacity(2);
         let mut oneofs = ::std::vec::Vec::with_capacity(0);
         fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
             "wallet",
             |m: &WalletRequest| { &m.wallet },
             |m: &mut WalletRequest| { &mut m.wallet },
         ));
+        fields.push(::protobuf::reflect::rt::v2::make_option_accessor::<_, _>(
+            "height",
+            |m: &WalletRequest| { &m.height },
+            |m: &mut WalletRequest| { &mut m.height },
+        ));
         ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<WalletRequest>(
             "WalletRequest",
             fields,
@@ -1449,6 +1456,9 @@ impl ::protobuf::Message for WalletRequest {
                 10 => {
                     self.wallet = is.read_bytes()?;
                 },
+                16 => {
+                    self.height = ::std::option::Option::Some(is.read_uint64()?);
+                },
                 tag => {
                     ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
                 },
@@ -1464,6 +1474,9 @@ impl ::protobuf::Message for WalletRequest {
         if !self.wallet.is_empty() {
             my_size += ::protobuf::rt::bytes_size(1, &self.wallet);
         }
+        if let Some(v) = self.height {
+            my_size += ::protobuf::rt::uint64_size(2, v);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
         self.special_fields.cached_size().set(my_size as u32);
         my_size
@@ -1473,6 +1486,9 @@ impl ::protobuf::Message for WalletRequest {
         if !self.wallet.is_empty() {
             os.write_bytes(1, &self.wallet)?;
         }
+        if let Some(v) = self.height {
+            os.write_uint64(2, v)?;
+        }
         os.write_unknown_fields(self.special_fields.unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -1491,12 +1507,14 @@ impl ::protobuf::Message for WalletRequest {
 
     fn clear(&mut self) {
         self.wallet.clear();
+        self.height = ::std::option::Option::None;
         self.special_fields.clear();
     }
 
     fn default_instance() -> &'static WalletRequest {
         static instance: WalletRequest = WalletRequest {
             wallet: ::std::vec::Vec::new(),
+            height: ::std::option::Option::None,
             special_fields: ::protobuf::SpecialFields::new(),
         };
         &instance
@@ -1670,6 +1688,8 @@ pub struct ProtorunesWalletRequest {
     pub wallet: ::std::vec::Vec<u8>,
     // @@protoc_insertion_point(field:protorune.ProtorunesWalletRequest.protocol_tag)
     pub protocol_tag: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:protorune.ProtorunesWalletRequest.height)
+    pub height: ::std::option::Option<u64>,
     // special fields
     // @@protoc_insertion_point(special_field:protorune.ProtorunesWalletRequest.special_fields)
     pub special_fields: ::protobuf::SpecialFields,
@@ -1687,7 +1707,7 @@ impl ProtorunesWalletRequest {
     }
 
     fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
-        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut fields = ::std::vec::Vec::with_capacity(3);
         let mut oneofs = ::std::vec::Vec::with_capacity(0);
         fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
             "wallet",
@@ -1699,6 +1719,11 @@ impl ProtorunesWalletRequest {
             |m: &ProtorunesWalletRequest| { &m.protocol_tag },
             |m: &mut ProtorunesWalletRequest| { &mut m.protocol_tag },
         ));
+        fields.push(::protobuf::reflect::rt::v2::make_option_accessor::<_, _>(
+            "height",
+            |m: &ProtorunesWalletRequest| { &m.height },
+            |m: &mut ProtorunesWalletRequest| { &mut m.height },
+        ));
         ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ProtorunesWalletRequest>(
             "ProtorunesWalletRequest",
             fields,
@@ -1723,6 +1748,9 @@ impl ::protobuf::Message for ProtorunesWalletRequest {
                 18 => {
                     self.protocol_tag = is.read_bytes()?;
                 },
+                24 => {
+                    self.height = ::std::option::Option::Some(is.read_uint64()?);
+                },
                 tag => {
                     ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
                 },
@@ -1741,6 +1769,9 @@ impl ::protobuf::Message for ProtorunesWalletRequest {
         if !self.protocol_tag.is_empty() {
             my_size += ::protobuf::rt::bytes_size(2, &self.protocol_tag);
         }
+        if let Some(v) = self.height {
+            my_size += ::protobuf::rt::uint64_size(3, v);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
         self.special_fields.cached_size().set(my_size as u32);
         my_size
@@ -1753,6 +1784,9 @@ impl ::protobuf::Message for ProtorunesWalletRequest {
         if !self.protocol_tag.is_empty() {
             os.write_bytes(2, &self.protocol_tag)?;
         }
+        if let Some(v) = self.height {
+            os.write_uint64(3, v)?;
+        }
         os.write_unknown_fields(self.special_fields.unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -1772,6 +1806,7 @@ impl ::protobuf::Message for ProtorunesWalletRequest {
     fn clear(&mut self) {
         self.wallet.clear();
         self.protocol_tag.clear();
+        self.height = ::std::option::Option::None;
         self.special_fields.clear();
     }
 
@@ -1779,6 +1814,7 @@ impl ::protobuf::Message for ProtorunesWalletRequest {
         static instance: ProtorunesWalletRequest = ProtorunesWalletRequest {
             wallet: ::std::vec::Vec::new(),
             protocol_tag: ::std::vec::Vec::new(),
+            height: ::std::option::Option::None,
             special_fields: ::protobuf::SpecialFields::new(),
         };
         &instance
@@ -3014,6 +3050,1808 @@ impl ::protobuf::reflect::ProtobufValue for Runtime {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
+// @@protoc_insertion_point(message:protorune.ProtocolViewRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct ProtocolViewRequest {
+    // message fields
+    // @@protoc_insertion_point(field:protorune.ProtocolViewRequest.protocol_tag)
+    pub protocol_tag: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:protorune.ProtocolViewRequest.method)
+    pub method: u32,
+    // @@protoc_insertion_point(field:protorune.ProtocolViewRequest.input)
+    pub input: ::std::vec::Vec<u8>,
+    // special fields
+    // @@protoc_insertion_point(special_field:protorune.ProtocolViewRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ProtocolViewRequest {
+    fn default() -> &'a ProtocolViewRequest {
+        <ProtocolViewRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ProtocolViewRequest {
+    pub fn new() -> ProtocolViewRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "protocol_tag",
+            |m: &ProtocolViewRequest| { &m.protocol_tag },
+            |m: &mut ProtocolViewRequest| { &mut m.protocol_tag },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "method",
+            |m: &ProtocolViewRequest| { &m.method },
+            |m: &mut ProtocolViewRequest| { &mut m.method },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "input",
+            |m: &ProtocolViewRequest| { &m.input },
+            |m: &mut ProtocolViewRequest| { &mut m.input },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ProtocolViewRequest>(
+            "ProtocolViewRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ProtocolViewRequest {
+    const NAME: &'static str = "ProtocolViewRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.protocol_tag = is.read_bytes()?;
+                },
+                16 => {
+                    self.method = is.read_uint32()?;
+                },
+                26 => {
+                    self.input = is.read_bytes()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.protocol_tag.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(1, &self.protocol_tag);
+        }
+        if self.method != 0 {
+            my_size += ::protobuf::rt::uint32_size(2, self.method);
+        }
+        if !self.input.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(3, &self.input);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.protocol_tag.is_empty() {
+            os.write_bytes(1, &self.protocol_tag)?;
+        }
+        if self.method != 0 {
+            os.write_uint32(2, self.method)?;
+        }
+        if !self.input.is_empty() {
+            os.write_bytes(3, &self.input)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ProtocolViewRequest {
+        ProtocolViewRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.protocol_tag.clear();
+        self.method = 0;
+        self.input.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ProtocolViewRequest {
+        static instance: ProtocolViewRequest = ProtocolViewRequest {
+            protocol_tag: ::std::vec::Vec::new(),
+            method: 0,
+            input: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for ProtocolViewRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ProtocolViewRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ProtocolViewRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ProtocolViewRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:protorune.SimulateRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct SimulateRequest {
+    // message fields
+    // @@protoc_insertion_point(field:protorune.SimulateRequest.transaction)
+    pub transaction: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:protorune.SimulateRequest.height)
+    pub height: ::std::option::Option<u64>,
+    // special fields
+    // @@protoc_insertion_point(special_field:protorune.SimulateRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a SimulateRequest {
+    fn default() -> &'a SimulateRequest {
+        <SimulateRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl SimulateRequest {
+    pub fn new() -> SimulateRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "transaction",
+            |m: &SimulateRequest| { &m.transaction },
+            |m: &mut SimulateRequest| { &mut m.transaction },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_option_accessor::<_, _>(
+            "height",
+            |m: &SimulateRequest| { &m.height },
+            |m: &mut SimulateRequest| { &mut m.height },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<SimulateRequest>(
+            "SimulateRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for SimulateRequest {
+    const NAME: &'static str = "SimulateRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.transaction = is.read_bytes()?;
+                },
+                16 => {
+                    self.height = ::std::option::Option::Some(is.read_uint64()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.transaction.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(1, &self.transaction);
+        }
+        if let Some(v) = self.height {
+            my_size += ::protobuf::rt::uint64_size(2, v);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.transaction.is_empty() {
+            os.write_bytes(1, &self.transaction)?;
+        }
+        if let Some(v) = self.height {
+            os.write_uint64(2, v)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> SimulateRequest {
+        SimulateRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.transaction.clear();
+        self.height = ::std::option::Option::None;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static SimulateRequest {
+        static instance: SimulateRequest = SimulateRequest {
+            transaction: ::std::vec::Vec::new(),
+            height: ::std::option::Option::None,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for SimulateRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("SimulateRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for SimulateRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for SimulateRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:protorune.ProtocolBalanceSheet)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct ProtocolBalanceSheet {
+    // message fields
+    // @@protoc_insertion_point(field:protorune.ProtocolBalanceSheet.protocol_tag)
+    pub protocol_tag: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:protorune.ProtocolBalanceSheet.balances)
+    pub balances: ::protobuf::MessageField<BalanceSheet>,
+    // special fields
+    // @@protoc_insertion_point(special_field:protorune.ProtocolBalanceSheet.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ProtocolBalanceSheet {
+    fn default() -> &'a ProtocolBalanceSheet {
+        <ProtocolBalanceSheet as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ProtocolBalanceSheet {
+    pub fn new() -> ProtocolBalanceSheet {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "protocol_tag",
+            |m: &ProtocolBalanceSheet| { &m.protocol_tag },
+            |m: &mut ProtocolBalanceSheet| { &mut m.protocol_tag },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_message_field_accessor::<_, BalanceSheet>(
+            "balances",
+            |m: &ProtocolBalanceSheet| { &m.balances },
+            |m: &mut ProtocolBalanceSheet| { &mut m.balances },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ProtocolBalanceSheet>(
+            "ProtocolBalanceSheet",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ProtocolBalanceSheet {
+    const NAME: &'static str = "ProtocolBalanceSheet";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.protocol_tag = is.read_bytes()?;
+                },
+                18 => {
+                    ::protobuf::rt::read_singular_message_into_field(is, &mut self.balances)?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.protocol_tag.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(1, &self.protocol_tag);
+        }
+        if let Some(v) = self.balances.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.protocol_tag.is_empty() {
+            os.write_bytes(1, &self.protocol_tag)?;
+        }
+        if let Some(v) = self.balances.as_ref() {
+            ::protobuf::rt::write_message_field_with_cached_size(2, v, os)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ProtocolBalanceSheet {
+        ProtocolBalanceSheet::new()
+    }
+
+    fn clear(&mut self) {
+        self.protocol_tag.clear();
+        self.balances.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ProtocolBalanceSheet {
+        static instance: ProtocolBalanceSheet = ProtocolBalanceSheet {
+            protocol_tag: ::std::vec::Vec::new(),
+            balances: ::protobuf::MessageField::none(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for ProtocolBalanceSheet {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ProtocolBalanceSheet").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ProtocolBalanceSheet {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ProtocolBalanceSheet {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:protorune.SimulatedOutput)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct SimulatedOutput {
+    // message fields
+    // @@protoc_insertion_point(field:protorune.SimulatedOutput.vout)
+    pub vout: u32,
+    // @@protoc_insertion_point(field:protorune.SimulatedOutput.runes)
+    pub runes: ::protobuf::MessageField<BalanceSheet>,
+    // @@protoc_insertion_point(field:protorune.SimulatedOutput.protorunes)
+    pub protorunes: ::std::vec::Vec<ProtocolBalanceSheet>,
+    // special fields
+    // @@protoc_insertion_point(special_field:protorune.SimulatedOutput.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a SimulatedOutput {
+    fn default() -> &'a SimulatedOutput {
+        <SimulatedOutput as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl SimulatedOutput {
+    pub fn new() -> SimulatedOutput {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "vout",
+            |m: &SimulatedOutput| { &m.vout },
+            |m: &mut SimulatedOutput| { &mut m.vout },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_message_field_accessor::<_, BalanceSheet>(
+            "runes",
+            |m: &SimulatedOutput| { &m.runes },
+            |m: &mut SimulatedOutput| { &mut m.runes },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "protorunes",
+            |m: &SimulatedOutput| { &m.protorunes },
+            |m: &mut SimulatedOutput| { &mut m.protorunes },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<SimulatedOutput>(
+            "SimulatedOutput",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for SimulatedOutput {
+    const NAME: &'static str = "SimulatedOutput";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.vout = is.read_uint32()?;
+                },
+                18 => {
+                    ::protobuf::rt::read_singular_message_into_field(is, &mut self.runes)?;
+                },
+                26 => {
+                    self.protorunes.push(is.read_message()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.vout != 0 {
+            my_size += ::protobuf::rt::uint32_size(1, self.vout);
+        }
+        if let Some(v) = self.runes.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        }
+        for value in &self.protorunes {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.vout != 0 {
+            os.write_uint32(1, self.vout)?;
+        }
+        if let Some(v) = self.runes.as_ref() {
+            ::protobuf::rt::write_message_field_with_cached_size(2, v, os)?;
+        }
+        for v in &self.protorunes {
+            ::protobuf::rt::write_message_field_with_cached_size(3, v, os)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> SimulatedOutput {
+        SimulatedOutput::new()
+    }
+
+    fn clear(&mut self) {
+        self.vout = 0;
+        self.runes.clear();
+        self.protorunes.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static SimulatedOutput {
+        static instance: SimulatedOutput = SimulatedOutput {
+            vout: 0,
+            runes: ::protobuf::MessageField::none(),
+            protorunes: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for SimulatedOutput {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("SimulatedOutput").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for SimulatedOutput {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for SimulatedOutput {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:protorune.SimulatedMessage)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct SimulatedMessage {
+    // message fields
+    // @@protoc_insertion_point(field:protorune.SimulatedMessage.protocol_tag)
+    pub protocol_tag: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:protorune.SimulatedMessage.vout)
+    pub vout: u32,
+    // @@protoc_insertion_point(field:protorune.SimulatedMessage.outcome)
+    pub outcome: u32,
+    // @@protoc_insertion_point(field:protorune.SimulatedMessage.events)
+    pub events: ::std::vec::Vec<::std::vec::Vec<u8>>,
+    // special fields
+    // @@protoc_insertion_point(special_field:protorune.SimulatedMessage.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a SimulatedMessage {
+    fn default() -> &'a SimulatedMessage {
+        <SimulatedMessage as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl SimulatedMessage {
+    pub fn new() -> SimulatedMessage {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(4);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "protocol_tag",
+            |m: &SimulatedMessage| { &m.protocol_tag },
+            |m: &mut SimulatedMessage| { &mut m.protocol_tag },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "vout",
+            |m: &SimulatedMessage| { &m.vout },
+            |m: &mut SimulatedMessage| { &mut m.vout },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "outcome",
+            |m: &SimulatedMessage| { &m.outcome },
+            |m: &mut SimulatedMessage| { &mut m.outcome },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "events",
+            |m: &SimulatedMessage| { &m.events },
+            |m: &mut SimulatedMessage| { &mut m.events },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<SimulatedMessage>(
+            "SimulatedMessage",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for SimulatedMessage {
+    const NAME: &'static str = "SimulatedMessage";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.protocol_tag = is.read_bytes()?;
+                },
+                16 => {
+                    self.vout = is.read_uint32()?;
+                },
+                24 => {
+                    self.outcome = is.read_uint32()?;
+                },
+                34 => {
+                    self.events.push(is.read_bytes()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.protocol_tag.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(1, &self.protocol_tag);
+        }
+        if self.vout != 0 {
+            my_size += ::protobuf::rt::uint32_size(2, self.vout);
+        }
+        if self.outcome != 0 {
+            my_size += ::protobuf::rt::uint32_size(3, self.outcome);
+        }
+        for value in &self.events {
+            my_size += ::protobuf::rt::bytes_size(4, &value);
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.protocol_tag.is_empty() {
+            os.write_bytes(1, &self.protocol_tag)?;
+        }
+        if self.vout != 0 {
+            os.write_uint32(2, self.vout)?;
+        }
+        if self.outcome != 0 {
+            os.write_uint32(3, self.outcome)?;
+        }
+        for v in &self.events {
+            os.write_bytes(4, &v)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> SimulatedMessage {
+        SimulatedMessage::new()
+    }
+
+    fn clear(&mut self) {
+        self.protocol_tag.clear();
+        self.vout = 0;
+        self.outcome = 0;
+        self.events.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static SimulatedMessage {
+        static instance: SimulatedMessage = SimulatedMessage {
+            protocol_tag: ::std::vec::Vec::new(),
+            vout: 0,
+            outcome: 0,
+            events: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for SimulatedMessage {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("SimulatedMessage").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for SimulatedMessage {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for SimulatedMessage {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:protorune.SimulateResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct SimulateResponse {
+    // message fields
+    // @@protoc_insertion_point(field:protorune.SimulateResponse.outputs)
+    pub outputs: ::std::vec::Vec<SimulatedOutput>,
+    // @@protoc_insertion_point(field:protorune.SimulateResponse.burns)
+    pub burns: ::std::vec::Vec<ProtocolBalanceSheet>,
+    // @@protoc_insertion_point(field:protorune.SimulateResponse.messages)
+    pub messages: ::std::vec::Vec<SimulatedMessage>,
+    // @@protoc_insertion_point(field:protorune.SimulateResponse.error)
+    pub error: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:protorune.SimulateResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a SimulateResponse {
+    fn default() -> &'a SimulateResponse {
+        <SimulateResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl SimulateResponse {
+    pub fn new() -> SimulateResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(4);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "outputs",
+            |m: &SimulateResponse| { &m.outputs },
+            |m: &mut SimulateResponse| { &mut m.outputs },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "burns",
+            |m: &SimulateResponse| { &m.burns },
+            |m: &mut SimulateResponse| { &mut m.burns },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "messages",
+            |m: &SimulateResponse| { &m.messages },
+            |m: &mut SimulateResponse| { &mut m.messages },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "error",
+            |m: &SimulateResponse| { &m.error },
+            |m: &mut SimulateResponse| { &mut m.error },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<SimulateResponse>(
+            "SimulateResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for SimulateResponse {
+    const NAME: &'static str = "SimulateResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.outputs.push(is.read_message()?);
+                },
+                18 => {
+                    self.burns.push(is.read_message()?);
+                },
+                26 => {
+                    self.messages.push(is.read_message()?);
+                },
+                34 => {
+                    self.error = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        for value in &self.outputs {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        for value in &self.burns {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        for value in &self.messages {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        if !self.error.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.error);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        for v in &self.outputs {
+            ::protobuf::rt::write_message_field_with_cached_size(1, v, os)?;
+        };
+        for v in &self.burns {
+            ::protobuf::rt::write_message_field_with_cached_size(2, v, os)?;
+        };
+        for v in &self.messages {
+            ::protobuf::rt::write_message_field_with_cached_size(3, v, os)?;
+        };
+        if !self.error.is_empty() {
+            os.write_string(4, &self.error)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> SimulateResponse {
+        SimulateResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.outputs.clear();
+        self.burns.clear();
+        self.messages.clear();
+        self.error.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static SimulateResponse {
+        static instance: SimulateResponse = SimulateResponse {
+            outputs: ::std::vec::Vec::new(),
+            burns: ::std::vec::Vec::new(),
+            messages: ::std::vec::Vec::new(),
+            error: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for SimulateResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("SimulateResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for SimulateResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for SimulateResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:protorune.ConservationViolation)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct ConservationViolation {
+    // message fields
+    // @@protoc_insertion_point(field:protorune.ConservationViolation.description)
+    pub description: ::std::string::String,
+    // @@protoc_insertion_point(field:protorune.ConservationViolation.protocol_tag)
+    pub protocol_tag: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:protorune.ConservationViolation.rune)
+    pub rune: ::protobuf::MessageField<RuneId>,
+    // @@protoc_insertion_point(field:protorune.ConservationViolation.outpoints)
+    pub outpoints: ::std::vec::Vec<Outpoint>,
+    // special fields
+    // @@protoc_insertion_point(special_field:protorune.ConservationViolation.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ConservationViolation {
+    fn default() -> &'a ConservationViolation {
+        <ConservationViolation as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ConservationViolation {
+    pub fn new() -> ConservationViolation {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(4);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "description",
+            |m: &ConservationViolation| { &m.description },
+            |m: &mut ConservationViolation| { &mut m.description },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "protocol_tag",
+            |m: &ConservationViolation| { &m.protocol_tag },
+            |m: &mut ConservationViolation| { &mut m.protocol_tag },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_message_field_accessor::<_, RuneId>(
+            "rune",
+            |m: &ConservationViolation| { &m.rune },
+            |m: &mut ConservationViolation| { &mut m.rune },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "outpoints",
+            |m: &ConservationViolation| { &m.outpoints },
+            |m: &mut ConservationViolation| { &mut m.outpoints },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ConservationViolation>(
+            "ConservationViolation",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ConservationViolation {
+    const NAME: &'static str = "ConservationViolation";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.description = is.read_string()?;
+                },
+                18 => {
+                    self.protocol_tag = is.read_bytes()?;
+                },
+                26 => {
+                    ::protobuf::rt::read_singular_message_into_field(is, &mut self.rune)?;
+                },
+                34 => {
+                    self.outpoints.push(is.read_message()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.description.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.description);
+        }
+        if !self.protocol_tag.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(2, &self.protocol_tag);
+        }
+        if let Some(v) = self.rune.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        }
+        for value in &self.outpoints {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.description.is_empty() {
+            os.write_string(1, &self.description)?;
+        }
+        if !self.protocol_tag.is_empty() {
+            os.write_bytes(2, &self.protocol_tag)?;
+        }
+        if let Some(v) = self.rune.as_ref() {
+            ::protobuf::rt::write_message_field_with_cached_size(3, v, os)?;
+        }
+        for v in &self.outpoints {
+            ::protobuf::rt::write_message_field_with_cached_size(4, v, os)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ConservationViolation {
+        ConservationViolation::new()
+    }
+
+    fn clear(&mut self) {
+        self.description.clear();
+        self.protocol_tag.clear();
+        self.rune.clear();
+        self.outpoints.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ConservationViolation {
+        static instance: ConservationViolation = ConservationViolation {
+            description: ::std::string::String::new(),
+            protocol_tag: ::std::vec::Vec::new(),
+            rune: ::protobuf::MessageField::none(),
+            outpoints: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for ConservationViolation {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ConservationViolation").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ConservationViolation {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ConservationViolation {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:protorune.ConservationResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct ConservationResponse {
+    // message fields
+    // @@protoc_insertion_point(field:protorune.ConservationResponse.blocks)
+    pub blocks: u64,
+    // @@protoc_insertion_point(field:protorune.ConservationResponse.outpoints)
+    pub outpoints: u64,
+    // @@protoc_insertion_point(field:protorune.ConservationResponse.runes)
+    pub runes: u64,
+    // @@protoc_insertion_point(field:protorune.ConservationResponse.violations)
+    pub violations: ::std::vec::Vec<ConservationViolation>,
+    // special fields
+    // @@protoc_insertion_point(special_field:protorune.ConservationResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ConservationResponse {
+    fn default() -> &'a ConservationResponse {
+        <ConservationResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ConservationResponse {
+    pub fn new() -> ConservationResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(4);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "blocks",
+            |m: &ConservationResponse| { &m.blocks },
+            |m: &mut ConservationResponse| { &mut m.blocks },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "outpoints",
+            |m: &ConservationResponse| { &m.outpoints },
+            |m: &mut ConservationResponse| { &mut m.outpoints },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "runes",
+            |m: &ConservationResponse| { &m.runes },
+            |m: &mut ConservationResponse| { &mut m.runes },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "violations",
+            |m: &ConservationResponse| { &m.violations },
+            |m: &mut ConservationResponse| { &mut m.violations },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ConservationResponse>(
+            "ConservationResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ConservationResponse {
+    const NAME: &'static str = "ConservationResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.blocks = is.read_uint64()?;
+                },
+                16 => {
+                    self.outpoints = is.read_uint64()?;
+                },
+                24 => {
+                    self.runes = is.read_uint64()?;
+                },
+                34 => {
+                    self.violations.push(is.read_message()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.blocks != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.blocks);
+        }
+        if self.outpoints != 0 {
+            my_size += ::protobuf::rt::uint64_size(2, self.outpoints);
+        }
+        if self.runes != 0 {
+            my_size += ::protobuf::rt::uint64_size(3, self.runes);
+        }
+        for value in &self.violations {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.blocks != 0 {
+            os.write_uint64(1, self.blocks)?;
+        }
+        if self.outpoints != 0 {
+            os.write_uint64(2, self.outpoints)?;
+        }
+        if self.runes != 0 {
+            os.write_uint64(3, self.runes)?;
+        }
+        for v in &self.violations {
+            ::protobuf::rt::write_message_field_with_cached_size(4, v, os)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ConservationResponse {
+        ConservationResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.blocks = 0;
+        self.outpoints = 0;
+        self.runes = 0;
+        self.violations.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ConservationResponse {
+        static instance: ConservationResponse = ConservationResponse {
+            blocks: 0,
+            outpoints: 0,
+            runes: 0,
+            violations: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for ConservationResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ConservationResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ConservationResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ConservationResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:protorune.StateRootRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct StateRootRequest {
+    // message fields
+    // @@protoc_insertion_point(field:protorune.StateRootRequest.height)
+    pub height: ::std::option::Option<u64>,
+    // special fields
+    // @@protoc_insertion_point(special_field:protorune.StateRootRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a StateRootRequest {
+    fn default() -> &'a StateRootRequest {
+        <StateRootRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl StateRootRequest {
+    pub fn new() -> StateRootRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_option_accessor::<_, _>(
+            "height",
+            |m: &StateRootRequest| { &m.height },
+            |m: &mut StateRootRequest| { &mut m.height },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<StateRootRequest>(
+            "StateRootRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for StateRootRequest {
+    const NAME: &'static str = "StateRootRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.height = ::std::option::Option::Some(is.read_uint64()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if let Some(v) = self.height {
+            my_size += ::protobuf::rt::uint64_size(1, v);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if let Some(v) = self.height {
+            os.write_uint64(1, v)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> StateRootRequest {
+        StateRootRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.height = ::std::option::Option::None;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static StateRootRequest {
+        static instance: StateRootRequest = StateRootRequest {
+            height: ::std::option::Option::None,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for StateRootRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("StateRootRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for StateRootRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for StateRootRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:protorune.StateRootResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct StateRootResponse {
+    // message fields
+    // @@protoc_insertion_point(field:protorune.StateRootResponse.height)
+    pub height: u64,
+    // @@protoc_insertion_point(field:protorune.StateRootResponse.root)
+    pub root: ::std::vec::Vec<u8>,
+    // special fields
+    // @@protoc_insertion_point(special_field:protorune.StateRootResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a StateRootResponse {
+    fn default() -> &'a StateRootResponse {
+        <StateRootResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl StateRootResponse {
+    pub fn new() -> StateRootResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "height",
+            |m: &StateRootResponse| { &m.height },
+            |m: &mut StateRootResponse| { &mut m.height },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "root",
+            |m: &StateRootResponse| { &m.root },
+            |m: &mut StateRootResponse| { &mut m.root },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<StateRootResponse>(
+            "StateRootResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for StateRootResponse {
+    const NAME: &'static str = "StateRootResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.height = is.read_uint64()?;
+                },
+                18 => {
+                    self.root = is.read_bytes()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.height != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.height);
+        }
+        if !self.root.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(2, &self.root);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.height != 0 {
+            os.write_uint64(1, self.height)?;
+        }
+        if !self.root.is_empty() {
+            os.write_bytes(2, &self.root)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> StateRootResponse {
+        StateRootResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.height = 0;
+        self.root.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static StateRootResponse {
+        static instance: StateRootResponse = StateRootResponse {
+            height: 0,
+            root: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for StateRootResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("StateRootResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for StateRootResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for StateRootResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:protorune.OutpointProofResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct OutpointProofResponse {
+    // message fields
+    // @@protoc_insertion_point(field:protorune.OutpointProofResponse.balances)
+    pub balances: ::protobuf::MessageField<BalanceSheet>,
+    // @@protoc_insertion_point(field:protorune.OutpointProofResponse.height)
+    pub height: u64,
+    // @@protoc_insertion_point(field:protorune.OutpointProofResponse.previous_root)
+    pub previous_root: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:protorune.OutpointProofResponse.root)
+    pub root: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:protorune.OutpointProofResponse.index)
+    pub index: u64,
+    // @@protoc_insertion_point(field:protorune.OutpointProofResponse.leaves)
+    pub leaves: u64,
+    // @@protoc_insertion_point(field:protorune.OutpointProofResponse.siblings)
+    pub siblings: ::std::vec::Vec<::std::vec::Vec<u8>>,
+    // special fields
+    // @@protoc_insertion_point(special_field:protorune.OutpointProofResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a OutpointProofResponse {
+    fn default() -> &'a OutpointProofResponse {
+        <OutpointProofResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl OutpointProofResponse {
+    pub fn new() -> OutpointProofResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(7);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_message_field_accessor::<_, BalanceSheet>(
+            "balances",
+            |m: &OutpointProofResponse| { &m.balances },
+            |m: &mut OutpointProofResponse| { &mut m.balances },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "height",
+            |m: &OutpointProofResponse| { &m.height },
+            |m: &mut OutpointProofResponse| { &mut m.height },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "previous_root",
+            |m: &OutpointProofResponse| { &m.previous_root },
+            |m: &mut OutpointProofResponse| { &mut m.previous_root },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "root",
+            |m: &OutpointProofResponse| { &m.root },
+            |m: &mut OutpointProofResponse| { &mut m.root },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "index",
+            |m: &OutpointProofResponse| { &m.index },
+            |m: &mut OutpointProofResponse| { &mut m.index },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "leaves",
+            |m: &OutpointProofResponse| { &m.leaves },
+            |m: &mut OutpointProofResponse| { &mut m.leaves },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "siblings",
+            |m: &OutpointProofResponse| { &m.siblings },
+            |m: &mut OutpointProofResponse| { &mut m.siblings },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<OutpointProofResponse>(
+            "OutpointProofResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for OutpointProofResponse {
+    const NAME: &'static str = "OutpointProofResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    ::protobuf::rt::read_singular_message_into_field(is, &mut self.balances)?;
+                },
+                16 => {
+                    self.height = is.read_uint64()?;
+                },
+                26 => {
+                    self.previous_root = is.read_bytes()?;
+                },
+                34 => {
+                    self.root = is.read_bytes()?;
+                },
+                40 => {
+                    self.index = is.read_uint64()?;
+                },
+                48 => {
+                    self.leaves = is.read_uint64()?;
+                },
+                58 => {
+                    self.siblings.push(is.read_bytes()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if let Some(v) = self.balances.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        }
+        if self.height != 0 {
+            my_size += ::protobuf::rt::uint64_size(2, self.height);
+        }
+        if !self.previous_root.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(3, &self.previous_root);
+        }
+        if !self.root.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(4, &self.root);
+        }
+        if self.index != 0 {
+            my_size += ::protobuf::rt::uint64_size(5, self.index);
+        }
+        if self.leaves != 0 {
+            my_size += ::protobuf::rt::uint64_size(6, self.leaves);
+        }
+        for value in &self.siblings {
+            my_size += ::protobuf::rt::bytes_size(7, &value);
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if let Some(v) = self.balances.as_ref() {
+            ::protobuf::rt::write_message_field_with_cached_size(1, v, os)?;
+        }
+        if self.height != 0 {
+            os.write_uint64(2, self.height)?;
+        }
+        if !self.previous_root.is_empty() {
+            os.write_bytes(3, &self.previous_root)?;
+        }
+        if !self.root.is_empty() {
+            os.write_bytes(4, &self.root)?;
+        }
+        if self.index != 0 {
+            os.write_uint64(5, self.index)?;
+        }
+        if self.leaves != 0 {
+            os.write_uint64(6, self.leaves)?;
+        }
+        for v in &self.siblings {
+            os.write_bytes(7, &v)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> OutpointProofResponse {
+        OutpointProofResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.balances.clear();
+        self.height = 0;
+        self.previous_root.clear();
+        self.root.clear();
+        self.index = 0;
+        self.leaves = 0;
+        self.siblings.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static OutpointProofResponse {
+        static instance: OutpointProofResponse = OutpointProofResponse {
+            balances: ::protobuf::MessageField::none(),
+            height: 0,
+            previous_root: ::std::vec::Vec::new(),
+            root: ::std::vec::Vec::new(),
+            index: 0,
+            leaves: 0,
+            siblings: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for OutpointProofResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("OutpointProofResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for OutpointProofResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for OutpointProofResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
 static file_descriptor_proto_data: &'static [u8] = b"\
     \n\x0fprotorune.proto\x12\tprotorune\":\n\x06RuneId\x12\x16\n\x06height\
     \x18\x01\x20\x01(\rR\x06height\x12\x18\n\x07txindex\x18\x02\x20\x01(\rR\
@@ -3037,28 +4875,67 @@ static file_descriptor_proto_data: &'static [u8] = b"\
     utputR\x06output\x12\x16\n\x06height\x18\x04\x20\x01(\rR\x06height\x12\
     \x18\n\x07txindex\x18\x05\x20\x01(\rR\x07txindex\"9\n\x0fPaginationInput\
     \x12\x14\n\x05start\x18\x01\x20\x01(\rR\x05start\x12\x10\n\x03end\x18\
-    \x02\x20\x01(\rR\x03end\"'\n\rWalletRequest\x12\x16\n\x06wallet\x18\x01\
-    \x20\x01(\x0cR\x06wallet\"\x80\x01\n\x0eWalletResponse\x129\n\toutpoints\
-    \x18\x01\x20\x03(\x0b2\x1b.protorune.OutpointResponseR\toutpoints\x123\n\
-    \x08balances\x18\x02\x20\x01(\x0b2\x17.protorune.BalanceSheetR\x08balanc\
-    es\"T\n\x17ProtorunesWalletRequest\x12\x16\n\x06wallet\x18\x01\x20\x01(\
-    \x0cR\x06wallet\x12!\n\x0cprotocol_tag\x18\x02\x20\x01(\x0cR\x0bprotocol\
-    Tag\".\n\x14RunesByHeightRequest\x12\x16\n\x06height\x18\x01\x20\x01(\
-    \x04R\x06height\"6\n\rRunesResponse\x12%\n\x05runes\x18\x01\x20\x03(\x0b\
-    2\x0f.protorune.RuneR\x05runes\"H\n\tProtoBurn\x12!\n\x0cprotocol_tag\
-    \x18\x01\x20\x01(\x0cR\x0bprotocolTag\x12\x18\n\x07pointer\x18\x02\x20\
-    \x01(\rR\x07pointer\")\n\x07uint128\x12\x0e\n\x02lo\x18\x01\x20\x01(\x04\
-    R\x02lo\x12\x0e\n\x02hi\x18\x02\x20\x01(\x04R\x02hi\"[\n\x06Clause\x12%\
-    \n\x04rune\x18\x01\x20\x01(\x0b2\x11.protorune.RuneIdR\x04rune\x12*\n\
-    \x06amount\x18\x02\x20\x01(\x0b2\x12.protorune.uint128R\x06amount\"8\n\t\
-    Predicate\x12+\n\x07clauses\x18\x01\x20\x03(\x0b2\x11.protorune.ClauseR\
-    \x07clauses\"\x9f\x01\n\x0cProtoMessage\x12\x1a\n\x08calldata\x18\x01\
-    \x20\x01(\x0cR\x08calldata\x122\n\tpredicate\x18\x02\x20\x01(\x0b2\x14.p\
-    rotorune.PredicateR\tpredicate\x12\x18\n\x07pointer\x18\x03\x20\x01(\rR\
-    \x07pointer\x12%\n\x0erefund_pointer\x18\x04\x20\x01(\rR\rrefundPointer\
-    \"1\n\x0cRuntimeInput\x12!\n\x0cprotocol_tag\x18\x01\x20\x01(\x0cR\x0bpr\
-    otocolTag\">\n\x07Runtime\x123\n\x08balances\x18\x01\x20\x01(\x0b2\x17.p\
-    rotorune.BalanceSheetR\x08balancesb\x06proto3\
+    \x02\x20\x01(\rR\x03end\"O\n\rWalletRequest\x12\x16\n\x06wallet\x18\x01\
+    \x20\x01(\x0cR\x06wallet\x12\x1b\n\x06height\x18\x02\x20\x01(\x04H\0R\
+    \x06height\x88\x01\x01B\t\n\x07_height\"\x80\x01\n\x0eWalletResponse\x12\
+    9\n\toutpoints\x18\x01\x20\x03(\x0b2\x1b.protorune.OutpointResponseR\tou\
+    tpoints\x123\n\x08balances\x18\x02\x20\x01(\x0b2\x17.protorune.BalanceSh\
+    eetR\x08balances\"|\n\x17ProtorunesWalletRequest\x12\x16\n\x06wallet\x18\
+    \x01\x20\x01(\x0cR\x06wallet\x12!\n\x0cprotocol_tag\x18\x02\x20\x01(\x0c\
+    R\x0bprotocolTag\x12\x1b\n\x06height\x18\x03\x20\x01(\x04H\0R\x06height\
+    \x88\x01\x01B\t\n\x07_height\".\n\x14RunesByHeightRequest\x12\x16\n\x06h\
+    eight\x18\x01\x20\x01(\x04R\x06height\"6\n\rRunesResponse\x12%\n\x05rune\
+    s\x18\x01\x20\x03(\x0b2\x0f.protorune.RuneR\x05runes\"H\n\tProtoBurn\x12\
+    !\n\x0cprotocol_tag\x18\x01\x20\x01(\x0cR\x0bprotocolTag\x12\x18\n\x07po\
+    inter\x18\x02\x20\x01(\rR\x07pointer\")\n\x07uint128\x12\x0e\n\x02lo\x18\
+    \x01\x20\x01(\x04R\x02lo\x12\x0e\n\x02hi\x18\x02\x20\x01(\x04R\x02hi\"[\
+    \n\x06Clause\x12%\n\x04rune\x18\x01\x20\x01(\x0b2\x11.protorune.RuneIdR\
+    \x04rune\x12*\n\x06amount\x18\x02\x20\x01(\x0b2\x12.protorune.uint128R\
+    \x06amount\"8\n\tPredicate\x12+\n\x07clauses\x18\x01\x20\x03(\x0b2\x11.p\
+    rotorune.ClauseR\x07clauses\"\x9f\x01\n\x0cProtoMessage\x12\x1a\n\x08cal\
+    ldata\x18\x01\x20\x01(\x0cR\x08calldata\x122\n\tpredicate\x18\x02\x20\
+    \x01(\x0b2\x14.protorune.PredicateR\tpredicate\x12\x18\n\x07pointer\x18\
+    \x03\x20\x01(\rR\x07pointer\x12%\n\x0erefund_pointer\x18\x04\x20\x01(\rR\
+    \rrefundPointer\"1\n\x0cRuntimeInput\x12!\n\x0cprotocol_tag\x18\x01\x20\
+    \x01(\x0cR\x0bprotocolTag\">\n\x07Runtime\x123\n\x08balances\x18\x01\x20\
+    \x01(\x0b2\x17.protorune.BalanceSheetR\x08balances\"f\n\x13ProtocolViewR\
+    equest\x12!\n\x0cprotocol_tag\x18\x01\x20\x01(\x0cR\x0bprotocolTag\x12\
+    \x16\n\x06method\x18\x02\x20\x01(\rR\x06method\x12\x14\n\x05input\x18\
+    \x03\x20\x01(\x0cR\x05input\"[\n\x0fSimulateRequest\x12\x20\n\x0btransac\
+    tion\x18\x01\x20\x01(\x0cR\x0btransaction\x12\x1b\n\x06height\x18\x02\
+    \x20\x01(\x04H\0R\x06height\x88\x01\x01B\t\n\x07_height\"n\n\x14Protocol\
+    BalanceSheet\x12!\n\x0cprotocol_tag\x18\x01\x20\x01(\x0cR\x0bprotocolTag\
+    \x123\n\x08balances\x18\x02\x20\x01(\x0b2\x17.protorune.BalanceSheetR\
+    \x08balances\"\x95\x01\n\x0fSimulatedOutput\x12\x12\n\x04vout\x18\x01\
+    \x20\x01(\rR\x04vout\x12-\n\x05runes\x18\x02\x20\x01(\x0b2\x17.protorune\
+    .BalanceSheetR\x05runes\x12?\n\nprotorunes\x18\x03\x20\x03(\x0b2\x1f.pro\
+    torune.ProtocolBalanceSheetR\nprotorunes\"{\n\x10SimulatedMessage\x12!\n\
+    \x0cprotocol_tag\x18\x01\x20\x01(\x0cR\x0bprotocolTag\x12\x12\n\x04vout\
+    \x18\x02\x20\x01(\rR\x04vout\x12\x18\n\x07outcome\x18\x03\x20\x01(\rR\
+    \x07outcome\x12\x16\n\x06events\x18\x04\x20\x03(\x0cR\x06events\"\xce\
+    \x01\n\x10SimulateResponse\x124\n\x07outputs\x18\x01\x20\x03(\x0b2\x1a.p\
+    rotorune.SimulatedOutputR\x07outputs\x125\n\x05burns\x18\x02\x20\x03(\
+    \x0b2\x1f.protorune.ProtocolBalanceSheetR\x05burns\x127\n\x08messages\
+    \x18\x03\x20\x03(\x0b2\x1b.protorune.SimulatedMessageR\x08messages\x12\
+    \x14\n\x05error\x18\x04\x20\x01(\tR\x05error\"\xb6\x01\n\x15Conservation\
+    Violation\x12\x20\n\x0bdescription\x18\x01\x20\x01(\tR\x0bdescription\
+    \x12!\n\x0cprotocol_tag\x18\x02\x20\x01(\x0cR\x0bprotocolTag\x12%\n\x04r\
+    une\x18\x03\x20\x01(\x0b2\x11.protorune.RuneIdR\x04rune\x121\n\toutpoint\
+    s\x18\x04\x20\x03(\x0b2\x13.protorune.OutpointR\toutpoints\"\xa4\x01\n\
+    \x14ConservationResponse\x12\x16\n\x06blocks\x18\x01\x20\x01(\x04R\x06bl\
+    ocks\x12\x1c\n\toutpoints\x18\x02\x20\x01(\x04R\toutpoints\x12\x14\n\x05\
+    runes\x18\x03\x20\x01(\x04R\x05runes\x12@\n\nviolations\x18\x04\x20\x03(\
+    \x0b2\x20.protorune.ConservationViolationR\nviolations\":\n\x10StateRoot\
+    Request\x12\x1b\n\x06height\x18\x01\x20\x01(\x04H\0R\x06height\x88\x01\
+    \x01B\t\n\x07_height\"?\n\x11StateRootResponse\x12\x16\n\x06height\x18\
+    \x01\x20\x01(\x04R\x06height\x12\x12\n\x04root\x18\x02\x20\x01(\x0cR\x04\
+    root\"\xe7\x01\n\x15OutpointProofResponse\x123\n\x08balances\x18\x01\x20\
+    \x01(\x0b2\x17.protorune.BalanceSheetR\x08balances\x12\x16\n\x06height\
+    \x18\x02\x20\x01(\x04R\x06height\x12#\n\rprevious_root\x18\x03\x20\x01(\
+    \x0cR\x0cpreviousRoot\x12\x12\n\x04root\x18\x04\x20\x01(\x0cR\x04root\
+    \x12\x14\n\x05index\x18\x05\x20\x01(\x04R\x05index\x12\x16\n\x06leaves\
+    \x18\x06\x20\x01(\x04R\x06leaves\x12\x1a\n\x08siblings\x18\x07\x20\x03(\
+    \x0cR\x08siblingsb\x06proto3\
 ";
 
 /// `FileDescriptorProto` object which was a source for this generated file
@@ -3076,7 +4953,7 @@ pub fn file_descriptor() -> &'static ::protobuf::reflect::FileDescriptor {
     file_descriptor.get(|| {
         let generated_file_descriptor = generated_file_descriptor_lazy.get(|| {
             let mut deps = ::std::vec::Vec::with_capacity(0);
-            let mut messages = ::std::vec::Vec::with_capacity(21);
+            let mut messages = ::std::vec::Vec::with_capacity(32);
             messages.push(RuneId::generated_message_descriptor_data());
             messages.push(Rune::generated_message_descriptor_data());
             messages.push(BalanceSheetItem::generated_message_descriptor_data());
@@ -3098,6 +4975,17 @@ pub fn file_descriptor() -> &'static ::protobuf::reflect::FileDescriptor {
             messages.push(ProtoMessage::generated_message_descriptor_data());
             messages.push(RuntimeInput::generated_message_descriptor_data());
             messages.push(Runtime::generated_message_descriptor_data());
+            messages.push(ProtocolViewRequest::generated_message_descriptor_data());
+            messages.push(SimulateRequest::generated_message_descriptor_data());
+            messages.push(ProtocolBalanceSheet::generated_message_descriptor_data());
+            messages.push(SimulatedOutput::generated_message_descriptor_data());
+            messages.push(SimulatedMessage::generated_message_descriptor_data());
+            messages.push(SimulateResponse::generated_message_descriptor_data());
+            messages.push(ConservationViolation::generated_message_descriptor_data());
+            messages.push(ConservationResponse::generated_message_descriptor_data());
+            messages.push(StateRootRequest::generated_message_descriptor_data());
+            messages.push(StateRootResponse::generated_message_descriptor_data());
+            messages.push(OutpointProofResponse::generated_message_descriptor_data());
             let mut enums = ::std::vec::Vec::with_capacity(0);
             ::protobuf::reflect::GeneratedFileDescriptor::new_generated(
                 file_descriptor_proto(),
@@ -1,4 +1,4 @@
-use crate::tables::{RuneTable, RUNES};
+use crate::tables::{RuneTable, PROTOCOL_TAGS, PROTOCOL_TAG_SEEN, RUNES};
 use anyhow::{anyhow, Result};
 use bitcoin::{OutPoint, Txid};
 use metashrew::index_pointer::{AtomicPointer, KeyValuePointer};
@@ -28,10 +28,20 @@ impl Protoburn {
         proto_balances_by_output: &mut HashMap<u32, BalanceSheet>,
         outpoint: OutPoint,
     ) -> Result<()> {
-        let table = RuneTable::for_protocol(self.tag.ok_or(anyhow!("no tag found"))?);
-        for (rune, _balance) in balance_sheet.clone().balances.into_iter() {
+        let tag = self.tag.ok_or(anyhow!("no tag found"))?;
+        let table = RuneTable::for_protocol(tag);
+        // a protocol holds what is burned into it whether or not it is registered
+        let mut seen = atomic.derive(&PROTOCOL_TAG_SEEN.select_value::<u128>(tag));
+        if seen.get().is_empty() {
+            seen.set_value::<u8>(1);
+            atomic.derive(&PROTOCOL_TAGS).append_value::<u128>(tag);
+        }
+        for (rune, balance) in balance_sheet.clone().balances.into_iter() {
             let name = RUNES.RUNE_ID_TO_ETCHING.select(&rune.into()).get();
             let runeid: Arc<Vec<u8>> = rune.into();
+            // the total burned in bounds what can later be unwrapped
+            let mut burned = atomic.derive(&table.BURNED.select(&runeid));
+            burned.set_value::<u128>(burned.get_value::<u128>() + balance);
             atomic
                 .derive(&table.RUNE_ID_TO_ETCHING.select(&runeid))
                 .set(name.clone());
@@ -68,7 +78,7 @@ pub trait Protoburns<T>: Deref<Target = [T]> {
         runestone_edicts: Vec<Edict>,
         runestone_output_index: u32,
         balances_by_output: &HashMap<u32, BalanceSheet>,
-        proto_balances_by_output: &mut HashMap<u32, BalanceSheet>,
+        proto_balances_by_output: &mut HashMap<u128, HashMap<u32, BalanceSheet>>,
         default_output: u32,
         txid: Txid,
     ) -> Result<()>;
@@ -81,7 +91,7 @@ impl Protoburns<Protoburn> for Vec<Protoburn> {
         runestone_edicts: Vec<Edict>,
         runestone_output_index: u32,
         balances_by_output: &HashMap<u32, BalanceSheet>,
-        proto_balances_by_output: &mut HashMap<u32, BalanceSheet>,
+        proto_balances_by_output: &mut HashMap<u128, HashMap<u32, BalanceSheet>>,
         default_output: u32,
         txid: Txid,
     ) -> Result<()> {
@@ -159,12 +169,15 @@ impl Protoburns<Protoburn> for Vec<Protoburn> {
             }
         }
 
+        // protorunes are kept apart per protocol, since a single runestone can
+        // burn into several protocols
         for (i, burn) in self.into_iter().enumerate() {
             let sheet = burn_sheets[i].clone();
+            let tag = burn.tag.ok_or(anyhow!("no tag found"))?;
             burn.process(
                 atomic,
                 sheet,
-                proto_balances_by_output,
+                proto_balances_by_output.entry(tag).or_default(),
                 OutPoint::new(txid, burn.pointer.ok_or(anyhow!("no vout on protoburn"))?),
             )?;
         }
@@ -332,8 +345,8 @@ mod tests {
 
         // Assert that the function executed successfully
         assert!(result.is_ok());
-        assert_eq!(proto_balances_by_output[&0], BalanceSheet::new());
-        assert_eq!(proto_balances_by_output[&1], BalanceSheet::new());
+        assert_eq!(proto_balances_by_output[&1][&0], BalanceSheet::new());
+        assert_eq!(proto_balances_by_output[&2][&1], BalanceSheet::new());
     }
 
     #[test]
@@ -391,8 +404,8 @@ mod tests {
 
         // Assert that the function executed successfully
         assert!(result.is_ok());
-        assert_eq!(proto_balances_by_output[&0], balance_sheet_1.clone());
-        assert_eq!(proto_balances_by_output[&1], BalanceSheet::new());
+        assert_eq!(proto_balances_by_output[&1][&0], balance_sheet_1.clone());
+        assert_eq!(proto_balances_by_output[&2][&1], BalanceSheet::new());
     }
 
     #[test]
@@ -468,8 +481,8 @@ mod tests {
         );
         let expected_sheet_1 =
             BalanceSheet::from_pairs(vec![ProtoruneRuneId { block: 1, tx: 1 }], vec![290 as u128]);
-        assert_eq!(proto_balances_by_output[&0], expected_sheet_0);
-        assert_eq!(proto_balances_by_output[&1], expected_sheet_1);
+        assert_eq!(proto_balances_by_output[&13][&0], expected_sheet_0);
+        assert_eq!(proto_balances_by_output[&13][&1], expected_sheet_1);
     }
 
     #[test]
@@ -557,8 +570,8 @@ mod tests {
             ],
             vec![290 as u128, 390 as u128],
         );
-        assert_eq!(proto_balances_by_output[&0], expected_sheet_0);
-        assert_eq!(proto_balances_by_output[&1], expected_sheet_1);
+        assert_eq!(proto_balances_by_output[&13][&0], expected_sheet_0);
+        assert_eq!(proto_balances_by_output[&13][&1], expected_sheet_1);
     }
 
     #[test]
@@ -651,8 +664,8 @@ mod tests {
             ],
             vec![20 as u128, 390 as u128],
         );
-        assert_eq!(proto_balances_by_output[&0], expected_sheet_0);
-        assert_eq!(proto_balances_by_output[&1], expected_sheet_1);
+        assert_eq!(proto_balances_by_output[&13][&0], expected_sheet_0);
+        assert_eq!(proto_balances_by_output[&13][&1], expected_sheet_1);
     }
 
     #[test]
@@ -815,8 +828,8 @@ mod tests {
         );
         let expected_sheet_1 =
             BalanceSheet::from_pairs(vec![ProtoruneRuneId { block: 1, tx: 1 }], vec![20 as u128]);
-        assert_eq!(proto_balances_by_output[&0], expected_sheet_0);
-        assert_eq!(proto_balances_by_output[&1], expected_sheet_1);
+        assert_eq!(proto_balances_by_output[&13][&0], expected_sheet_0);
+        assert_eq!(proto_balances_by_output[&13][&1], expected_sheet_1);
     }
 
     #[test]
@@ -908,7 +921,7 @@ mod tests {
             ],
             vec![20 as u128, 390 as u128],
         );
-        assert_eq!(proto_balances_by_output[&0], expected_sheet_0);
-        assert_eq!(proto_balances_by_output[&1], expected_sheet_1);
+        assert_eq!(proto_balances_by_output[&13][&0], expected_sheet_0);
+        assert_eq!(proto_balances_by_output[&13][&1], expected_sheet_1);
     }
 }
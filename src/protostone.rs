@@ -1,25 +1,30 @@
 use crate::{
-    message::{MessageContext, MessageContextParcel},
+    call::{apply_calls, CallLedger},
+    etching::load_minted,
+    flaw::ProtostoneFlaw,
+    fuel::FuelMeter,
+    message::{resolve_inputs, MessageContextParcel, MessageOutcome},
+    predicate::{ClauseTarget, Predicate},
     protoburn::{Protoburn, Protoburns},
+    registry::ProtocolRegistry,
+    tables::RuneTable,
 };
 use anyhow::{anyhow, Result};
-use bitcoin::{Block, Transaction, Txid};
-use metashrew::index_pointer::{AtomicPointer, IndexPointer};
+use bitcoin::{Block, OutPoint, Transaction, Txid};
+use metashrew::index_pointer::{AtomicPointer, IndexPointer, KeyValuePointer};
 use ordinals::{runestone::tag::Tag, Edict, RuneId, Runestone};
+pub use protorune_support::protostone::protostone_vout;
+pub use protorune_support::utils::split_bytes;
 use protorune_support::{
     balance_sheet::{BalanceSheet, ProtoruneRuneId},
     byte_utils::ByteUtils,
+    protostone::{check_edict_output, check_target, encipher, ProtostoneFields},
     rune_transfer::{OutgoingRunes, RuneTransfer},
-    utils::encode_varint_list,
+    utils::consensus_encode,
 };
-use std::collections::{HashMap, HashSet};
-
-static mut PROTOCOLS: Option<HashSet<u128>> = None;
-
-pub fn initialized_protocol_index() -> Result<()> {
-    unsafe { PROTOCOLS = Some(HashSet::new()) }
-    Ok(())
-}
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
 
 pub fn next_protostone_edict_id(
     id: &ProtoruneRuneId,
@@ -36,7 +41,7 @@ pub fn next_protostone_edict_id(
     })
 }
 
-#[derive(Clone, Default, PartialEq, Debug)]
+#[derive(Clone, Default, PartialEq, Debug, Serialize, Deserialize)]
 pub struct ProtostoneEdict {
     pub id: ProtoruneRuneId,
     pub amount: u128,
@@ -88,27 +93,6 @@ pub fn protostone_edicts_from_integers(v: &Vec<u128>) -> Result<Vec<ProtostoneEd
     Ok(result)
 }
 
-pub fn add_to_indexable_protocols(protocol_tag: u128) -> Result<()> {
-    unsafe {
-        if let Some(set) = PROTOCOLS.as_mut() {
-            set.insert(protocol_tag);
-        }
-    }
-    Ok(())
-}
-
-/*
-fn has_protocol(protocol_tag: &u128) -> Result<bool> {
-    unsafe {
-        if let Some(set) = PROTOCOLS.as_mut() {
-            let contains = set.contains(protocol_tag);
-            return Ok(contains);
-        }
-    }
-    Ok(false)
-}
-*/
-
 fn next_two<T, I>(iter: &mut I) -> Option<(T, T)>
 where
     I: Iterator<Item = T>,
@@ -139,6 +123,20 @@ fn take_n<T, I: Iterator<Item = T>>(iter: &mut I, n: usize) -> Option<Vec<T>> {
     }
 }
 
+/// True when the tag/value pairs before the edict body end on a tag with no value
+fn has_truncated_field(values: &[u128]) -> bool {
+    let mut iter = values.iter();
+    while let Some(key) = iter.next() {
+        if iter.next().is_none() {
+            return true;
+        }
+        if *key == 0 {
+            break;
+        }
+    }
+    false
+}
+
 pub fn to_fields(values: &Vec<u128>) -> HashMap<u128, Vec<u128>> {
     let mut map: HashMap<u128, Vec<u128>> = HashMap::new();
     let mut iter = values
@@ -159,7 +157,9 @@ pub fn to_fields(values: &Vec<u128>) -> HashMap<u128, Vec<u128>> {
     map
 }
 
-#[derive(Clone, PartialEq, Debug)]
+/// Fields left out of a JSON protostone take their default
+#[derive(Clone, Default, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Protostone {
     pub burn: Option<u128>,
     pub message: Vec<u8>,
@@ -168,6 +168,11 @@ pub struct Protostone {
     pub pointer: Option<u32>,
     pub from: Option<u32>,
     pub protocol_tag: u128,
+    pub predicate: Option<Predicate>,
+    /// real output credited with the protostone's protorunes as base runes
+    pub unwrap: Option<u32>,
+    /// set when the protostone did not decode cleanly, it is then not executed
+    pub flaw: Option<ProtostoneFlaw>,
 }
 
 /*
@@ -183,31 +188,6 @@ fn varint_byte_len(input: &Vec<u8>, n: u128) -> Result<usize> {
 }
 */
 
-/// This takes in an arbituary amount of bytes, and
-/// converts it in a list of u128s, making sure we don't
-/// write to the 16th byte of the u128.
-///
-/// To ensure the range of bytearrays does not exclude
-/// any bitfields within its terminal bytes, we choose a maximum length f
-/// or a u128 value within a u128[] intended for interpretation as a u8[] to 15 bytes.
-/// This allows us to safely model an arbitrary bytearray within the Runestone paradigm.
-pub fn split_bytes(v: &Vec<u8>) -> Vec<u128> {
-    let mut result: Vec<Vec<u8>> = vec![];
-    v.iter().enumerate().for_each(|(i, b)| {
-        if i % 15 == 0 {
-            result.push(Vec::<u8>::new());
-        }
-        result.last_mut().unwrap().push(*b);
-    });
-    result
-        .iter_mut()
-        .map(|v| {
-            v.resize(std::mem::size_of::<u128>(), 0u8);
-            return u128::from_le_bytes((&v[0..16]).try_into().unwrap());
-        })
-        .collect::<Vec<u128>>()
-}
-
 pub fn join_to_bytes(v: &Vec<u128>) -> Vec<u8> {
     let mut result: Vec<u8> = vec![];
     for (_, integer) in v.iter().enumerate() {
@@ -228,55 +208,66 @@ impl Protostone {
     pub fn is_message(&self) -> bool {
         !self.message.is_empty()
     }
-    /// Enciphers a protostone into a vector of u128s
-    /// NOTE: This is not LEB encoded
-    pub fn to_integers(&self) -> Result<Vec<u128>> {
-        let mut payload = Vec::<u128>::new();
-
-        if let Some(burn) = self.burn {
-            payload.push(Tag::Burn.into());
-            payload.push(burn.into());
-        }
-        if let Some(pointer) = self.pointer {
-            payload.push(Tag::ProtoPointer.into());
-            payload.push(pointer.into());
-        }
-        if let Some(refund) = self.refund {
-            payload.push(Tag::Refund.into());
-            payload.push(refund.into());
-        }
-        if let Some(from) = self.from.as_ref() {
-            payload.push(Tag::From.into());
-            payload.push((*from).into());
+    /// Flags the protostone at `index` if its pointer or refund is neither a real
+    /// output nor the virtual vout of a later protostone, or it unwraps to
+    /// anything but a real output
+    pub fn check_targets(&mut self, index: u32, num_outputs: u32, num_protostones: u32) {
+        let out_of_bounds = self
+            .pointer
+            .iter()
+            .chain(self.refund.iter())
+            .any(|target| check_target(index, *target, num_outputs, num_protostones).is_err())
+            || self.unwrap.is_some_and(|unwrap| unwrap >= num_outputs);
+        if out_of_bounds {
+            self.flag(ProtostoneFlaw::TargetOutput);
         }
-        if !self.message.is_empty() {
-            for item in split_bytes(&self.message) {
-                payload.push(Tag::Message.into());
-                payload.push(item);
-            }
+    }
+    /// Flags the protostone at `index` if an edict outputs anywhere but a real
+    /// output, the edict-spread output or the virtual vout of a later protostone,
+    /// the bounds `ordinals` puts on runestone edicts
+    pub fn check_edict_outputs(&mut self, index: u32, num_outputs: u32, num_protostones: u32) {
+        let out_of_bounds = self.edicts.iter().any(|edict| {
+            check_edict_output(index, edict.output, num_outputs, num_protostones).is_err()
+        });
+        if out_of_bounds {
+            self.flag(ProtostoneFlaw::EdictOutput);
         }
-        if !self.edicts.is_empty() {
-            payload.push(Tag::Body.into());
-            let mut edicts = self.edicts.clone();
-            edicts.sort_by_key(|edict| edict.id);
-
-            let mut previous = ProtoruneRuneId::default();
-            for edict in edicts {
-                let (block, tx) = previous
-                    .delta(edict.id.into())
-                    .ok_or("")
-                    .map_err(|_| anyhow!("invalid delta"))?;
-                payload.push(block);
-                payload.push(tx);
-                payload.push(edict.amount);
-                payload.push(edict.output.into());
-                previous = edict.id.into();
-            }
+    }
+    /// The protostone's fields in the form they are enciphered
+    pub fn fields(&self) -> ProtostoneFields {
+        ProtostoneFields {
+            protocol_tag: self.protocol_tag,
+            burn: self.burn,
+            pointer: self.pointer,
+            refund: self.refund,
+            from: self.from,
+            unwrap: self.unwrap,
+            message: self.message.clone(),
+            predicate: self
+                .predicate
+                .as_ref()
+                .map(|predicate| predicate.to_integers())
+                .unwrap_or_default(),
+            edicts: self
+                .edicts
+                .iter()
+                .map(|edict| (edict.id, edict.amount, edict.output))
+                .collect(),
         }
-        Ok(payload)
     }
-    pub fn process_message<T: MessageContext>(
+    /// Enciphers a protostone into a vector of u128s
+    /// NOTE: This is not LEB encoded
+    pub fn to_integers(&self) -> Result<Vec<u128>> {
+        self.fields().to_integers()
+    }
+    /// Dispatches the message to the handler registered for its protocol tag.
+    /// Messages for unregistered protocols, or sent before the protocol's
+    /// activation height, are not executed. The handler may send runes to any
+    /// real output or later protostone, but must account for all it was sent.
+    /// Calls it made into other protocols only take effect if it succeeds
+    pub fn process_message(
         &self,
+        registry: &ProtocolRegistry,
         atomic: &mut AtomicPointer,
         transaction: &Transaction,
         txindex: u32,
@@ -284,23 +275,49 @@ impl Protostone {
         height: u64,
         _runestone_output_index: u32,
         vout: u32,
-        balances_by_output: &mut HashMap<u32, BalanceSheet>,
+        num_protostones: u32,
+        proto_balances_by_output: &mut HashMap<u128, HashMap<u32, BalanceSheet>>,
         default_output: u32,
     ) -> Result<()> {
-        if self.is_message() {
+        if let (true, Some((handler, version))) = (
+            self.is_message(),
+            registry.handler(&self.protocol_tag, height),
+        ) {
+            // inputs that can't be resolved fail the message, not the runestone
+            let (inputs, resolved) = match resolve_inputs(atomic, transaction, self.protocol_tag) {
+                Ok(inputs) => (inputs, Ok(())),
+                Err(e) => (vec![], Err(e)),
+            };
+            let message_key = consensus_encode(&OutPoint::new(transaction.txid(), vout))?;
+            let mut balances_by_output = proto_balances_by_output
+                .remove(&self.protocol_tag)
+                .unwrap_or_default();
+            let runtimes = proto_balances_by_output
+                .iter()
+                .filter_map(|(tag, balances)| balances.get(&u32::MAX).map(|v| (*tag, v.clone())))
+                .collect::<HashMap<u128, BalanceSheet>>();
+            let runtime_initial = balances_by_output.get(&u32::MAX).cloned();
             let initial_sheet = balances_by_output
                 .get(&vout)
                 .map(|v| v.clone())
                 .unwrap_or_else(|| BalanceSheet::default());
+            let fuel_limit = registry
+                .entry(&self.protocol_tag)
+                .and_then(|entry| entry.fuel_limit);
             atomic.checkpoint();
             let parcel = MessageContextParcel {
                 atomic: atomic.derive(&IndexPointer::default()),
+                protocol_tag: self.protocol_tag,
+                fuel: FuelMeter::for_transaction(transaction, fuel_limit),
                 runes: RuneTransfer::from_balance_sheet(initial_sheet.clone()),
                 transaction: transaction.clone(),
+                inputs,
                 block: block.clone(),
                 height,
+                version,
+                vout,
                 pointer: self.pointer.unwrap_or_else(|| default_output),
-                refund_pointer: self.pointer.unwrap_or_else(|| default_output),
+                refund_pointer: self.refund.unwrap_or_else(|| default_output),
                 calldata: self
                     .message
                     .iter()
@@ -308,40 +325,168 @@ impl Protostone {
                     .flatten()
                     .collect::<Vec<u8>>(),
                 txindex,
-                runtime_balances: Box::new(
-                    balances_by_output
-                        .get(&u32::MAX)
-                        .map(|v| v.clone())
-                        .unwrap_or_else(|| BalanceSheet::default()),
-                ),
+                runtime_balances: Box::new(runtime_initial.clone().unwrap_or_default()),
                 sheets: Box::new(BalanceSheet::default()),
+                registry: Arc::new(registry.clone()),
+                calls: CallLedger::default(),
+                call_stack: vec![],
+                runtimes,
             };
             let pointer = self.pointer.unwrap_or_else(|| default_output);
             let refund_pointer = self.refund.unwrap_or_else(|| default_output);
-            match T::handle(&parcel) {
-                Ok(values) => match values.reconcile(balances_by_output, vout, pointer) {
-                    Ok(_) => atomic.commit(),
-                    Err(_) => {
-                        let sheet = balances_by_output
-                            .get(&vout)
-                            .map(|v| v.clone())
-                            .unwrap_or_else(|| BalanceSheet::default());
-                        balances_by_output.remove(&vout);
-                        if !balances_by_output.contains_key(&refund_pointer) {
-                            balances_by_output.insert(refund_pointer, BalanceSheet::default());
-                        }
-                        sheet.pipe(balances_by_output.get_mut(&refund_pointer).unwrap());
-                        atomic.rollback()
+            let predicate = self.predicate.clone().unwrap_or_default();
+            let outcome = match resolved
+                .and_then(|_| predicate.check(ClauseTarget::Incoming, &initial_sheet))
+                .and_then(|_| handler(&parcel))
+                .and_then(|result| {
+                    // a handler that swallowed a fuel error still fails
+                    if parcel.fuel.is_exhausted() {
+                        return Err(anyhow!("message ran out of fuel"));
                     }
-                },
+                    let num_outputs = transaction.output.len() as u32;
+                    let calls = parcel.calls.lock().unwrap().clone();
+                    for target in result.destinations(pointer).into_keys().chain(
+                        calls
+                            .iter()
+                            .flat_map(|call| call.result.allocations.iter().map(|(v, _)| *v)),
+                    ) {
+                        check_target(
+                            vout - protostone_vout(num_outputs, 0),
+                            target,
+                            num_outputs,
+                            num_protostones,
+                        )?;
+                    }
+                    predicate.check(ClauseTarget::Outgoing, &result.outgoing())?;
+                    let events = parcel.atomic.derive(
+                        &RuneTable::for_protocol(self.protocol_tag)
+                            .EVENTS
+                            .select(&message_key),
+                    );
+                    for event in &result.events {
+                        events.append(Arc::new(event.clone()));
+                    }
+                    // protorunes the handler minted count as incoming
+                    load_minted(&parcel)?.pipe(balances_by_output.entry(vout).or_default());
+                    // as do protorunes calls sent back, what was sent to calls is spent
+                    let mut incoming = balances_by_output.remove(&vout).unwrap_or_default();
+                    let mut runtime = balances_by_output.remove(&u32::MAX).unwrap_or_default();
+                    parcel.settle_calls(&mut incoming, &mut runtime)?;
+                    balances_by_output.insert(vout, incoming);
+                    balances_by_output.insert(u32::MAX, runtime);
+                    result.reconcile(&mut balances_by_output, vout, pointer)?;
+                    apply_calls(&calls, proto_balances_by_output);
+                    Ok(())
+                }) {
+                Ok(_) => {
+                    atomic.commit();
+                    MessageOutcome::Success
+                }
                 Err(_) => {
+                    // only what came in is refunded, anything minted is dropped
+                    let sheet = initial_sheet;
+                    balances_by_output.remove(&vout);
+                    if !balances_by_output.contains_key(&refund_pointer) {
+                        balances_by_output.insert(refund_pointer, BalanceSheet::default());
+                    }
+                    sheet.pipe(balances_by_output.get_mut(&refund_pointer).unwrap());
+                    match runtime_initial {
+                        Some(runtime) => balances_by_output.insert(u32::MAX, runtime),
+                        None => balances_by_output.remove(&u32::MAX),
+                    };
                     atomic.rollback();
+                    if parcel.fuel.is_exhausted() {
+                        MessageOutcome::OutOfFuel
+                    } else {
+                        MessageOutcome::Reverted
+                    }
                 }
-            }
+            };
+            proto_balances_by_output.insert(self.protocol_tag, balances_by_output);
+            atomic
+                .derive(
+                    &RuneTable::for_protocol(self.protocol_tag)
+                        .OUTCOMES
+                        .select(&message_key),
+                )
+                .set_value::<u8>(outcome as u8);
         }
         Ok(())
     }
+    /// Until the burn target's activation height a protoburn is a plain carry,
+    /// the runes it would have burned stay where the runestone put them
+    pub fn at_height(mut self, registry: &ProtocolRegistry, height: u64) -> Self {
+        if let Some(tag) = self.burn {
+            if registry.is_pending(&tag, height) {
+                self.burn = None;
+            }
+        }
+        self
+    }
+    /// A protostone that decoded no further than its protocol tag
+    pub fn flawed(protocol_tag: u128, flaw: ProtostoneFlaw) -> Self {
+        Protostone {
+            protocol_tag,
+            flaw: Some(flaw),
+            ..Default::default()
+        }
+    }
+    /// Records `flaw` unless the protostone is already flawed, the first flaw found
+    /// is the one reported
+    pub fn flag(&mut self, flaw: ProtostoneFlaw) {
+        self.flaw.get_or_insert(flaw);
+    }
+    /// A flawed protostone keeps its place, so the virtual vouts of the ones after
+    /// it stay put, but burns, moves and dispatches nothing. Whatever reaches it
+    /// goes on to the runestone's default output
+    pub fn inert_if_flawed(self) -> Self {
+        match self.flaw {
+            Some(flaw) => Protostone::flawed(self.protocol_tag, flaw),
+            None => self,
+        }
+    }
     pub fn from_fields_and_tag(map: &HashMap<u128, Vec<u128>>, protocol_tag: u128) -> Result<Self> {
+        let mut flaw = map
+            .keys()
+            .find(|tag| **tag != 0 && **tag % 2 == 0)
+            .map(|_| ProtostoneFlaw::UnrecognizedEvenTag);
+        let edicts = match map.get(&0u128) {
+            Some(list) if list.len() % 4 != 0 => {
+                flaw.get_or_insert(ProtostoneFlaw::EdictSet);
+                vec![]
+            }
+            Some(list) => match protostone_edicts_from_integers(list) {
+                Ok(edicts) => edicts,
+                Err(_) => {
+                    flaw.get_or_insert(ProtostoneFlaw::EdictRuneId);
+                    vec![]
+                }
+            },
+            None => vec![],
+        };
+        if edicts
+            .iter()
+            .any(|edict| u32::try_from(edict.output).is_err())
+        {
+            flaw.get_or_insert(ProtostoneFlaw::EdictOutput);
+        }
+        // a vout past u32::MAX would otherwise truncate to some other vout
+        let mut vout = |tag: Tag| {
+            let value = map.get(&tag.into())?[0];
+            u32::try_from(value)
+                .map_err(|_| flaw.get_or_insert(ProtostoneFlaw::FieldOverflow))
+                .ok()
+        };
+        let refund = vout(Tag::Refund);
+        let pointer = vout(Tag::ProtoPointer);
+        let from = vout(Tag::From);
+        let unwrap = vout(Tag::Unwrap);
+        let predicate = match map.get(&Tag::Predicate.into()) {
+            Some(list) => Predicate::from_integers(list)
+                .map_err(|_| flaw.get_or_insert(ProtostoneFlaw::Predicate))
+                .ok(),
+            None => None,
+        };
         Ok(Protostone {
             burn: map.get(&Tag::Burn.into()).map(|v| v[0] as u128),
             message: join_to_bytes(
@@ -349,17 +494,14 @@ impl Protostone {
                     .map(|v| v.clone())
                     .unwrap_or_else(|| Vec::<u128>::new()),
             ),
-            refund: map.get(&Tag::Refund.into()).map(|v| v[0] as u32),
-            pointer: map.get(&Tag::ProtoPointer.into()).map(|v| v[0] as u32),
+            refund,
+            pointer,
             protocol_tag,
-            from: map.get(&Tag::From.into()).map(|v| v[0] as u32),
-            edicts: map
-                .get(&0u128)
-                .map(|list| -> Result<Vec<ProtostoneEdict>> {
-                    protostone_edicts_from_integers(&list)
-                })
-                .and_then(|v| v.ok())
-                .unwrap_or_else(|| vec![]),
+            from,
+            unwrap,
+            predicate,
+            edicts,
+            flaw,
         })
     }
 
@@ -383,27 +525,39 @@ impl Protostone {
         let raw: Vec<u8> = join_to_bytes(values);
         let mut iter = Runestone::integers(&raw)?.into_iter();
         let mut result: Vec<Protostone> = vec![];
-        loop {
-            if let Some(protocol_tag) = iter.next() {
-                // if protocol_tag == 0 then break, since we don't allow protocol tag to equal zero anyways.
-                // also this means we have postfix zeroes in the last u128
-                if protocol_tag == 0 {
-                    break;
+        while let Some(protocol_tag) = iter.next() {
+            // if protocol_tag == 0 then break, since we don't allow protocol tag to equal zero anyways.
+            // also this means we have postfix zeroes in the last u128
+            if protocol_tag == 0 {
+                // anything but padding after the last protostone taints the whole
+                // stream, as trailing integers make a runestone a cenotaph
+                if iter.any(|v| v != 0) {
+                    for stone in result.iter_mut() {
+                        stone.flag(ProtostoneFlaw::TrailingIntegers);
+                    }
                 }
-                if let Some(length) = iter.next() {
-                    result.push(Protostone::from_fields_and_tag(
-                        &to_fields(
-                            &(take_n(&mut iter, length.try_into()?)
-                                .ok_or("")
-                                .map_err(|_| anyhow!("less values than expected")))?,
-                        ),
+                break;
+            }
+            let values = iter
+                .next()
+                .and_then(|length| usize::try_from(length).ok())
+                .and_then(|length| take_n(&mut iter, length));
+            match values {
+                Some(values) => {
+                    let mut stone =
+                        Protostone::from_fields_and_tag(&to_fields(&values), protocol_tag)?;
+                    if has_truncated_field(&values) {
+                        stone.flag(ProtostoneFlaw::TruncatedField);
+                    }
+                    result.push(stone);
+                }
+                None => {
+                    result.push(Protostone::flawed(
                         protocol_tag,
-                    )?);
-                } else {
+                        ProtostoneFlaw::TruncatedField,
+                    ));
                     break;
                 }
-            } else {
-                break;
             }
         }
         Ok(result)
@@ -420,23 +574,26 @@ pub trait Protostones {
         runestone: &Runestone,
         runestone_output_index: u32,
         balances_by_output: &HashMap<u32, BalanceSheet>,
-        proto_balances_by_output: &mut HashMap<u32, BalanceSheet>,
+        proto_balances_by_output: &mut HashMap<u128, HashMap<u32, BalanceSheet>>,
         default_output: u32,
         txid: Txid,
     ) -> Result<()>;
     fn encipher(&self) -> Result<Vec<u128>>;
+    /// Flags each protostone whose edicts, pointer, refund or unwrap target a
+    /// vout the transaction doesn't have, as the indexer does before running any
+    fn check_outputs(&mut self, num_outputs: u32);
 }
 
 impl Protostones for Vec<Protostone> {
     fn encipher(&self) -> Result<Vec<u128>> {
-        let mut values = Vec::<u128>::new();
-        for stone in self {
-            values.push(stone.protocol_tag);
-            let varints = stone.to_integers()?;
-            values.push(varints.len() as u128);
-            values.extend(&varints);
+        encipher(&self.iter().map(|stone| stone.fields()).collect::<Vec<_>>())
+    }
+    fn check_outputs(&mut self, num_outputs: u32) {
+        let num_protostones = self.len() as u32;
+        for (i, stone) in self.iter_mut().enumerate() {
+            stone.check_edict_outputs(i as u32, num_outputs, num_protostones);
+            stone.check_targets(i as u32, num_outputs, num_protostones);
         }
-        Ok(split_bytes(&encode_varint_list(&values)))
     }
     fn burns(&self) -> Result<Vec<Protoburn>> {
         Ok(self
@@ -455,11 +612,14 @@ impl Protostones for Vec<Protostone> {
         runestone: &Runestone,
         runestone_output_index: u32,
         balances_by_output: &HashMap<u32, BalanceSheet>,
-        proto_balances_by_output: &mut HashMap<u32, BalanceSheet>,
+        proto_balances_by_output: &mut HashMap<u128, HashMap<u32, BalanceSheet>>,
         default_output: u32,
         txid: Txid,
     ) -> Result<()> {
         let mut burns = self.burns()?;
+        if burns.is_empty() {
+            return Ok(());
+        }
         burns.process(
             atomic,
             runestone.edicts.clone(),
@@ -504,6 +664,11 @@ mod tests {
     ///         output: vec<u128>![1 4 83 0 91 3 0 0 0 0 0 0 0 0 0]
     ///
     use super::*;
+    use bitcoin::ScriptBuf;
+    use ordinals::Artifact;
+    use protorune_support::builder::{ProtostoneBuilder, TransactionBuilder};
+    use protorune_support::constants::PROTOBURN_TAG;
+    use protorune_support::utils::encode_varint_list;
 
     #[test]
     fn test_protostone_encipher_burn() {
@@ -515,6 +680,9 @@ mod tests {
             from: None,
             protocol_tag: 13, // must be 13 when protoburn
             message: vec![],
+            unwrap: None,
+            flaw: None,
+            predicate: None,
         }];
 
         let protostone_enciphered = protostones.encipher().unwrap();
@@ -541,6 +709,9 @@ mod tests {
             from: None,
             protocol_tag: 1,
             message: vec![],
+            unwrap: None,
+            flaw: None,
+            predicate: None,
         }];
 
         let protostone_enciphered = protostones.encipher().unwrap();
@@ -560,6 +731,9 @@ mod tests {
             from: None,
             protocol_tag: 1,
             message: vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 0, 0, 0, 0, 0, 0], // what we pass in should be well defined by the subprotocol
+            unwrap: None,
+            flaw: None,
+            predicate: None,
         }];
 
         let protostone_enciphered = protostones.encipher().unwrap();
@@ -580,6 +754,9 @@ mod tests {
                 from: None,
                 protocol_tag: 13,
                 message: vec![],
+                unwrap: None,
+                flaw: None,
+                predicate: None,
             },
             Protostone {
                 burn: Some(1u128),
@@ -589,6 +766,9 @@ mod tests {
                 from: None,
                 protocol_tag: 3,
                 message: vec![100, 11, 112, 113, 114, 115, 116, 117, 118, 0, 0, 0, 0, 0, 0],
+                unwrap: None,
+                flaw: None,
+                predicate: None,
             },
         ];
 
@@ -598,4 +778,202 @@ mod tests {
 
         assert_eq!(protostones, protostone_decipered);
     }
+
+    #[test]
+    fn test_target_bounds() {
+        // two outputs and three protostones, at virtual vouts 3, 4 and 5
+        let flaw = |pointer: u32, refund: u32, unwrap: Option<u32>| {
+            let mut stone = Protostone {
+                pointer: Some(pointer),
+                refund: Some(refund),
+                unwrap,
+                ..Default::default()
+            };
+            stone.check_targets(1, 2, 3);
+            stone.flaw
+        };
+        assert_eq!(flaw(0, 5, Some(1)), None);
+        assert_eq!(flaw(3, 0, None), Some(ProtostoneFlaw::TargetOutput));
+        assert_eq!(flaw(0, 6, None), Some(ProtostoneFlaw::TargetOutput));
+        assert_eq!(flaw(0, 0, Some(2)), Some(ProtostoneFlaw::TargetOutput));
+    }
+
+    #[test]
+    fn test_protostone_encipher_predicate() {
+        let protostones = vec![Protostone {
+            burn: None,
+            edicts: vec![],
+            pointer: Some(0),
+            refund: Some(0),
+            from: None,
+            protocol_tag: 1,
+            message: vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            unwrap: None,
+            flaw: None,
+            predicate: Some(Predicate {
+                clauses: vec![crate::predicate::Clause {
+                    target: ClauseTarget::Outgoing,
+                    id: ProtoruneRuneId {
+                        block: 840000,
+                        tx: 1,
+                    },
+                    amount: 123456789,
+                }],
+            }),
+        }];
+
+        let protostone_enciphered = protostones.encipher().unwrap();
+
+        let protostone_decipered = Protostone::decipher(&protostone_enciphered).unwrap();
+
+        assert_eq!(protostones, protostone_decipered);
+    }
+
+    #[test]
+    fn test_protostone_builder_round_trip() {
+        let id = ProtoruneRuneId::new(840000, 1);
+        let predicate = Predicate {
+            clauses: vec![crate::predicate::Clause {
+                target: ClauseTarget::Outgoing,
+                id,
+                amount: 100,
+            }],
+        };
+        // two outputs and the OP_RETURN, so protostones sit at virtual vouts 4 and 5
+        let transaction = TransactionBuilder::new()
+            .output(ScriptBuf::new(), 546)
+            .output(ScriptBuf::new(), 546)
+            .pointer(2)
+            .protostone(ProtostoneBuilder::protoburn(1).pointer(5))
+            .protostone(
+                ProtostoneBuilder::message(1, vec![7; 30])
+                    .pointer(0)
+                    .refund(1)
+                    .unwrap(1)
+                    .predicate(predicate.to_integers())
+                    .edict(id, 100, 0),
+            )
+            .build()
+            .unwrap();
+        let runestone = match Runestone::decipher(&transaction) {
+            Some(Artifact::Runestone(runestone)) => runestone,
+            _ => panic!("built transaction carries no runestone"),
+        };
+        let protostones = Protostone::from_runestone(&runestone).unwrap();
+        assert_eq!(
+            protostones,
+            vec![
+                Protostone {
+                    protocol_tag: PROTOBURN_TAG,
+                    burn: Some(1),
+                    pointer: Some(5),
+                    ..Default::default()
+                },
+                Protostone {
+                    protocol_tag: 1,
+                    message: vec![7; 30],
+                    pointer: Some(0),
+                    refund: Some(1),
+                    unwrap: Some(1),
+                    predicate: Some(predicate),
+                    edicts: vec![ProtostoneEdict {
+                        id,
+                        amount: 100,
+                        output: 0,
+                    }],
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    fn decipher_integers(values: Vec<u128>) -> Vec<Protostone> {
+        Protostone::decipher(&split_bytes(&encode_varint_list(&values))).unwrap()
+    }
+
+    #[test]
+    fn test_protostone_flaws() {
+        let flaws = |values: Vec<u128>| {
+            decipher_integers(values)
+                .into_iter()
+                .map(|stone| stone.flaw)
+                .collect::<Vec<Option<ProtostoneFlaw>>>()
+        };
+        // pointer 0, well formed
+        assert_eq!(flaws(vec![1, 2, 91, 0]), vec![None]);
+        // declares forty values but carries two, more than the zero padding can make up
+        assert_eq!(
+            flaws(vec![1, 2, 91, 0, 1, 40, 91, 0]),
+            vec![None, Some(ProtostoneFlaw::TruncatedField)]
+        );
+        // a pointer tag with no value
+        assert_eq!(
+            flaws(vec![1, 3, 93, 0, 91]),
+            vec![Some(ProtostoneFlaw::TruncatedField)]
+        );
+        // integers after the terminating zero
+        assert_eq!(
+            flaws(vec![1, 2, 91, 0, 0, 7]),
+            vec![Some(ProtostoneFlaw::TrailingIntegers)]
+        );
+        // an edict body of three values
+        assert_eq!(
+            flaws(vec![1, 4, 0, 840000, 1, 100]),
+            vec![Some(ProtostoneFlaw::EdictSet)]
+        );
+        // even tags are reserved
+        assert_eq!(
+            flaws(vec![1, 2, 2, 1]),
+            vec![Some(ProtostoneFlaw::UnrecognizedEvenTag)]
+        );
+        // an edict output past any vout
+        assert_eq!(
+            flaws(vec![1, 5, 0, 840000, 1, 100, u64::MAX as u128]),
+            vec![Some(ProtostoneFlaw::EdictOutput)]
+        );
+        // a predicate clause cut short, with a target that is neither side
+        assert_eq!(
+            flaws(vec![1, 2, 85, 7]),
+            vec![Some(ProtostoneFlaw::Predicate)]
+        );
+        // a pointer that would truncate to vout 0
+        assert_eq!(
+            flaws(vec![1, 2, 91, u32::MAX as u128 + 1]),
+            vec![Some(ProtostoneFlaw::FieldOverflow)]
+        );
+    }
+
+    #[test]
+    fn test_flawed_protostone_is_inert() {
+        let stone = decipher_integers(vec![1, 6, 91, 0, 0, 840000, 1, 100])
+            .pop()
+            .unwrap();
+        assert_eq!(stone.flaw, Some(ProtostoneFlaw::EdictSet));
+        assert_eq!(stone.pointer, Some(0));
+        let inert = stone.inert_if_flawed();
+        assert_eq!(inert, Protostone::flawed(1, ProtostoneFlaw::EdictSet));
+    }
+
+    #[test]
+    fn test_edict_output_bounds() {
+        // two outputs and three protostones, at virtual vouts 3, 4 and 5
+        let flaw = |output: u128| {
+            let mut stone = Protostone {
+                edicts: vec![ProtostoneEdict {
+                    id: ProtoruneRuneId::new(840000, 1),
+                    amount: 100,
+                    output,
+                }],
+                ..Default::default()
+            };
+            stone.check_edict_outputs(1, 2, 3);
+            stone.flaw
+        };
+        for output in [0, 1, 2, 5] {
+            assert_eq!(flaw(output), None);
+        }
+        for output in [3, 4, 6, u32::MAX as u128 + 1] {
+            assert_eq!(flaw(output), Some(ProtostoneFlaw::EdictOutput));
+        }
+    }
 }
@@ -0,0 +1,221 @@
+use crate::message::{MessageContext, MessageContextParcel};
+use crate::tables;
+use crate::view::ViewContext;
+use anyhow::{anyhow, Result};
+use metashrew::index_pointer::KeyValuePointer;
+use protorune_support::rune_transfer::MessageResult;
+use std::collections::BTreeMap;
+
+pub type MessageHandler = fn(parcel: &MessageContextParcel) -> Result<MessageResult>;
+pub type ViewHandler = fn(method: u32, input: &[u8]) -> Result<Vec<u8>>;
+
+#[derive(Clone)]
+pub struct ProtocolEntry {
+    pub handler: MessageHandler,
+    /// first block at which burns into the protocol and its messages are processed
+    pub activation_height: u64,
+    /// (height, version) rule upgrades in ascending height order, version 0 applies
+    /// from activation until the first upgrade
+    pub versions: Vec<(u64, u32)>,
+    /// fuel each message gets, in place of the transaction size based budget
+    pub fuel_limit: Option<u64>,
+    /// read-only queries the protocol answers through `protocolview`
+    pub view: Option<ViewHandler>,
+}
+
+impl ProtocolEntry {
+    pub fn is_active(&self, height: u64) -> bool {
+        height >= self.activation_height
+    }
+    pub fn version(&self, height: u64) -> u32 {
+        self.versions
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= height)
+            .map(|(_, version)| *version)
+            .unwrap_or(0)
+    }
+}
+
+/// The set of subprotocols hosted by one indexer, keyed by protocol tag.
+/// Protostones for tags without an entry, or for an entry that is not yet
+/// active, are not dispatched, their protorunes are only carried between outputs
+#[derive(Clone, Default)]
+pub struct ProtocolRegistry {
+    entries: BTreeMap<u128, ProtocolEntry>,
+}
+
+impl ProtocolRegistry {
+    pub fn new() -> Self {
+        ProtocolRegistry::default()
+    }
+    pub fn register<T: MessageContext>(&mut self) -> Result<()> {
+        self.register_at::<T>(0)
+    }
+    pub fn register_at<T: MessageContext>(&mut self, activation_height: u64) -> Result<()> {
+        let tag = T::protocol_tag();
+        if self.entries.contains_key(&tag) {
+            return Err(anyhow!("protocol {} is already registered", tag));
+        }
+        self.entries.insert(
+            tag,
+            ProtocolEntry {
+                handler: T::handle,
+                activation_height,
+                versions: vec![],
+                fuel_limit: None,
+                view: None,
+            },
+        );
+        Ok(())
+    }
+    /// Switches the protocol to rule `version` from `height` onward
+    pub fn add_version(&mut self, tag: u128, height: u64, version: u32) -> Result<()> {
+        let entry = self
+            .entries
+            .get_mut(&tag)
+            .ok_or(anyhow!("protocol {} is not registered", tag))?;
+        if height < entry.activation_height {
+            return Err(anyhow!(
+                "protocol {} upgrade at {} precedes its activation at {}",
+                tag,
+                height,
+                entry.activation_height
+            ));
+        }
+        if let Some((last, _)) = entry.versions.last() {
+            if height <= *last {
+                return Err(anyhow!(
+                    "protocol {} upgrades must be added in ascending height order",
+                    tag
+                ));
+            }
+        }
+        entry.versions.push((height, version));
+        Ok(())
+    }
+    pub fn set_fuel_limit(&mut self, tag: u128, limit: u64) -> Result<()> {
+        self.entries
+            .get_mut(&tag)
+            .ok_or(anyhow!("protocol {} is not registered", tag))?
+            .fuel_limit = Some(limit);
+        Ok(())
+    }
+    /// Routes views for a registered protocol to `T::view`
+    pub fn register_view<T: ViewContext>(&mut self) -> Result<()> {
+        let tag = T::protocol_tag();
+        self.entries
+            .get_mut(&tag)
+            .ok_or(anyhow!("protocol {} is not registered", tag))?
+            .view = Some(T::view);
+        Ok(())
+    }
+    pub fn has_protocol(&self, tag: &u128) -> bool {
+        self.entries.contains_key(tag)
+    }
+    pub fn entry(&self, tag: &u128) -> Option<&ProtocolEntry> {
+        self.entries.get(tag)
+    }
+    pub fn is_active(&self, tag: &u128, height: u64) -> bool {
+        self.entries
+            .get(tag)
+            .map(|entry| entry.is_active(height))
+            .unwrap_or(false)
+    }
+    /// True for a registered protocol whose activation height has not been reached
+    pub fn is_pending(&self, tag: &u128, height: u64) -> bool {
+        self.entries
+            .get(tag)
+            .map(|entry| !entry.is_active(height))
+            .unwrap_or(false)
+    }
+    /// The handler and rule version to dispatch a message with at `height`
+    pub fn handler(&self, tag: &u128, height: u64) -> Option<(MessageHandler, u32)> {
+        self.entries
+            .get(tag)
+            .filter(|entry| entry.is_active(height))
+            .map(|entry| (entry.handler, entry.version(height)))
+    }
+    pub fn tags(&self) -> Vec<u128> {
+        self.entries.keys().copied().collect()
+    }
+    /// Tags of the registered protocols and of every protocol anything was
+    /// protoburned into, registered or not, in ascending order
+    pub fn indexed_tags(&self) -> Vec<u128> {
+        let mut tags = self.tags();
+        tags.extend(tables::PROTOCOL_TAGS.get_list_values::<u128>());
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protorune_support::balance_sheet::BalanceSheet;
+    use protorune_support::rune_transfer::RuneTransfer;
+
+    struct First(());
+    struct Second(());
+
+    impl MessageContext for First {
+        fn handle(_parcel: &MessageContextParcel) -> Result<MessageResult> {
+            Ok((Vec::<RuneTransfer>::new(), BalanceSheet::default()).into())
+        }
+        fn protocol_tag() -> u128 {
+            100
+        }
+    }
+
+    impl MessageContext for Second {
+        fn handle(_parcel: &MessageContextParcel) -> Result<MessageResult> {
+            Err(anyhow!("always fails"))
+        }
+        fn protocol_tag() -> u128 {
+            101
+        }
+    }
+
+    #[test]
+    fn test_registry_dispatch() {
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<First>().unwrap();
+        registry.register::<Second>().unwrap();
+        assert_eq!(registry.tags(), vec![100, 101]);
+        assert!(!registry.has_protocol(&102));
+        let parcel = MessageContextParcel::default();
+        assert!(registry.handler(&100, 0).unwrap().0(&parcel).is_ok());
+        assert!(registry.handler(&101, 0).unwrap().0(&parcel).is_err());
+    }
+
+    #[test]
+    fn test_registry_duplicate_tag() {
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<First>().unwrap();
+        assert!(registry.register::<First>().is_err());
+    }
+
+    #[test]
+    fn test_registry_activation_and_versions() {
+        let mut registry = ProtocolRegistry::new();
+        registry.register_at::<First>(840000).unwrap();
+        assert!(registry.is_pending(&100, 839999));
+        assert!(registry.handler(&100, 839999).is_none());
+        assert!(registry.is_active(&100, 840000));
+        // unregistered tags are neither active nor pending
+        assert!(!registry.is_active(&101, 840000));
+        assert!(!registry.is_pending(&101, 840000));
+
+        assert!(registry.add_version(100, 839999, 1).is_err());
+        registry.add_version(100, 850000, 1).unwrap();
+        registry.add_version(100, 860000, 2).unwrap();
+        assert!(registry.add_version(100, 855000, 3).is_err());
+        assert!(registry.add_version(101, 850000, 1).is_err());
+
+        assert_eq!(registry.handler(&100, 840000).unwrap().1, 0);
+        assert_eq!(registry.handler(&100, 850000).unwrap().1, 1);
+        assert_eq!(registry.handler(&100, 859999).unwrap().1, 1);
+        assert_eq!(registry.handler(&100, 900000).unwrap().1, 2);
+    }
+}
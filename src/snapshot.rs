@@ -0,0 +1,421 @@
+use crate::balance_sheet::{load_sheet, PersistentRecord};
+use crate::proto::protorune::Output;
+use crate::registry::ProtocolRegistry;
+use crate::tables::{self, RuneTable};
+use crate::Protorune;
+use anyhow::{anyhow, Result};
+use bitcoin::hashes::Hash;
+use bitcoin::{Address, OutPoint, Script, Txid};
+use metashrew::flush;
+use metashrew::index_pointer::{AtomicPointer, IndexPointer, KeyValuePointer};
+use protobuf::Message;
+use protorune_support::balance_sheet::{BalanceSheet, ProtoruneRuneId};
+use protorune_support::commitment::RUNES_TAG;
+use protorune_support::constants::{GENESIS, NETWORK};
+use protorune_support::snapshot::{
+    OutpointEntry, ProtocolEntry, ProtocolRuneEntry, RuneEntry, Snapshot, StateEntry,
+};
+use protorune_support::utils::{consensus_decode, consensus_encode, field_to_name};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::Cursor;
+use std::str::FromStr;
+use std::sync::Arc;
+
+fn rune_entry(atomic: &AtomicPointer, name: &Arc<Vec<u8>>) -> Result<RuneEntry> {
+    let runes = &tables::RUNES;
+    let field = |ptr: &IndexPointer| atomic.derive(&ptr.select(name));
+    let id = ProtoruneRuneId::from_str(&String::from_utf8_lossy(
+        &field(&runes.ETCHING_TO_RUNE_ID).get(),
+    ))?;
+    let number = String::from_utf8_lossy(
+        &atomic
+            .derive(
+                &runes
+                    .RUNE_ID_TO_ETCHING
+                    .select(&id.to_string().into_bytes()),
+            )
+            .get(),
+    )
+    .parse::<u128>()?;
+    Ok(RuneEntry {
+        id,
+        number,
+        divisibility: field(&runes.DIVISIBILITY).get_value::<u8>(),
+        premine: field(&runes.PREMINE).get_value::<u128>(),
+        amount: field(&runes.AMOUNT).get_value::<u128>(),
+        cap: field(&runes.CAP).get_value::<u128>(),
+        mints_remaining: field(&runes.MINTS_REMAINING).get_value::<u128>(),
+        height_start: field(&runes.HEIGHTSTART).get_value::<u64>(),
+        height_end: field(&runes.HEIGHTEND).get_value::<u64>(),
+        offset_start: field(&runes.OFFSETSTART).get_value::<u64>(),
+        offset_end: field(&runes.OFFSETEND).get_value::<u64>(),
+        symbol: field(&runes.SYMBOL).get_value::<u32>(),
+        spacers: field(&runes.SPACERS).get_value::<u32>(),
+    })
+}
+
+/// Every key of the protocol of `table` holding anything, as of `height`. A key
+/// is logged under the height of each block whose handlers wrote it, and under
+/// the height of a snapshot that seeded it
+fn state_entries(atomic: &AtomicPointer, table: &RuneTable, height: u64) -> Vec<StateEntry> {
+    let mut keys = BTreeSet::<Arc<Vec<u8>>>::new();
+    for at in GENESIS as u64..=height {
+        keys.extend(
+            atomic
+                .derive(&table.STATE_WRITES.select_value::<u64>(at))
+                .get_list(),
+        );
+    }
+    keys.into_iter()
+        .map(|key| {
+            let state = atomic.derive(&table.STATE.select(&key));
+            StateEntry {
+                key: key.to_vec(),
+                value: state.get().to_vec(),
+                items: state.get_list().iter().map(|item| item.to_vec()).collect(),
+            }
+        })
+        .filter(|entry| !entry.value.is_empty() || !entry.items.is_empty())
+        .collect()
+}
+
+fn protocol_entry(
+    atomic: &AtomicPointer,
+    protocol_tag: u128,
+    runes: &[RuneEntry],
+    height: u64,
+) -> ProtocolEntry {
+    let table = RuneTable::for_protocol(protocol_tag);
+    // a protoburn appends the name of each rune it burns in, again every time
+    let mut names = HashSet::<Arc<Vec<u8>>>::new();
+    let etchings = atomic
+        .derive(&table.ETCHINGS)
+        .get_list()
+        .into_iter()
+        .filter(|name| names.insert(name.clone()))
+        .map(|name| {
+            let field = |ptr: &IndexPointer| atomic.derive(&ptr.select(&name));
+            let runeid = field(&table.ETCHING_TO_RUNE_ID).get();
+            ProtocolRuneEntry {
+                id: runeid.clone().into(),
+                internal: atomic
+                    .derive(&table.INTERNAL_MINT.select(&runeid))
+                    .get_value::<u8>()
+                    == 1,
+                name: name.to_vec(),
+                divisibility: field(&table.DIVISIBILITY).get_value::<u8>(),
+                symbol: field(&table.SYMBOL).get_value::<u32>(),
+                spacers: field(&table.SPACERS).get_value::<u32>(),
+            }
+        })
+        .collect();
+    let mut burned = BalanceSheet::default();
+    let mut unwrapped = BalanceSheet::default();
+    for rune in runes {
+        let runeid: Arc<Vec<u8>> = rune.id.into();
+        for (sheet, table) in [
+            (&mut burned, &table.BURNED),
+            (&mut unwrapped, &table.UNWRAPPED),
+        ] {
+            let amount = atomic.derive(&table.select(&runeid)).get_value::<u128>();
+            if amount != 0 {
+                sheet.set(&rune.id, amount);
+            }
+        }
+    }
+    ProtocolEntry {
+        protocol_tag,
+        sequence: atomic.derive(&table.SEQUENCE).get_value::<u128>(),
+        etchings,
+        runtime: load_sheet(&atomic.derive(&table.RUNTIME_BALANCE)),
+        burned,
+        unwrapped,
+        state: state_entries(atomic, &table, height),
+    }
+}
+
+/// Index in its block of a transaction a snapshot import seeded outputs of,
+/// which is not listed under its height
+pub fn seeded_txindex(txid: &Txid) -> Option<u32> {
+    txindex_in(&AtomicPointer::default(), txid)
+}
+
+fn txindex_in(atomic: &AtomicPointer, txid: &Txid) -> Option<u32> {
+    let txindex = atomic.derive(&tables::SNAPSHOT_TXINDEX.select(&txid.as_byte_array().to_vec()));
+    if txindex.get().is_empty() {
+        None
+    } else {
+        Some(txindex.get_value::<u32>())
+    }
+}
+
+/// Entry of `outpoint`, whose transaction is at `txindex` in its block, if it
+/// is unspent and holds anything
+fn outpoint_entry(
+    atomic: &AtomicPointer,
+    outpoint: OutPoint,
+    txindex: u32,
+    tags: &[u128],
+) -> Result<Option<OutpointEntry>> {
+    let key = consensus_encode(&outpoint)?;
+    if !atomic
+        .derive(&tables::OUTPOINT_SPENT_AT.select(&key))
+        .get()
+        .is_empty()
+    {
+        return Ok(None);
+    }
+    let balances = [RUNES_TAG]
+        .iter()
+        .chain(tags)
+        .map(|tag| {
+            let table = match *tag {
+                RUNES_TAG => tables::RUNES.clone(),
+                tag => RuneTable::for_protocol(tag),
+            };
+            (
+                *tag,
+                load_sheet(&atomic.derive(&table.OUTPOINT_TO_RUNES.select(&key))),
+            )
+        })
+        .filter(|(_, sheet)| sheet.balances.values().any(|balance| *balance != 0))
+        .collect::<Vec<(u128, BalanceSheet)>>();
+    if balances.is_empty() {
+        return Ok(None);
+    }
+    let output = Output::parse_from_bytes(
+        &atomic
+            .derive(&tables::OUTPOINT_TO_OUTPUT.select(&key))
+            .get(),
+    )?;
+    Ok(Some(OutpointEntry {
+        outpoint,
+        height: atomic
+            .derive(
+                &tables::RUNES
+                    .OUTPOINT_TO_HEIGHT
+                    .select(&outpoint.txid.as_byte_array().to_vec())
+                    .select_value::<u32>(outpoint.vout),
+            )
+            .get_value::<u64>(),
+        txindex,
+        script: output.script,
+        value: output.value,
+        balances,
+    }))
+}
+
+/// Snapshot of the index as read through `atomic`, which sees writes it has
+/// staged over what is stored
+fn collect(atomic: &AtomicPointer, tags: &[u128]) -> Result<Snapshot> {
+    let tip = atomic.derive(&tables::TIP_HEIGHT);
+    if tip.get().is_empty() {
+        return Err(anyhow!("nothing is indexed to snapshot"));
+    }
+    let height = tip.get_value::<u64>();
+    let mut snapshot = Snapshot {
+        height,
+        state_root: atomic
+            .derive(&tables::STATE_ROOT.select_value::<u64>(height))
+            .get()
+            .as_slice()
+            .try_into()
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+    let mut outpoints = BTreeMap::<OutPoint, OutpointEntry>::new();
+    // seeded outpoints are not listed under their heights
+    for key in atomic.derive(&tables::SNAPSHOT_OUTPOINTS).get_list() {
+        let outpoint = consensus_decode::<OutPoint>(&mut Cursor::new(key.to_vec()))?;
+        let txindex = txindex_in(atomic, &outpoint.txid).unwrap_or_default();
+        if let Some(entry) = outpoint_entry(atomic, outpoint, txindex, tags)? {
+            outpoints.insert(outpoint, entry);
+        }
+    }
+    for at in GENESIS as u64..=height {
+        // indexing a height again appends its runes and txids again
+        let mut names = HashSet::<Arc<Vec<u8>>>::new();
+        let mut txids = HashSet::<Arc<Vec<u8>>>::new();
+        for name in atomic
+            .derive(&tables::HEIGHT_TO_RUNES.select_value::<u64>(at))
+            .get_list()
+        {
+            if names.insert(name.clone()) {
+                snapshot.runes.push(rune_entry(atomic, &name)?);
+            }
+        }
+        let listed = atomic
+            .derive(
+                &tables::RUNES
+                    .HEIGHT_TO_TRANSACTION_IDS
+                    .select_value::<u64>(at),
+            )
+            .get_list()
+            .into_iter()
+            .filter(|txid| txids.insert(txid.clone()));
+        for (txindex, txid) in listed.enumerate() {
+            let txid = Txid::from_slice(&txid)?;
+            for vout in 0.. {
+                let outpoint = OutPoint::new(txid, vout);
+                let key = consensus_encode(&outpoint)?;
+                if atomic
+                    .derive(&tables::OUTPOINT_TO_OUTPUT.select(&key))
+                    .get()
+                    .is_empty()
+                {
+                    break;
+                }
+                if let Some(entry) = outpoint_entry(atomic, outpoint, txindex as u32, tags)? {
+                    outpoints.insert(outpoint, entry);
+                }
+            }
+        }
+    }
+    snapshot.outpoints = outpoints.into_values().collect();
+    snapshot.protocols = tags
+        .iter()
+        .map(|tag| protocol_entry(atomic, *tag, &snapshot.runes, height))
+        .collect();
+    Ok(snapshot)
+}
+
+/// Snapshot of the rune state at the tip: the etchings, terms and mint counters
+/// of base runes, what each protocol registered or burned into etched, holds in
+/// its runtime, keeps in its state and has burned in and unwrapped, and the
+/// balances of every unspent outpoint in the base table and the protocols'
+/// tables. Heights are walked from the runes genesis
+pub fn export_snapshot(registry: &ProtocolRegistry) -> Result<Snapshot> {
+    collect(&AtomicPointer::default(), &registry.indexed_tags())
+}
+
+/// Seeds an empty index with `snapshot`, so indexing carries on from the block
+/// after its height with the state root chaining on. The writes are staged and
+/// checked by snapshotting them again and comparing content hashes, so an
+/// import that fails leaves the index empty
+pub fn import_snapshot(snapshot: &Snapshot) -> Result<()> {
+    if !tables::TIP_HEIGHT.get().is_empty() {
+        return Err(anyhow!(
+            "the index holds blocks up to {}, a snapshot only seeds an empty index",
+            tables::TIP_HEIGHT.get_value::<u64>()
+        ));
+    }
+    let mut atomic = AtomicPointer::default();
+    let at = |ptr: &IndexPointer| atomic.derive(ptr);
+    let runes = &tables::RUNES;
+    for rune in &snapshot.runes {
+        let name = field_to_name(&rune.number).into_bytes();
+        let runeid = Protorune::build_rune_id(rune.id.block as u64, rune.id.tx as u32);
+        let field = |ptr: &IndexPointer| at(&ptr.select(&name));
+        at(&runes.RUNE_ID_TO_ETCHING.select(&runeid))
+            .set(Arc::new(rune.number.to_string().into_bytes()));
+        field(&runes.ETCHING_TO_RUNE_ID).set(runeid.clone());
+        at(&runes.RUNE_ID_TO_HEIGHT.select(&runeid)).set_value::<u64>(rune.id.block as u64);
+        field(&runes.DIVISIBILITY).set_value::<u8>(rune.divisibility);
+        field(&runes.PREMINE).set_value::<u128>(rune.premine);
+        field(&runes.AMOUNT).set_value::<u128>(rune.amount);
+        field(&runes.CAP).set_value::<u128>(rune.cap);
+        field(&runes.MINTS_REMAINING).set_value::<u128>(rune.mints_remaining);
+        field(&runes.HEIGHTSTART).set_value::<u64>(rune.height_start);
+        field(&runes.HEIGHTEND).set_value::<u64>(rune.height_end);
+        field(&runes.OFFSETSTART).set_value::<u64>(rune.offset_start);
+        field(&runes.OFFSETEND).set_value::<u64>(rune.offset_end);
+        field(&runes.SYMBOL).set_value::<u32>(rune.symbol);
+        field(&runes.SPACERS).set_value::<u32>(rune.spacers);
+        field(&runes.ETCHINGS).append(Arc::new(name.clone()));
+        at(&tables::HEIGHT_TO_RUNES.select_value::<u64>(rune.id.block as u64))
+            .append(Arc::new(name));
+    }
+    for protocol in &snapshot.protocols {
+        let table = RuneTable::for_protocol(protocol.protocol_tag);
+        // indexed whether or not the importing indexer registers it
+        at(&tables::PROTOCOL_TAG_SEEN.select_value::<u128>(protocol.protocol_tag))
+            .set_value::<u8>(1);
+        at(&tables::PROTOCOL_TAGS).append_value::<u128>(protocol.protocol_tag);
+        at(&table.SEQUENCE).set_value::<u128>(protocol.sequence);
+        for etching in &protocol.etchings {
+            let runeid: Arc<Vec<u8>> = etching.id.into();
+            let field = |ptr: &IndexPointer| at(&ptr.select(&etching.name));
+            at(&table.RUNE_ID_TO_ETCHING.select(&runeid)).set(Arc::new(etching.name.clone()));
+            field(&table.ETCHING_TO_RUNE_ID).set(runeid.clone());
+            field(&table.DIVISIBILITY).set_value::<u8>(etching.divisibility);
+            field(&table.SPACERS).set_value::<u32>(etching.spacers);
+            field(&table.SYMBOL).set_value::<u32>(etching.symbol);
+            if etching.internal {
+                at(&table.INTERNAL_MINT.select(&runeid)).set_value::<u8>(1);
+            }
+            at(&table.ETCHINGS).append(Arc::new(etching.name.clone()));
+        }
+        protocol.runtime.save(&at(&table.RUNTIME_BALANCE), false);
+        for (sheet, ptr) in [
+            (&protocol.burned, &table.BURNED),
+            (&protocol.unwrapped, &table.UNWRAPPED),
+        ] {
+            for (rune, amount) in &sheet.balances {
+                let runeid: Arc<Vec<u8>> = (*rune).into();
+                at(&ptr.select(&runeid)).set_value::<u128>(*amount);
+            }
+        }
+        let writes = at(&table.STATE_WRITES.select_value::<u64>(snapshot.height));
+        for entry in &protocol.state {
+            let mut state = at(&table.STATE.select(&entry.key));
+            if !entry.value.is_empty() {
+                state.set(Arc::new(entry.value.clone()));
+            }
+            for item in &entry.items {
+                state.append(Arc::new(item.clone()));
+            }
+            writes.append(Arc::new(entry.key.clone()));
+        }
+    }
+    for entry in &snapshot.outpoints {
+        let key = consensus_encode(&entry.outpoint)?;
+        let txid = entry.outpoint.txid.as_byte_array().to_vec();
+        for (tag, sheet) in &entry.balances {
+            let table = match *tag {
+                RUNES_TAG => tables::RUNES.clone(),
+                tag => RuneTable::for_protocol(tag),
+            };
+            sheet.save(&at(&table.OUTPOINT_TO_RUNES.select(&key)), false);
+        }
+        let output = Output {
+            script: entry.script.clone(),
+            value: entry.value,
+            ..Default::default()
+        };
+        at(&tables::OUTPOINT_TO_OUTPUT.select(&key)).set(Arc::new(output.write_to_bytes()?));
+        at(&runes
+            .OUTPOINT_TO_HEIGHT
+            .select(&txid)
+            .select_value::<u32>(entry.outpoint.vout))
+        .set_value::<u64>(entry.height);
+        // only the transactions still holding runes are known, so they can't be
+        // listed under their height in block order. Their outputs are listed in
+        // SNAPSHOT_OUTPOINTS and their indexes in the block kept apart
+        at(&tables::SNAPSHOT_TXINDEX.select(&txid)).set_value::<u32>(entry.txindex);
+        at(&tables::SNAPSHOT_OUTPOINTS).append(Arc::new(key.clone()));
+        if let Ok(address) = Address::from_script(Script::from_bytes(&entry.script), NETWORK) {
+            let address = address.to_string().into_bytes();
+            at(&tables::OUTPOINTS_FOR_ADDRESS.select(&address)).append(Arc::new(key.clone()));
+            at(&tables::OUTPOINT_SPENDABLE_BY.select(&key)).set(Arc::new(address));
+        }
+    }
+    if snapshot.state_root != [0; 32] {
+        at(&tables::STATE_ROOT.select_value::<u64>(snapshot.height))
+            .set(Arc::new(snapshot.state_root.to_vec()));
+    }
+    at(&tables::TIP_HEIGHT).set_value::<u64>(snapshot.height);
+    let tags = snapshot
+        .protocols
+        .iter()
+        .map(|protocol| protocol.protocol_tag)
+        .collect::<Vec<u128>>();
+    if collect(&atomic, &tags)?.content_hash() != snapshot.content_hash() {
+        atomic.rollback();
+        return Err(anyhow!(
+            "the imported state does not match the snapshot's content hash"
+        ));
+    }
+    atomic.commit();
+    flush();
+    Ok(())
+}
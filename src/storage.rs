@@ -0,0 +1,158 @@
+use crate::fuel::{FuelMeter, BYTE_FUEL, READ_FUEL, WRITE_FUEL};
+use crate::tables::RuneTable;
+use metashrew::index_pointer::{AtomicPointer, KeyValuePointer};
+use metashrew_support::byte_view::ByteView;
+use std::sync::Arc;
+
+/// Key-value storage private to one protocol, rooted at
+/// `/runes/proto/{tag}/state/`. Keys can only be extended from the root, so a
+/// handler can't write outside of its own protocol's state. Every read and
+/// write draws on the message's fuel, once it runs dry reads come back empty
+/// and writes are dropped. The key of every write is logged under the height
+/// of the block, for the state root to commit to what the block touched
+#[derive(Clone)]
+pub struct ProtocolStorage {
+    pointer: AtomicPointer,
+    /// the key of `pointer` below the protocol's state root
+    key: Vec<u8>,
+    writes: AtomicPointer,
+    fuel: FuelMeter,
+}
+
+impl ProtocolStorage {
+    pub(crate) fn new(
+        atomic: &AtomicPointer,
+        protocol_tag: u128,
+        height: u64,
+        fuel: &FuelMeter,
+    ) -> Self {
+        let table = RuneTable::for_protocol(protocol_tag);
+        ProtocolStorage {
+            pointer: atomic.derive(&table.STATE),
+            key: vec![],
+            writes: atomic.derive(&table.STATE_WRITES.select_value::<u64>(height)),
+            fuel: fuel.clone(),
+        }
+    }
+    fn with_key(&self, pointer: AtomicPointer, key: &[u8]) -> Self {
+        let mut storage = self.clone();
+        storage.pointer = pointer;
+        storage.key.extend(key);
+        storage
+    }
+    fn log_write(&self) {
+        self.writes.append(Arc::new(self.key.clone()));
+    }
+    pub fn select(&self, key: &Vec<u8>) -> Self {
+        self.with_key(self.pointer.select(key), key)
+    }
+    pub fn keyword(&self, word: &str) -> Self {
+        self.with_key(self.pointer.keyword(word), word.as_bytes())
+    }
+    pub fn select_value<T: ByteView>(&self, key: T) -> Self {
+        let key = key.to_bytes();
+        self.with_key(self.pointer.select(&key), &key)
+    }
+    pub fn get(&self) -> Arc<Vec<u8>> {
+        match self.fuel.consume(READ_FUEL) {
+            Ok(_) => self.pointer.get(),
+            Err(_) => Arc::new(vec![]),
+        }
+    }
+    pub fn set(&mut self, v: Arc<Vec<u8>>) {
+        if self
+            .fuel
+            .consume(WRITE_FUEL + BYTE_FUEL * v.len() as u64)
+            .is_ok()
+        {
+            self.log_write();
+            self.pointer.set(v)
+        }
+    }
+    pub fn get_value<T: ByteView>(&self) -> T {
+        let value = self.get();
+        if value.is_empty() {
+            T::zero()
+        } else {
+            T::from_bytes(value.as_ref().clone())
+        }
+    }
+    pub fn set_value<T: ByteView>(&mut self, v: T) {
+        self.set(Arc::new(v.to_bytes()))
+    }
+    pub fn append(&self, v: Arc<Vec<u8>>) {
+        if self
+            .fuel
+            .consume(READ_FUEL + WRITE_FUEL * 2 + BYTE_FUEL * v.len() as u64)
+            .is_ok()
+        {
+            self.log_write();
+            self.pointer.append(v)
+        }
+    }
+    pub fn length(&self) -> u32 {
+        match self.fuel.consume(READ_FUEL) {
+            Ok(_) => self.pointer.length(),
+            Err(_) => 0,
+        }
+    }
+    pub fn get_list(&self) -> Vec<Arc<Vec<u8>>> {
+        (0..self.length())
+            .map(|i| {
+                let mut item = self.clone();
+                item.pointer = self.pointer.select_index(i);
+                item.get()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::MessageContextParcel;
+    use crate::tables;
+    use metashrew::clear;
+    use metashrew::index_pointer::IndexPointer;
+
+    #[test]
+    fn test_storage_is_rooted_in_protocol_state() {
+        clear();
+        let mut atomic = AtomicPointer::default();
+        let parcel = MessageContextParcel {
+            atomic: atomic.clone(),
+            protocol_tag: 122,
+            ..Default::default()
+        };
+        parcel
+            .storage()
+            .select(&b"counter".to_vec())
+            .set_value::<u64>(7);
+        atomic.commit();
+        assert_eq!(
+            IndexPointer::from_keyword("/runes/proto/122/state/counter").get_value::<u64>(),
+            7
+        );
+        // another protocol sees none of it
+        let other = MessageContextParcel {
+            protocol_tag: 123,
+            ..parcel.clone()
+        };
+        assert_eq!(
+            other
+                .storage()
+                .select(&b"counter".to_vec())
+                .get_value::<u64>(),
+            0
+        );
+        // the core tables stay readable
+        tables::RUNES
+            .ETCHINGS
+            .clone()
+            .set(Arc::new(b"TESTTESTTEST".to_vec()));
+        assert_eq!(
+            parcel.read(&tables::RUNES.ETCHINGS).as_ref(),
+            &b"TESTTESTTEST".to_vec()
+        );
+    }
+}
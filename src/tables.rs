@@ -26,6 +26,15 @@ pub struct RuneTable {
     pub ETCHING_TO_RUNE_ID: IndexPointer,
     pub RUNTIME_BALANCE: IndexPointer,
     pub INTERNAL_MINT: IndexPointer,
+    pub BURNED: IndexPointer,
+    pub UNWRAPPED: IndexPointer,
+    pub SEQUENCE: IndexPointer,
+    pub MINTS: IndexPointer,
+    pub EVENTS: IndexPointer,
+    pub STATE: IndexPointer,
+    pub STATE_WRITES: IndexPointer,
+    pub OUTCOMES: IndexPointer,
+    pub FLAWS: IndexPointer,
     /*
 
     pub HEIGHT_TO_BLOCKHASH: IndexPointer::from_keyword("/blockhash/byheight/"),
@@ -76,6 +85,15 @@ impl RuneTable {
             ETCHING_TO_RUNE_ID: IndexPointer::from_keyword("/runeid/byetching/"),
             RUNTIME_BALANCE: IndexPointer::from_keyword("/runes/null"),
             INTERNAL_MINT: IndexPointer::from_keyword("/runes/null"),
+            BURNED: IndexPointer::from_keyword("/runes/null"),
+            UNWRAPPED: IndexPointer::from_keyword("/runes/null"),
+            SEQUENCE: IndexPointer::from_keyword("/runes/null"),
+            MINTS: IndexPointer::from_keyword("/runes/null"),
+            EVENTS: IndexPointer::from_keyword("/runes/null"),
+            STATE: IndexPointer::from_keyword("/runes/null"),
+            STATE_WRITES: IndexPointer::from_keyword("/runes/null"),
+            OUTCOMES: IndexPointer::from_keyword("/runes/null"),
+            FLAWS: IndexPointer::from_keyword("/runes/null"),
         }
     }
     pub fn for_protocol(tag: u128) -> Self {
@@ -116,6 +134,27 @@ impl RuneTable {
             INTERNAL_MINT: IndexPointer::from_keyword(
                 format!("/runes/proto/{tag}/mint/isinternal").as_str(),
             ),
+            BURNED: IndexPointer::from_keyword(format!("/runes/proto/{tag}/burned/").as_str()),
+            UNWRAPPED: IndexPointer::from_keyword(
+                format!("/runes/proto/{tag}/unwrapped/").as_str(),
+            ),
+            SEQUENCE: IndexPointer::from_keyword(format!("/runes/proto/{tag}/sequence").as_str()),
+            MINTS: IndexPointer::from_keyword(
+                format!("/runes/proto/{tag}/mint/bymessage/").as_str(),
+            ),
+            EVENTS: IndexPointer::from_keyword(
+                format!("/runes/proto/{tag}/events/bymessage/").as_str(),
+            ),
+            STATE: IndexPointer::from_keyword(format!("/runes/proto/{tag}/state/").as_str()),
+            STATE_WRITES: IndexPointer::from_keyword(
+                format!("/runes/proto/{tag}/statewrites/byheight/").as_str(),
+            ),
+            OUTCOMES: IndexPointer::from_keyword(
+                format!("/runes/proto/{tag}/outcome/bymessage/").as_str(),
+            ),
+            FLAWS: IndexPointer::from_keyword(
+                format!("/runes/proto/{tag}/flaw/byprotostone/").as_str(),
+            ),
         }
     }
 }
@@ -130,5 +169,34 @@ pub static OUTPOINTS_FOR_ADDRESS: Lazy<IndexPointer> =
 
 pub static OUTPOINT_SPENDABLE_BY: Lazy<IndexPointer> =
     Lazy::new(|| IndexPointer::from_keyword("/outpoint/spendableby/"));
+/// the tag of every protocol anything was protoburned into, in the order first seen
+pub static PROTOCOL_TAGS: Lazy<IndexPointer> =
+    Lazy::new(|| IndexPointer::from_keyword("/runes/proto/tags"));
+/// set for each tag listed in `PROTOCOL_TAGS`
+pub static PROTOCOL_TAG_SEEN: Lazy<IndexPointer> =
+    Lazy::new(|| IndexPointer::from_keyword("/runes/proto/seen/bytag/"));
+/// the txid of the transaction that spent an outpoint
+pub static OUTPOINT_SPENT_BY: Lazy<IndexPointer> =
+    Lazy::new(|| IndexPointer::from_keyword("/outpoint/spentby/"));
+/// the height of the block that spent an outpoint
+pub static OUTPOINT_SPENT_AT: Lazy<IndexPointer> =
+    Lazy::new(|| IndexPointer::from_keyword("/outpoint/spentat/"));
+/// the state root committed for each height indexed
+pub static STATE_ROOT: Lazy<IndexPointer> =
+    Lazy::new(|| IndexPointer::from_keyword("/stateroot/byheight/"));
+/// the leaf hashes of each height's state root, concatenated in order
+pub static STATE_LEAVES: Lazy<IndexPointer> =
+    Lazy::new(|| IndexPointer::from_keyword("/stateroot/leaves/byheight/"));
+/// the outpoints a snapshot import seeded, whose transactions' other outputs
+/// and positions in their blocks are not known, so they are not listed in
+/// `HEIGHT_TO_TRANSACTION_IDS`
+pub static SNAPSHOT_OUTPOINTS: Lazy<IndexPointer> =
+    Lazy::new(|| IndexPointer::from_keyword("/snapshot/outpoints"));
+/// the index in its block of each transaction a snapshot import seeded outputs of
+pub static SNAPSHOT_TXINDEX: Lazy<IndexPointer> =
+    Lazy::new(|| IndexPointer::from_keyword("/snapshot/txindex/bytxid/"));
+/// the height of the last block indexed
+pub static TIP_HEIGHT: Lazy<IndexPointer> = Lazy::new(|| IndexPointer::from_keyword("/height/tip"));
+
 pub static OUTPOINT_TO_OUTPUT: Lazy<IndexPointer> =
     Lazy::new(|| IndexPointer::from_keyword("/output/byoutpoint/"));
@@ -14,6 +14,7 @@ use std::sync::Arc;
 use crate::protostone::{Protostone, Protostones};
 
 pub const ADDRESS1: &'static str = "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu";
+pub const ADDRESS2: &str = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
 
 pub fn display_vec_as_hex(data: Vec<u8>) -> String {
     let mut hex_string = String::new();
@@ -496,6 +497,9 @@ pub fn create_protoburn_transaction(previous_output: OutPoint, protocol_id: u128
             refund: None,
             from: None,
             protocol_tag: 13, // this value must be 13 if protoburn
+            unwrap: None,
+            flaw: None,
+            predicate: None,
             message: vec![],
         }]
         .encipher()
@@ -520,6 +524,55 @@ pub fn create_protoburn_transaction(previous_output: OutPoint, protocol_id: u128
     }
 }
 
+/// Etch 1000 runes to the OP_RETURN and run the given protostones against them,
+/// so the first protostone can protoburn the premine
+pub fn create_etching_protostone_transaction(
+    previous_output: OutPoint,
+    protostones: Vec<Protostone>,
+) -> Transaction {
+    let txin = TxIn {
+        previous_output,
+        script_sig: ScriptBuf::new(),
+        sequence: Sequence::MAX,
+        witness: Witness::new(),
+    };
+
+    let txout = TxOut {
+        value: Amount::from_sat(100_000_000).to_sat(),
+        script_pubkey: get_address(ADDRESS1).script_pubkey(),
+    };
+
+    let runestone: ScriptBuf = (Runestone {
+        etching: Some(Etching {
+            divisibility: Some(2),
+            premine: Some(1000),
+            rune: Some(Rune::from_str("TESTTESTTEST").unwrap()),
+            spacers: Some(0),
+            symbol: Some(char::from_str("A").unwrap()),
+            turbo: true,
+            terms: None,
+        }),
+        pointer: Some(1), // points to the OP_RETURN, so therefore targets the protoburn
+        edicts: Vec::new(),
+        mint: None,
+        protocol: protostones.encipher().ok(),
+    })
+    .encipher();
+
+    // op return is at output 1
+    let op_return = TxOut {
+        value: Amount::from_sat(0).to_sat(),
+        script_pubkey: runestone,
+    };
+
+    Transaction {
+        version: 1,
+        lock_time: bitcoin::absolute::LockTime::ZERO,
+        input: vec![txin],
+        output: vec![txout, op_return],
+    }
+}
+
 // pub fn create_block_with_protoburn() {
 //     let config = RunesTestingConfig::new(
 //         "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
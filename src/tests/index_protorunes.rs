@@ -1,25 +1,52 @@
 #[cfg(test)]
 mod tests {
-    use crate::message::{MessageContext, MessageContextParcel};
-    use crate::protostone::{Protostone, Protostones};
-    use crate::test_helpers::{self as helpers, get_address, ADDRESS1};
+    use crate::chain_builder::ChainBuilder;
+    use crate::commitment;
+    use crate::conservation::{check_conservation, Violation};
+    use crate::etching::{etch, mint, ProtoruneEtching, PROTOCOL_ETCHING_BLOCK};
+    use crate::flaw::ProtostoneFlaw;
+    use crate::message::{MessageContext, MessageContextParcel, MessageOutcome};
+    use crate::predicate::{Clause, ClauseTarget, Predicate};
+    use crate::proto::protorune::{
+        OutpointWithProtocol, ProtocolViewRequest, ProtorunesWalletRequest, StateRootRequest,
+        WalletRequest,
+    };
+    use crate::protostone::{
+        protostone_vout, split_bytes, Protostone, ProtostoneEdict, Protostones,
+    };
+    use crate::registry::ProtocolRegistry;
+    use crate::snapshot::{export_snapshot, import_snapshot};
+    use crate::test_helpers::{self as helpers, get_address, ADDRESS1, ADDRESS2};
+    use crate::view::{self, protocol_view, simulate_transaction, ViewContext};
     use crate::{tables, Protorune};
-    use anyhow::Result;
-    use bitcoin::Transaction;
+    use anyhow::{anyhow, Result};
+    use bitcoin::hashes::Hash;
     use bitcoin::{
         address::NetworkChecked, Address, Amount, OutPoint, ScriptBuf, Sequence, TxIn, TxOut,
         Witness,
     };
+    use bitcoin::{Block, Transaction};
     use protorune::balance_sheet::load_sheet;
     use protorune_support::balance_sheet::{BalanceSheet, ProtoruneRuneId};
-    use protorune_support::rune_transfer::RuneTransfer;
-    use protorune_support::utils::consensus_encode;
+    use protorune_support::commitment::{
+        chain_root, etching_key, leaf_hash, merkle_root, mint_key, outpoint_key, state_key,
+        verify_outpoint, MerkleProof, OutpointProof, RUNES_TAG,
+    };
+    use protorune_support::constants::GENESIS;
+    use protorune_support::rune_transfer::{MessageResult, RuneTransfer};
+    use protorune_support::snapshot::{Snapshot, SNAPSHOT_MAGIC};
+    use protorune_support::utils::{consensus_encode, encode_varint_list};
 
+    use metashrew::index_pointer::AtomicPointer;
     use metashrew::{clear, get_cache, index_pointer::KeyValuePointer, println, stdio::stdout};
+    use metashrew_support::byte_view::ByteView;
     use metashrew_support::utils::format_key;
-    use ordinals::{Etching, Rune, Runestone};
+    use ordinals::{Etching, Rune, Runestone, Terms};
+    use protobuf::Message;
+    use std::collections::HashMap;
     use std::fmt::Write;
     use std::str::FromStr;
+    use std::sync::Arc;
     use wasm_bindgen_test::wasm_bindgen_test;
 
     struct TestMessageContext(());
@@ -29,7 +56,7 @@ mod tests {
             122
         }
         // takes half of the first runes balance
-        fn handle(parcel: &MessageContextParcel) -> Result<(Vec<RuneTransfer>, BalanceSheet)> {
+        fn handle(parcel: &MessageContextParcel) -> Result<MessageResult> {
             let mut new_runtime_balances = parcel.runtime_balances.clone();
             let mut runes = parcel.runes.clone();
             runes[0].value = runes[0].value / 2;
@@ -37,7 +64,181 @@ mod tests {
             <BalanceSheet as TryFrom<Vec<RuneTransfer>>>::try_from(runes)?
                 .pipe(&mut new_runtime_balances);
             // transfer protorunes to the pointer
-            Ok((vec![transfer], *new_runtime_balances))
+            Ok((vec![transfer], *new_runtime_balances).into())
+        }
+    }
+
+    struct MintingMessageContext(());
+
+    impl MessageContext for MintingMessageContext {
+        fn protocol_tag() -> u128 {
+            123
+        }
+        // etches a protocol token and sends 1000 of it to the pointer
+        fn handle(parcel: &MessageContextParcel) -> Result<MessageResult> {
+            let rune = etch(
+                parcel,
+                &ProtoruneEtching {
+                    name: String::from("LPTOKEN"),
+                    divisibility: 8,
+                    symbol: Some('L'),
+                    spacers: 0,
+                },
+            )?;
+            let transfer = mint(parcel, &rune, 1000)?;
+            Ok((vec![transfer], *parcel.runtime_balances.clone()).into())
+        }
+    }
+
+    struct SplittingMessageContext(());
+
+    impl MessageContext for SplittingMessageContext {
+        fn protocol_tag() -> u128 {
+            124
+        }
+        // keeps a tenth as a fee and splits the rest between the pointer and the
+        // protostone after it, calldata picks an invalid split instead
+        fn handle(parcel: &MessageContextParcel) -> Result<MessageResult> {
+            let incoming = parcel.runes[0];
+            let tenth = incoming.value / 10;
+            let mut runtime = *parcel.runtime_balances.clone();
+            runtime.increase(&incoming.id, tenth);
+            let split = |value: u128| RuneTransfer {
+                id: incoming.id,
+                value,
+            };
+            let next = parcel.vout + 1;
+            Ok(match parcel.calldata[0] {
+                // sends on more than it was sent
+                2 => MessageResult {
+                    transfers: vec![split(tenth * 6)],
+                    allocations: vec![(next, split(tenth * 4))],
+                    runtime,
+                    events: vec![],
+                },
+                // sends runes back to its own protostone
+                3 => MessageResult {
+                    transfers: vec![split(tenth * 6)],
+                    allocations: vec![(parcel.vout, split(tenth * 3))],
+                    runtime,
+                    events: vec![],
+                },
+                _ => MessageResult {
+                    transfers: vec![split(tenth * 6)],
+                    allocations: vec![(next, split(tenth * 3))],
+                    runtime,
+                    events: vec![b"split".to_vec()],
+                },
+            })
+        }
+    }
+
+    struct AuthorizingMessageContext(());
+
+    impl MessageContext for AuthorizingMessageContext {
+        fn protocol_tag() -> u128 {
+            125
+        }
+        // passes its runes on only when the first input is spent by ADDRESS1
+        fn handle(parcel: &MessageContextParcel) -> Result<MessageResult> {
+            let input = &parcel.inputs[0];
+            if input.spendable_by.as_deref() != Some(ADDRESS1)
+                || input.script != get_address(ADDRESS1).script_pubkey()
+                || input.value != 100_000_000
+            {
+                return Err(anyhow!("input is not spent by {}", ADDRESS1));
+            }
+            Ok((parcel.runes.clone(), *parcel.runtime_balances.clone()).into())
+        }
+    }
+
+    struct StoringMessageContext(());
+
+    impl MessageContext for StoringMessageContext {
+        fn protocol_tag() -> u128 {
+            126
+        }
+        // records each of 50 slots in its storage, then passes its runes on
+        fn handle(parcel: &MessageContextParcel) -> Result<MessageResult> {
+            let storage = parcel.storage();
+            for slot in 0..50u32 {
+                storage.select_value::<u32>(slot).set_value::<u64>(1);
+            }
+            Ok((parcel.runes.clone(), *parcel.runtime_balances.clone()).into())
+        }
+    }
+
+    impl ViewContext for StoringMessageContext {
+        // method 0 reads back the slot given as input
+        fn view(method: u32, input: &[u8]) -> Result<Vec<u8>> {
+            match method {
+                0 => Ok(tables::RuneTable::for_protocol(Self::protocol_tag())
+                    .STATE
+                    .select_value::<u32>(u32::from_bytes(input.to_vec()))
+                    .get()
+                    .as_ref()
+                    .clone()),
+                _ => Err(anyhow!("unknown view method {}", method)),
+            }
+        }
+    }
+
+    struct VaultMessageContext(());
+
+    impl MessageContext for VaultMessageContext {
+        fn protocol_tag() -> u128 {
+            127
+        }
+        // keeps a tenth of what it is sent and returns the rest, calldata 2
+        // fails after writing to its storage
+        fn handle(parcel: &MessageContextParcel) -> Result<MessageResult> {
+            parcel
+                .storage()
+                .select(&b"deposits".to_vec())
+                .set_value::<u64>(1);
+            if parcel.calldata[0] == 2 {
+                return Err(anyhow!("vault is closed"));
+            }
+            let incoming = parcel.runes[0];
+            let mut runtime = *parcel.runtime_balances.clone();
+            runtime.increase(&incoming.id, incoming.value / 10);
+            Ok((
+                vec![RuneTransfer {
+                    id: incoming.id,
+                    value: incoming.value - incoming.value / 10,
+                }],
+                runtime,
+            )
+                .into())
+        }
+    }
+
+    struct RoutingMessageContext(());
+
+    impl MessageContext for RoutingMessageContext {
+        fn protocol_tag() -> u128 {
+            128
+        }
+        // deposits its runes into the vault and sends on what comes back, or
+        // everything it was sent when the vault call fails. Calldata 3 also
+        // tries to deposit a protorune etched by the router
+        fn handle(parcel: &MessageContextParcel) -> Result<MessageResult> {
+            let mut deposit = parcel.runes.clone();
+            if parcel.calldata[0] == 3 {
+                deposit.push(RuneTransfer {
+                    id: ProtoruneRuneId::new(PROTOCOL_ETCHING_BLOCK, 1),
+                    value: 1,
+                });
+            }
+            let runes = match parcel.call(
+                VaultMessageContext::protocol_tag(),
+                parcel.calldata.clone(),
+                deposit,
+            ) {
+                Ok(result) => result.transfers,
+                Err(_) => parcel.runes.clone(),
+            };
+            Ok((runes, *parcel.runtime_balances.clone()).into())
         }
     }
 
@@ -167,6 +368,9 @@ mod tests {
                     refund: None,
                     from: None,
                     protocol_tag: 13, // this value must be 13 if protoburn
+                    unwrap: None,
+                    flaw: None,
+                    predicate: None,
                     message: vec![],
                 },
                 Protostone {
@@ -178,6 +382,9 @@ mod tests {
                     from: None,
                     burn: None,
                     protocol_tag: protocol_id as u128,
+                    unwrap: None,
+                    flaw: None,
+                    predicate: None,
                 },
             ]
             .encipher()
@@ -234,4 +441,1395 @@ mod tests {
         let stored_protorune_balance = protorunes_sheet.get(&protorune_id);
         assert_eq!(stored_protorune_balance, 500);
     }
+
+    /// Indexes a block with one transaction that etches 1000 runes and runs `protostones`
+    /// against them, returning the protorune balance left on output 0
+    fn index_etching_protostones(protostones: Vec<Protostone>) -> u128 {
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<TestMessageContext>().unwrap();
+        index_etching_protostones_with_registry(protostones, &registry)
+    }
+
+    fn index_etching_protostones_with_registry(
+        protostones: Vec<Protostone>,
+        registry: &ProtocolRegistry,
+    ) -> u128 {
+        let test_block = index_etching_block(protostones, registry);
+        balance_on_output_0(
+            &tables::RuneTable::for_protocol(TestMessageContext::protocol_tag()),
+            &test_block,
+        )
+    }
+
+    fn index_etching_block(protostones: Vec<Protostone>, registry: &ProtocolRegistry) -> Block {
+        clear();
+        let block_height = 840000;
+        let mut test_block = helpers::create_block_with_coinbase_tx(block_height);
+
+        let previous_output = OutPoint {
+            txid: bitcoin::Txid::from_str(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            )
+            .unwrap(),
+            vout: 0,
+        };
+
+        test_block
+            .txdata
+            .push(helpers::create_etching_protostone_transaction(
+                previous_output,
+                protostones,
+            ));
+        assert!(Protorune::index_block_with_registry(
+            test_block.clone(),
+            block_height as u64,
+            registry
+        )
+        .is_ok());
+        test_block
+    }
+
+    /// Balance of the etched rune on output 0 of the etching transaction in `table`
+    fn balance_on_output_0(table: &tables::RuneTable, test_block: &Block) -> u128 {
+        let outpoint_address: OutPoint = OutPoint {
+            txid: test_block.txdata[1].txid(),
+            vout: 0,
+        };
+        let sheet = load_sheet(
+            &table
+                .OUTPOINT_TO_RUNES
+                .select(&consensus_encode(&outpoint_address).unwrap()),
+        );
+        sheet.get(&ProtoruneRuneId::new(840000, 1))
+    }
+
+    /// Protoburn the premine into the protostone at `pointer`
+    fn protoburn_to(pointer: u32) -> Protostone {
+        Protostone {
+            burn: Some(TestMessageContext::protocol_tag()),
+            edicts: vec![],
+            pointer: Some(pointer),
+            refund: None,
+            from: None,
+            protocol_tag: 13,
+            unwrap: None,
+            flaw: None,
+            predicate: None,
+            message: vec![],
+        }
+    }
+
+    fn protomessage(pointer: u32, predicate: Option<Predicate>) -> Protostone {
+        Protostone {
+            message: vec![1u8],
+            pointer: Some(pointer),
+            refund: Some(0),
+            edicts: vec![],
+            from: None,
+            burn: None,
+            protocol_tag: TestMessageContext::protocol_tag(),
+            unwrap: None,
+            flaw: None,
+            predicate,
+        }
+    }
+
+    /// Burns into the first message, whose result feeds the second message through its
+    /// virtual vout before landing on output 0
+    #[wasm_bindgen_test]
+    fn protomessage_chain_test() {
+        // two real outputs, so the protostones sit at virtual vouts 3, 4 and 5
+        let balance = index_etching_protostones(vec![
+            protoburn_to(4),
+            protomessage(5, None),
+            protomessage(0, None),
+        ]);
+        // each message keeps half of what it receives
+        assert_eq!(balance, 250);
+    }
+
+    /// A protostone may not point back at an earlier protostone. It is flagged and
+    /// inert, what reaches it goes on to the runestone's default output, and the
+    /// etching and protoburn of the runestone still stand
+    #[wasm_bindgen_test]
+    fn protomessage_backward_pointer_test() {
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<TestMessageContext>().unwrap();
+        let test_block =
+            index_etching_block(vec![protoburn_to(4), protomessage(3, None)], &registry);
+        let table = tables::RuneTable::for_protocol(TestMessageContext::protocol_tag());
+        let txid = test_block.txdata[1].txid();
+        let message = consensus_encode(&OutPoint { txid, vout: 4 }).unwrap();
+        assert_eq!(
+            table.FLAWS.select(&message).get_value::<u8>(),
+            ProtostoneFlaw::TargetOutput as u8
+        );
+        assert_eq!(table.OUTCOMES.select(&message).get_value::<u8>(), 0);
+        assert_eq!(balance_on_output_0(&table, &test_block), 0);
+        // the default output here is the OP_RETURN, where the protorunes stay burned
+        let rune = ProtoruneRuneId::new(840000, 1);
+        let runeid: Arc<Vec<u8>> = rune.into();
+        assert_eq!(table.BURNED.select(&runeid).get_value::<u128>(), 1000);
+        let etching = tables::RUNES
+            .RUNE_ID_TO_ETCHING
+            .select(&Protorune::build_rune_id(840000, 1));
+        assert!(!etching.get().is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    fn protomessage_predicate_satisfied_test() {
+        let rune = ProtoruneRuneId::new(840000, 1);
+        let balance = index_etching_protostones(vec![
+            protoburn_to(4),
+            protomessage(
+                0,
+                Some(Predicate {
+                    clauses: vec![
+                        Clause {
+                            target: ClauseTarget::Incoming,
+                            id: rune,
+                            amount: 1000,
+                        },
+                        Clause {
+                            target: ClauseTarget::Outgoing,
+                            id: rune,
+                            amount: 500,
+                        },
+                    ],
+                }),
+            ),
+        ]);
+        assert_eq!(balance, 500);
+    }
+
+    /// The handler only sends on half, so a minimum outgoing amount above that
+    /// refunds everything to the refund pointer
+    #[wasm_bindgen_test]
+    fn protomessage_predicate_refund_test() {
+        let balance = index_etching_protostones(vec![
+            protoburn_to(4),
+            protomessage(
+                0,
+                Some(Predicate {
+                    clauses: vec![Clause {
+                        target: ClauseTarget::Outgoing,
+                        id: ProtoruneRuneId::new(840000, 1),
+                        amount: 600,
+                    }],
+                }),
+            ),
+        ]);
+        assert_eq!(balance, 1000);
+    }
+
+    /// Before activation the protoburn is only a carry, so nothing reaches the
+    /// protocol and the runes stay burned on the OP_RETURN
+    #[wasm_bindgen_test]
+    fn protoburn_before_activation_test() {
+        let mut registry = ProtocolRegistry::new();
+        registry.register_at::<TestMessageContext>(840001).unwrap();
+        let balance = index_etching_protostones_with_registry(
+            vec![protoburn_to(4), protomessage(0, None)],
+            &registry,
+        );
+        assert_eq!(balance, 0);
+
+        let mut registry = ProtocolRegistry::new();
+        registry.register_at::<TestMessageContext>(840000).unwrap();
+        let balance = index_etching_protostones_with_registry(
+            vec![protoburn_to(4), protomessage(0, None)],
+            &registry,
+        );
+        assert_eq!(balance, 500);
+    }
+
+    /// Unwraps the burned premine back into base runes on output 0
+    #[wasm_bindgen_test]
+    fn protostone_unwrap_test() {
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<TestMessageContext>().unwrap();
+        let mut unwrap = protomessage(0, None);
+        unwrap.message = vec![];
+        unwrap.unwrap = Some(0);
+        let test_block = index_etching_block(vec![protoburn_to(4), unwrap], &registry);
+        let protocol = tables::RuneTable::for_protocol(TestMessageContext::protocol_tag());
+        assert_eq!(balance_on_output_0(&protocol, &test_block), 0);
+        assert_eq!(balance_on_output_0(&tables::RUNES, &test_block), 1000);
+        let runeid: Arc<Vec<u8>> = ProtoruneRuneId::new(840000, 1).into();
+        assert_eq!(protocol.BURNED.select(&runeid).get_value::<u128>(), 0);
+        // the burn stays on the OP_RETURN while the unwrap adds to the supply
+        assert_eq!(protocol.UNWRAPPED.select(&runeid).get_value::<u128>(), 1000);
+        assert!(check_conservation(&registry).unwrap().is_conserved());
+    }
+
+    /// An unwrap larger than what is burned into the protocol releases nothing
+    #[wasm_bindgen_test]
+    fn protostone_unwrap_bounded_by_burned_test() {
+        clear();
+        let rune = ProtoruneRuneId::new(840000, 1);
+        let runeid: Arc<Vec<u8>> = rune.into();
+        let table = tables::RuneTable::for_protocol(TestMessageContext::protocol_tag());
+        table.BURNED.select(&runeid).set_value::<u128>(100);
+        let mut balances_by_output = HashMap::<u32, BalanceSheet>::new();
+        assert!(Protorune::unwrap_protorunes(
+            &mut AtomicPointer::default(),
+            &table,
+            &BalanceSheet::from_pairs(vec![rune], vec![101]),
+            0,
+            &mut balances_by_output,
+        )
+        .is_err());
+        assert!(balances_by_output.is_empty());
+        assert_eq!(table.BURNED.select(&runeid).get_value::<u128>(), 100);
+
+        let mut atomic = AtomicPointer::default();
+        assert!(Protorune::unwrap_protorunes(
+            &mut atomic,
+            &table,
+            &BalanceSheet::from_pairs(vec![rune], vec![60]),
+            0,
+            &mut balances_by_output,
+        )
+        .is_ok());
+        atomic.commit();
+        assert_eq!(balances_by_output[&0].get(&rune), 60);
+        assert_eq!(table.BURNED.select(&runeid).get_value::<u128>(), 40);
+    }
+
+    /// A handler can issue its own protorune and mint supply of it to the pointer
+    #[wasm_bindgen_test]
+    fn protocol_etching_test() {
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<MintingMessageContext>().unwrap();
+        let mut message = protomessage(0, None);
+        message.protocol_tag = MintingMessageContext::protocol_tag();
+        let test_block = index_etching_block(vec![message], &registry);
+
+        let table = tables::RuneTable::for_protocol(MintingMessageContext::protocol_tag());
+        let rune = ProtoruneRuneId::new(PROTOCOL_ETCHING_BLOCK, 1);
+        let runeid: Arc<Vec<u8>> = rune.into();
+        assert_eq!(
+            table.RUNE_ID_TO_ETCHING.select(&runeid).get().as_ref(),
+            &b"LPTOKEN".to_vec()
+        );
+        assert_eq!(table.INTERNAL_MINT.select(&runeid).get_value::<u8>(), 1);
+        let outpoint = OutPoint {
+            txid: test_block.txdata[1].txid(),
+            vout: 0,
+        };
+        let sheet = load_sheet(
+            &table
+                .OUTPOINT_TO_RUNES
+                .select(&consensus_encode(&outpoint).unwrap()),
+        );
+        assert_eq!(sheet.get(&rune), 1000);
+    }
+
+    /// A protorune a handler etched moves by protostone edict like any other
+    #[wasm_bindgen_test]
+    fn protocol_etching_edict_test() {
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<MintingMessageContext>().unwrap();
+        let tag = MintingMessageContext::protocol_tag();
+        let mut message = protomessage(0, None);
+        message.protocol_tag = tag;
+        let test_block = index_etching_block(vec![message], &registry);
+        let rune = ProtoruneRuneId::new(PROTOCOL_ETCHING_BLOCK, 1);
+        // 400 to the OP_RETURN by edict, the rest to output 0 by pointer
+        let mut transfer = protomessage(0, None);
+        transfer.protocol_tag = tag;
+        transfer.message = vec![];
+        transfer.edicts = vec![ProtostoneEdict {
+            id: rune,
+            amount: 400,
+            output: 1,
+        }];
+        let mut block = helpers::create_block_with_coinbase_tx(840001);
+        block.txdata.push(helpers::create_protostone_encoded_tx(
+            OutPoint {
+                txid: test_block.txdata[1].txid(),
+                vout: 0,
+            },
+            vec![transfer],
+        ));
+        assert!(Protorune::index_block_with_registry(block.clone(), 840001, &registry).is_ok());
+        let outpoint = OutPoint {
+            txid: block.txdata[1].txid(),
+            vout: 0,
+        };
+        let sheet = load_sheet(
+            &tables::RuneTable::for_protocol(tag)
+                .OUTPOINT_TO_RUNES
+                .select(&consensus_encode(&outpoint).unwrap()),
+        );
+        assert_eq!(sheet.get(&rune), 600);
+    }
+
+    /// Burns into the splitting protocol, runs its message with `calldata`, then
+    /// carries whatever reaches the last protostone to output 0
+    fn index_split(calldata: u8) -> Block {
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<SplittingMessageContext>().unwrap();
+        let tag = SplittingMessageContext::protocol_tag();
+        let mut burn = protoburn_to(4);
+        burn.burn = Some(tag);
+        let mut message = protomessage(0, None);
+        message.protocol_tag = tag;
+        message.message = vec![calldata];
+        let mut carry = protomessage(0, None);
+        carry.protocol_tag = tag;
+        carry.message = vec![];
+        carry.refund = None;
+        index_etching_block(vec![burn, message, carry], &registry)
+    }
+
+    #[wasm_bindgen_test]
+    fn protomessage_split_allocation_test() {
+        let test_block = index_split(1);
+        let table = tables::RuneTable::for_protocol(SplittingMessageContext::protocol_tag());
+        // 600 through the pointer and 300 through the next protostone
+        assert_eq!(balance_on_output_0(&table, &test_block), 900);
+        assert_eq!(
+            load_sheet(&table.RUNTIME_BALANCE).get(&ProtoruneRuneId::new(840000, 1)),
+            100
+        );
+        let message = OutPoint {
+            txid: test_block.txdata[1].txid(),
+            vout: 4,
+        };
+        assert_eq!(
+            table
+                .EVENTS
+                .select(&consensus_encode(&message).unwrap())
+                .get_list()
+                .into_iter()
+                .map(|v| v.as_ref().clone())
+                .collect::<Vec<Vec<u8>>>(),
+            vec![b"split".to_vec()]
+        );
+    }
+
+    /// Results that create runes or send them backwards are refunded whole
+    #[wasm_bindgen_test]
+    fn protomessage_split_refund_test() {
+        let table = tables::RuneTable::for_protocol(SplittingMessageContext::protocol_tag());
+        for calldata in [2, 3] {
+            let test_block = index_split(calldata);
+            assert_eq!(balance_on_output_0(&table, &test_block), 1000);
+            assert_eq!(
+                load_sheet(&table.RUNTIME_BALANCE).get(&ProtoruneRuneId::new(840000, 1)),
+                0
+            );
+        }
+    }
+
+    /// A protocol without a registry entry still holds what is burned into it,
+    /// and is indexed alongside the registered ones
+    #[wasm_bindgen_test]
+    fn protoburn_unregistered_tag_test() {
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<TestMessageContext>().unwrap();
+        let mut burn = protoburn_to(0);
+        burn.burn = Some(500);
+        let test_block = index_etching_block(vec![burn], &registry);
+        let table = tables::RuneTable::for_protocol(500);
+        assert_eq!(balance_on_output_0(&table, &test_block), 1000);
+        assert_eq!(
+            registry.indexed_tags(),
+            vec![TestMessageContext::protocol_tag(), 500]
+        );
+        assert!(check_conservation(&registry).unwrap().is_conserved());
+        let output = OutPoint::new(test_block.txdata[1].txid(), 0);
+        assert!(commitment::block_leaves(&registry, &test_block, 840000)
+            .unwrap()
+            .iter()
+            .any(|(key, _)| *key == outpoint_key(500, &output)));
+        let snapshot = export_snapshot(&ProtocolRegistry::new()).unwrap();
+        assert_eq!(
+            snapshot
+                .protocols
+                .iter()
+                .map(|protocol| protocol.protocol_tag)
+                .collect::<Vec<u128>>(),
+            vec![500]
+        );
+        clear();
+        import_snapshot(&snapshot).unwrap();
+        assert_eq!(ProtocolRegistry::new().indexed_tags(), vec![500]);
+    }
+
+    /// A handler sending runes to the last output, here the OP_RETURN, still has
+    /// them saved there
+    #[wasm_bindgen_test]
+    fn protomessage_last_output_test() {
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<TestMessageContext>().unwrap();
+        let test_block =
+            index_etching_block(vec![protoburn_to(4), protomessage(1, None)], &registry);
+        let outpoint = OutPoint {
+            txid: test_block.txdata[1].txid(),
+            vout: 1,
+        };
+        let table = tables::RuneTable::for_protocol(TestMessageContext::protocol_tag());
+        assert_eq!(
+            load_sheet(
+                &table
+                    .OUTPOINT_TO_RUNES
+                    .select(&consensus_encode(&outpoint).unwrap())
+            )
+            .get(&ProtoruneRuneId::new(840000, 1)),
+            500
+        );
+    }
+
+    /// Each message adds to the runtime balance earlier transactions left
+    #[wasm_bindgen_test]
+    fn protomessage_runtime_accumulates_test() {
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<TestMessageContext>().unwrap();
+        let rune = ProtoruneRuneId::new(840000, 1);
+        let test_block =
+            index_etching_block(vec![protoburn_to(4), protomessage(0, None)], &registry);
+        let table = tables::RuneTable::for_protocol(TestMessageContext::protocol_tag());
+        assert_eq!(load_sheet(&table.RUNTIME_BALANCE).get(&rune), 500);
+        // hand the 500 on output 0 to a second message
+        let mut hand_over = protomessage(4, None);
+        hand_over.message = vec![];
+        hand_over.edicts = vec![ProtostoneEdict {
+            id: rune,
+            amount: 500,
+            output: 4,
+        }];
+        let mut block = helpers::create_block_with_coinbase_tx(840001);
+        block.txdata.push(helpers::create_protostone_encoded_tx(
+            OutPoint {
+                txid: test_block.txdata[1].txid(),
+                vout: 0,
+            },
+            vec![hand_over, protomessage(0, None)],
+        ));
+        assert!(Protorune::index_block_with_registry(block, 840001, &registry).is_ok());
+        assert_eq!(load_sheet(&table.RUNTIME_BALANCE).get(&rune), 750);
+    }
+
+    /// Indexes a funding transaction paying ADDRESS1 and, when `spend_funding`,
+    /// an etching transaction spending it whose message needs that input
+    fn index_authorized_message(spend_funding: bool) -> u128 {
+        clear();
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<AuthorizingMessageContext>().unwrap();
+        let tag = AuthorizingMessageContext::protocol_tag();
+        let mut test_block = helpers::create_block_with_coinbase_tx(840000);
+        let funding = helpers::create_test_transaction();
+        let previous_output = OutPoint {
+            txid: if spend_funding {
+                funding.txid()
+            } else {
+                bitcoin::Txid::from_str(
+                    "0000000000000000000000000000000000000000000000000000000000000000",
+                )
+                .unwrap()
+            },
+            vout: 0,
+        };
+        let mut burn = protoburn_to(4);
+        burn.burn = Some(tag);
+        let mut message = protomessage(0, None);
+        message.protocol_tag = tag;
+        // refunds land on the OP_RETURN, so a failed message leaves output 0 empty
+        message.refund = Some(1);
+        test_block.txdata.push(funding);
+        test_block
+            .txdata
+            .push(helpers::create_etching_protostone_transaction(
+                previous_output,
+                vec![burn, message],
+            ));
+        assert!(
+            Protorune::index_block_with_registry(test_block.clone(), 840000, &registry).is_ok()
+        );
+        let outpoint = OutPoint {
+            txid: test_block.txdata[2].txid(),
+            vout: 0,
+        };
+        load_sheet(
+            &tables::RuneTable::for_protocol(tag)
+                .OUTPOINT_TO_RUNES
+                .select(&consensus_encode(&outpoint).unwrap()),
+        )
+        .get(&ProtoruneRuneId::new(840000, 2))
+    }
+
+    #[wasm_bindgen_test]
+    fn protomessage_resolved_inputs_test() {
+        assert_eq!(index_authorized_message(true), 1000);
+        assert_eq!(index_authorized_message(false), 0);
+    }
+
+    /// A message whose inputs can't be resolved fails like a reverted handler,
+    /// refunding what it was sent instead of dropping the whole runestone
+    #[wasm_bindgen_test]
+    fn protomessage_unresolved_inputs_test() {
+        clear();
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<TestMessageContext>().unwrap();
+        let previous_output = OutPoint {
+            txid: bitcoin::Txid::from_str(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            )
+            .unwrap(),
+            vout: 0,
+        };
+        // an address that isn't UTF-8 can't be resolved
+        tables::OUTPOINT_SPENDABLE_BY
+            .select(&consensus_encode(&previous_output).unwrap())
+            .set(Arc::new(vec![0xff]));
+        let mut test_block = helpers::create_block_with_coinbase_tx(840000);
+        test_block
+            .txdata
+            .push(helpers::create_etching_protostone_transaction(
+                previous_output,
+                vec![protoburn_to(4), protomessage(1, None)],
+            ));
+        assert!(
+            Protorune::index_block_with_registry(test_block.clone(), 840000, &registry).is_ok()
+        );
+        let table = tables::RuneTable::for_protocol(TestMessageContext::protocol_tag());
+        assert_eq!(balance_on_output_0(&table, &test_block), 1000);
+        let message = OutPoint {
+            txid: test_block.txdata[1].txid(),
+            vout: 4,
+        };
+        assert_eq!(
+            table
+                .OUTCOMES
+                .select(&consensus_encode(&message).unwrap())
+                .get_value::<u8>(),
+            MessageOutcome::Reverted as u8
+        );
+    }
+
+    /// Indexes a message that writes 50 storage slots, with the protocol's fuel
+    /// limit when given, returning what reached output 0 and the message outcome
+    fn index_storing_message(fuel_limit: Option<u64>) -> (u128, u8) {
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<StoringMessageContext>().unwrap();
+        let tag = StoringMessageContext::protocol_tag();
+        if let Some(limit) = fuel_limit {
+            registry.set_fuel_limit(tag, limit).unwrap();
+        }
+        let mut burn = protoburn_to(4);
+        burn.burn = Some(tag);
+        let mut message = protomessage(0, None);
+        message.protocol_tag = tag;
+        // refunds land on the OP_RETURN, so a failed message leaves output 0 empty
+        message.refund = Some(1);
+        let test_block = index_etching_block(vec![burn, message], &registry);
+        let table = tables::RuneTable::for_protocol(tag);
+        let message = OutPoint {
+            txid: test_block.txdata[1].txid(),
+            vout: 4,
+        };
+        (
+            balance_on_output_0(&table, &test_block),
+            table
+                .OUTCOMES
+                .select(&consensus_encode(&message).unwrap())
+                .get_value::<u8>(),
+        )
+    }
+
+    #[wasm_bindgen_test]
+    fn protomessage_out_of_fuel_test() {
+        assert_eq!(
+            index_storing_message(None),
+            (1000, MessageOutcome::Success as u8)
+        );
+        assert_eq!(
+            index_storing_message(Some(1000)),
+            (0, MessageOutcome::OutOfFuel as u8)
+        );
+        let slot = tables::RuneTable::for_protocol(StoringMessageContext::protocol_tag())
+            .STATE
+            .select_value::<u32>(0);
+        assert_eq!(slot.get_value::<u64>(), 0);
+    }
+
+    /// Routes 1000 protorunes through a vault protocol, returning what reached
+    /// output 0 in the router's table and the vault's runtime balance
+    fn index_routed_message(calldata: u8) -> (u128, u128) {
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<VaultMessageContext>().unwrap();
+        registry.register::<RoutingMessageContext>().unwrap();
+        let mut burn = protoburn_to(4);
+        burn.burn = Some(RoutingMessageContext::protocol_tag());
+        let mut message = protomessage(0, None);
+        message.protocol_tag = RoutingMessageContext::protocol_tag();
+        message.message = vec![calldata];
+        let test_block = index_etching_block(vec![burn, message], &registry);
+        let vault = tables::RuneTable::for_protocol(VaultMessageContext::protocol_tag());
+        (
+            balance_on_output_0(
+                &tables::RuneTable::for_protocol(RoutingMessageContext::protocol_tag()),
+                &test_block,
+            ),
+            load_sheet(&vault.RUNTIME_BALANCE).get(&ProtoruneRuneId::new(840000, 1)),
+        )
+    }
+
+    /// What is burned into the router and the vault
+    fn routed_burns() -> (u128, u128) {
+        let runeid: Arc<Vec<u8>> = ProtoruneRuneId::new(840000, 1).into();
+        let burned = |tag: u128| {
+            tables::RuneTable::for_protocol(tag)
+                .BURNED
+                .select(&runeid)
+                .get_value::<u128>()
+        };
+        (
+            burned(RoutingMessageContext::protocol_tag()),
+            burned(VaultMessageContext::protocol_tag()),
+        )
+    }
+
+    #[wasm_bindgen_test]
+    fn protomessage_cross_protocol_call_test() {
+        assert_eq!(index_routed_message(1), (900, 100));
+        let deposits = tables::RuneTable::for_protocol(VaultMessageContext::protocol_tag())
+            .STATE
+            .select(&b"deposits".to_vec());
+        assert_eq!(deposits.get_value::<u64>(), 1);
+        // the burn backing what the vault kept moved with it
+        assert_eq!(routed_burns(), (900, 100));
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<VaultMessageContext>().unwrap();
+        registry.register::<RoutingMessageContext>().unwrap();
+        assert!(check_conservation(&registry).unwrap().is_conserved());
+    }
+
+    /// A failed call is rolled back on its own, the caller keeps its runes
+    #[wasm_bindgen_test]
+    fn protomessage_failed_cross_protocol_call_test() {
+        assert_eq!(index_routed_message(2), (1000, 0));
+        let deposits = tables::RuneTable::for_protocol(VaultMessageContext::protocol_tag())
+            .STATE
+            .select(&b"deposits".to_vec());
+        assert_eq!(deposits.get_value::<u64>(), 0);
+        assert_eq!(routed_burns(), (1000, 0));
+    }
+
+    /// A call sending a protorune its caller etched is refused before the callee runs
+    #[wasm_bindgen_test]
+    fn protomessage_call_with_protocol_etched_rune_test() {
+        assert_eq!(index_routed_message(3), (1000, 0));
+        let deposits = tables::RuneTable::for_protocol(VaultMessageContext::protocol_tag())
+            .STATE
+            .select(&b"deposits".to_vec());
+        assert_eq!(deposits.get_value::<u64>(), 0);
+        assert_eq!(routed_burns(), (1000, 0));
+    }
+
+    fn view_request(protocol_tag: u128, method: u32, input: Vec<u8>) -> Vec<u8> {
+        ProtocolViewRequest {
+            protocol_tag: protocol_tag.to_bytes(),
+            method,
+            input,
+            ..Default::default()
+        }
+        .write_to_bytes()
+        .unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn protocol_view_test() {
+        index_storing_message(None);
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<StoringMessageContext>().unwrap();
+        registry.register::<VaultMessageContext>().unwrap();
+        registry.register_view::<StoringMessageContext>().unwrap();
+        let tag = StoringMessageContext::protocol_tag();
+        let slot = protocol_view(&registry, &view_request(tag, 0, 0u32.to_bytes())).unwrap();
+        assert_eq!(u64::from_bytes(slot), 1);
+        assert!(protocol_view(&registry, &view_request(tag, 1, vec![])).is_err());
+        // a protocol without views, and one that is not registered
+        assert!(protocol_view(
+            &registry,
+            &view_request(VaultMessageContext::protocol_tag(), 0, vec![])
+        )
+        .is_err());
+        assert!(protocol_view(&registry, &view_request(200, 0, vec![])).is_err());
+    }
+
+    /// A simulated protoburn and message show up in the response, but nothing
+    /// of them is left in the index
+    #[wasm_bindgen_test]
+    fn simulate_transaction_test() {
+        clear();
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<StoringMessageContext>().unwrap();
+        let tag = StoringMessageContext::protocol_tag();
+        let mut burn = protoburn_to(4);
+        burn.burn = Some(tag);
+        let mut message = protomessage(0, None);
+        message.protocol_tag = tag;
+        let previous_output = OutPoint {
+            txid: bitcoin::Txid::from_str(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            )
+            .unwrap(),
+            vout: 0,
+        };
+        let transaction =
+            helpers::create_etching_protostone_transaction(previous_output, vec![burn, message]);
+        let response = simulate_transaction(&registry, &transaction, 840000).unwrap();
+        assert_eq!(response.error, "");
+        let rune = ProtoruneRuneId::new(840000, 1);
+        let output = &response.outputs[0];
+        assert_eq!(
+            u128::from_bytes(output.protorunes[0].protocol_tag.clone()),
+            tag
+        );
+        assert_eq!(
+            BalanceSheet::from(output.protorunes[0].balances.clone().unwrap()).get(&rune),
+            1000
+        );
+        assert_eq!(
+            BalanceSheet::from(response.burns[0].balances.clone().unwrap()).get(&rune),
+            1000
+        );
+        assert_eq!(response.messages[0].vout, 4);
+        assert_eq!(response.messages[0].outcome, MessageOutcome::Success as u32);
+
+        let table = tables::RuneTable::for_protocol(tag);
+        let outpoint = OutPoint {
+            txid: transaction.txid(),
+            vout: 0,
+        };
+        assert_eq!(
+            load_sheet(
+                &table
+                    .OUTPOINT_TO_RUNES
+                    .select(&consensus_encode(&outpoint).unwrap())
+            )
+            .get(&rune),
+            0
+        );
+        assert_eq!(table.STATE.select_value::<u32>(0).get_value::<u64>(), 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn simulate_rejected_transaction_test() {
+        clear();
+        let registry = ProtocolRegistry::new();
+        let response =
+            simulate_transaction(&registry, &helpers::create_test_transaction(), 840000).unwrap();
+        assert_eq!(response.error, "transaction has no runestone");
+        assert!(response.outputs.is_empty());
+    }
+
+    /// A message carrying a reserved even tag is recorded as flawed and never
+    /// dispatched
+    #[wasm_bindgen_test]
+    fn flawed_protostone_test() {
+        clear();
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<StoringMessageContext>().unwrap();
+        let tag = StoringMessageContext::protocol_tag();
+        let mut burn = protoburn_to(4);
+        burn.burn = Some(tag);
+        let mut message = protomessage(0, None);
+        message.protocol_tag = tag;
+        let mut values = vec![];
+        for (stone, reserved) in [(burn, vec![]), (message, vec![2, 1])] {
+            let mut fields = stone.to_integers().unwrap();
+            fields.extend(reserved);
+            values.extend([stone.protocol_tag, fields.len() as u128]);
+            values.extend(fields);
+        }
+        let previous_output = OutPoint {
+            txid: bitcoin::Txid::from_str(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            )
+            .unwrap(),
+            vout: 0,
+        };
+        let mut transaction =
+            helpers::create_etching_protostone_transaction(previous_output, vec![]);
+        let mut runestone = match Runestone::decipher(&transaction) {
+            Some(ordinals::Artifact::Runestone(runestone)) => runestone,
+            _ => panic!("etching transaction carries no runestone"),
+        };
+        runestone.protocol = Some(split_bytes(&encode_varint_list(&values)));
+        transaction.output[1].script_pubkey = runestone.encipher();
+        let mut test_block = helpers::create_block_with_coinbase_tx(840000);
+        test_block.txdata.push(transaction.clone());
+        assert!(Protorune::index_block_with_registry(test_block, 840000, &registry).is_ok());
+
+        let table = tables::RuneTable::for_protocol(tag);
+        let message = consensus_encode(&OutPoint {
+            txid: transaction.txid(),
+            vout: 4,
+        })
+        .unwrap();
+        assert_eq!(
+            table.FLAWS.select(&message).get_value::<u8>(),
+            ProtostoneFlaw::UnrecognizedEvenTag as u8
+        );
+        assert_eq!(table.OUTCOMES.select(&message).get_value::<u8>(), 0);
+        assert_eq!(table.STATE.select_value::<u32>(0).get_value::<u64>(), 0);
+    }
+
+    /// An edict to a vout past the last protostone flags its protostone, which
+    /// then moves nothing, and the protostones around it still run
+    #[wasm_bindgen_test]
+    fn protostone_edict_output_bounds_test() {
+        let mut registry = ProtocolRegistry::new();
+        registry.register::<TestMessageContext>().unwrap();
+        let mut edict = protomessage(0, None);
+        edict.message = vec![];
+        // the protostones sit at virtual vouts 3, 4 and 5
+        edict.edicts = vec![ProtostoneEdict {
+            id: ProtoruneRuneId::new(840000, 1),
+            amount: 1000,
+            output: 6,
+        }];
+        let test_block = index_etching_block(
+            vec![protoburn_to(5), edict, protomessage(0, None)],
+            &registry,
+        );
+        let table = tables::RuneTable::for_protocol(TestMessageContext::protocol_tag());
+        let protostone = consensus_encode(&OutPoint {
+            txid: test_block.txdata[1].txid(),
+            vout: 4,
+        })
+        .unwrap();
+        assert_eq!(
+            table.FLAWS.select(&protostone).get_value::<u8>(),
+            ProtostoneFlaw::EdictOutput as u8
+        );
+        // the message keeps half of the burn
+        assert_eq!(balance_on_output_0(&table, &test_block), 500);
+    }
+
+    /// Etches, transfers, protoburns and messages across blocks, following the
+    /// runes and protorunes by address
+    #[wasm_bindgen_test]
+    fn chain_builder_test() {
+        clear();
+        let tag = TestMessageContext::protocol_tag();
+        let mut chain = ChainBuilder::new();
+        chain
+            .register::<TestMessageContext>()
+            .unwrap()
+            .etch("TESTTESTTEST", 1000, ADDRESS1)
+            .unwrap()
+            .transfer(ADDRESS1, "TESTTESTTEST", 300, ADDRESS2)
+            .unwrap();
+        let rune = chain.rune("TESTTESTTEST").unwrap();
+        chain
+            .assert_address_balance(None, ADDRESS1, &rune, 700)
+            .assert_address_balance(None, ADDRESS2, &rune, 300);
+        chain.protoburn(ADDRESS2, tag, ADDRESS2).unwrap();
+        chain
+            .assert_address_balance(None, ADDRESS2, &rune, 0)
+            .assert_address_balance(Some(tag), ADDRESS2, &rune, 300);
+        // the message keeps half and sends the other half to ADDRESS1
+        chain.message(ADDRESS2, tag, vec![1], ADDRESS1).unwrap();
+        let sent = chain.outpoint(0).unwrap();
+        chain
+            .assert_outpoint_balance(Some(tag), &sent, &rune, 150)
+            .assert_address_balance(Some(tag), ADDRESS2, &rune, 0)
+            .assert_runtime_balance(tag, &rune, 150);
+        chain.spend(ADDRESS1, ADDRESS2).unwrap();
+        chain
+            .assert_address_balance(None, ADDRESS1, &rune, 0)
+            .assert_address_balance(Some(tag), ADDRESS1, &rune, 0)
+            .assert_address_balance(None, ADDRESS2, &rune, 700)
+            .assert_address_balance(Some(tag), ADDRESS2, &rune, 150);
+        assert_eq!(chain.height(), 840005);
+        chain.assert_conserved();
+    }
+
+    /// Mints a rune etched with terms until its cap runs out
+    #[wasm_bindgen_test]
+    fn chain_builder_mint_test() {
+        clear();
+        let mut chain = ChainBuilder::new();
+        chain
+            .etch_with(
+                Etching {
+                    divisibility: Some(0),
+                    premine: None,
+                    rune: Some(Rune::from_str("MINTABLERUNE").unwrap()),
+                    spacers: None,
+                    symbol: None,
+                    terms: Some(Terms {
+                        amount: Some(100),
+                        cap: Some(2),
+                        height: (None, None),
+                        offset: (None, None),
+                    }),
+                    turbo: true,
+                },
+                ADDRESS1,
+            )
+            .unwrap();
+        for _ in 0..3 {
+            chain.mint("MINTABLERUNE", ADDRESS1).unwrap();
+        }
+        let rune = chain.rune("MINTABLERUNE").unwrap();
+        chain
+            .assert_address_balance(None, ADDRESS1, &rune, 200)
+            .assert_conserved();
+    }
+
+    /// Spending runes without a runestone loses them, which the conservation
+    /// check reports against the rune's supply
+    #[wasm_bindgen_test]
+    fn conservation_violation_test() {
+        clear();
+        let mut chain = ChainBuilder::new();
+        chain
+            .register::<TestMessageContext>()
+            .unwrap()
+            .etch("TESTTESTTEST", 1000, ADDRESS1)
+            .unwrap()
+            .transfer(ADDRESS1, "TESTTESTTEST", 400, ADDRESS2)
+            .unwrap();
+        chain.assert_conserved();
+        let rune = chain.rune("TESTTESTTEST").unwrap();
+        let held = chain.outpoint(1).unwrap();
+        chain
+            .transaction(Transaction {
+                version: 2,
+                lock_time: bitcoin::absolute::LockTime::ZERO,
+                input: vec![TxIn {
+                    previous_output: held,
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::MAX,
+                    witness: Witness::new(),
+                }],
+                output: vec![TxOut {
+                    value: 546,
+                    script_pubkey: get_address(ADDRESS2).script_pubkey(),
+                }],
+            })
+            .unwrap();
+        let report = check_conservation(&chain.registry).unwrap();
+        assert_eq!(report.blocks, 3);
+        assert_eq!(report.runes, 1);
+        assert_eq!(
+            report.violations,
+            vec![Violation::Supply {
+                rune,
+                issued: 1000,
+                found: 400,
+                outpoints: vec![OutPoint::new(chain.blocks()[1].txdata[1].txid(), 0)],
+            }]
+        );
+        let response = view::conservation(&chain.registry).unwrap();
+        assert_eq!(response.violations.len(), 1);
+        assert_eq!(response.violations[0].outpoints.len(), 1);
+    }
+
+    /// Runs a protoburn and a message, returning the chain and the state root
+    /// of each of its blocks
+    fn state_roots() -> (ChainBuilder, Vec<[u8; 32]>) {
+        let tag = TestMessageContext::protocol_tag();
+        let mut chain = ChainBuilder::new();
+        chain
+            .register::<TestMessageContext>()
+            .unwrap()
+            .etch("TESTTESTTEST", 1000, ADDRESS1)
+            .unwrap()
+            .protoburn(ADDRESS1, tag, ADDRESS2)
+            .unwrap()
+            .message(ADDRESS2, tag, vec![1], ADDRESS1)
+            .unwrap();
+        let roots = (GENESIS as u64..chain.height())
+            .map(|height| commitment::state_root(height).unwrap())
+            .collect();
+        (chain, roots)
+    }
+
+    /// Indexing the same blocks again commits to the same roots, each block
+    /// commits to its leaves chained to the root before it
+    #[wasm_bindgen_test]
+    fn state_root_test() {
+        clear();
+        let (chain, roots) = state_roots();
+        clear();
+        assert_eq!(state_roots().1, roots);
+        // leaves read the tables as they stand, so only the tip's can be rebuilt
+        let tip = roots.len() - 1;
+        let leaves =
+            commitment::block_leaves(&chain.registry, &chain.blocks()[tip], chain.height() - 1)
+                .unwrap()
+                .iter()
+                .map(|(key, value)| leaf_hash(key, value))
+                .collect::<Vec<[u8; 32]>>();
+        assert!(!leaves.is_empty());
+        assert_eq!(
+            roots[tip],
+            chain_root(&roots[tip - 1], &merkle_root(&leaves))
+        );
+        assert_ne!(roots[tip], roots[tip - 1]);
+        let response =
+            view::state_root(&StateRootRequest::new().write_to_bytes().unwrap()).unwrap();
+        assert_eq!(response.height, chain.height() - 1);
+        assert_eq!(response.root, roots[tip].to_vec());
+    }
+
+    /// The leaves of a block commit to the state its handlers wrote and to what
+    /// its protocols etched and minted, so indexers that disagree on either
+    /// disagree on the root
+    #[wasm_bindgen_test]
+    fn state_root_protocol_leaves_test() {
+        clear();
+        let storing = StoringMessageContext::protocol_tag();
+        let minting = MintingMessageContext::protocol_tag();
+        let mut chain = ChainBuilder::new();
+        chain
+            .register::<StoringMessageContext>()
+            .unwrap()
+            .register::<MintingMessageContext>()
+            .unwrap()
+            .etch("TESTTESTTEST", 1000, ADDRESS1)
+            .unwrap()
+            .protoburn(ADDRESS1, storing, ADDRESS2)
+            .unwrap()
+            .message(ADDRESS2, storing, vec![1], ADDRESS1)
+            .unwrap()
+            .message(ADDRESS1, minting, vec![1], ADDRESS2)
+            .unwrap();
+        let leaves = |height: u64| {
+            let block = &chain.blocks()[(height - GENESIS as u64) as usize];
+            commitment::block_leaves(&chain.registry, block, height).unwrap()
+        };
+        let minted_at = chain.height() - 1;
+        let stored_at = minted_at - 1;
+        let slot = 0u32.to_bytes();
+        let stored = leaves(stored_at);
+        assert!(stored
+            .iter()
+            .any(|(key, _)| *key == state_key(storing, &slot)));
+        let mut state = tables::RuneTable::for_protocol(storing).STATE.select(&slot);
+        state.set_value::<u64>(2);
+        assert_ne!(leaves(stored_at), stored);
+        state.set_value::<u64>(1);
+        assert_eq!(leaves(stored_at), stored);
+
+        let tx = chain.last().unwrap();
+        let message = OutPoint::new(tx.txid(), protostone_vout(tx.output.len() as u32, 0));
+        let rune = ProtoruneRuneId::new(PROTOCOL_ETCHING_BLOCK, 1);
+        let minted = leaves(minted_at);
+        for key in [mint_key(minting, &message), etching_key(minting, &rune)] {
+            assert!(minted.iter().any(|(leaf, _)| *leaf == key));
+        }
+        let table = tables::RuneTable::for_protocol(minting);
+        let runeid: Arc<Vec<u8>> = rune.into();
+        for mut pointer in [
+            table.SEQUENCE.clone(),
+            table
+                .MINTS
+                .select(&consensus_encode(&message).unwrap())
+                .select(&runeid),
+        ] {
+            let value = pointer.get_value::<u128>();
+            pointer.set_value::<u128>(value + 1);
+            assert_ne!(leaves(minted_at), minted);
+            pointer.set_value::<u128>(value);
+        }
+        assert_eq!(leaves(minted_at), minted);
+    }
+
+    /// Proves the balances of an outpoint etched to and of one protoburned to,
+    /// and checks the proofs verify only the balances they were made for
+    #[wasm_bindgen_test]
+    fn outpoint_proof_test() {
+        clear();
+        let tag = TestMessageContext::protocol_tag();
+        let mut chain = ChainBuilder::new();
+        chain
+            .register::<TestMessageContext>()
+            .unwrap()
+            .etch("TESTTESTTEST", 1000, ADDRESS1)
+            .unwrap();
+        let etched = chain.outpoint(0).unwrap();
+        chain.protoburn(ADDRESS1, tag, ADDRESS2).unwrap();
+        let burned = chain.outpoint(0).unwrap();
+        let rune = chain.rune("TESTTESTTEST").unwrap();
+        for (protocol, outpoint) in [(vec![], etched), (tag.to_bytes(), burned)] {
+            let request = OutpointWithProtocol {
+                txid: outpoint.txid.as_byte_array().to_vec(),
+                vout: outpoint.vout,
+                protocol: protocol.clone(),
+                ..Default::default()
+            };
+            let response = view::outpoint_proof(&request.write_to_bytes().unwrap()).unwrap();
+            let protocol_tag = match protocol.is_empty() {
+                true => RUNES_TAG,
+                false => tag,
+            };
+            let proof = OutpointProof {
+                height: response.height,
+                previous: response.previous_root.as_slice().try_into().unwrap(),
+                proof: MerkleProof {
+                    index: response.index,
+                    leaves: response.leaves,
+                    siblings: response
+                        .siblings
+                        .iter()
+                        .map(|sibling| sibling.as_slice().try_into().unwrap())
+                        .collect(),
+                },
+            };
+            let root: [u8; 32] = response.root.as_slice().try_into().unwrap();
+            assert_eq!(commitment::state_root(proof.height), Some(root));
+            let sheet: BalanceSheet = response.balances.unwrap().into();
+            assert_eq!(sheet.get(&rune), 1000);
+            assert!(verify_outpoint(
+                protocol_tag,
+                &outpoint,
+                &sheet,
+                &proof,
+                &root
+            ));
+            let mut forged = sheet.clone();
+            forged.set(&rune, 1001);
+            assert!(!verify_outpoint(
+                protocol_tag,
+                &outpoint,
+                &forged,
+                &proof,
+                &root
+            ));
+            assert!(!verify_outpoint(
+                protocol_tag + 1,
+                &outpoint,
+                &sheet,
+                &proof,
+                &root
+            ));
+        }
+        // the etched outpoint holds nothing under the protocol
+        let request = OutpointWithProtocol {
+            txid: etched.txid.as_byte_array().to_vec(),
+            vout: etched.vout,
+            protocol: tag.to_bytes(),
+            ..Default::default()
+        };
+        assert!(view::outpoint_proof(&request.write_to_bytes().unwrap()).is_err());
+    }
+    /// Sums what `address` held as of `height`, in the base table or under a protocol
+    fn balance_at(address: &str, protocol_tag: Option<u128>, height: u64) -> BalanceSheet {
+        let response = match protocol_tag {
+            None => view::runes_by_address(
+                &WalletRequest {
+                    wallet: address.as_bytes().to_vec(),
+                    height: Some(height),
+                    ..Default::default()
+                }
+                .write_to_bytes()
+                .unwrap(),
+            ),
+            Some(tag) => view::protorunes_by_address(
+                &ProtorunesWalletRequest {
+                    wallet: address.as_bytes().to_vec(),
+                    protocol_tag: tag.to_bytes(),
+                    height: Some(height),
+                    ..Default::default()
+                }
+                .write_to_bytes()
+                .unwrap(),
+            ),
+        }
+        .unwrap();
+        response.balances.unwrap().into()
+    }
+
+    /// Wallet views given a height answer with what addresses held as of it
+    #[wasm_bindgen_test]
+    fn balance_history_test() {
+        clear();
+        let tag = TestMessageContext::protocol_tag();
+        let mut chain = ChainBuilder::new();
+        chain
+            .register::<TestMessageContext>()
+            .unwrap()
+            .etch("TESTTESTTEST", 1000, ADDRESS1)
+            .unwrap()
+            .transfer(ADDRESS1, "TESTTESTTEST", 300, ADDRESS2)
+            .unwrap()
+            .protoburn(ADDRESS2, tag, ADDRESS2)
+            .unwrap();
+        let rune = chain.rune("TESTTESTTEST").unwrap();
+        let etched = GENESIS as u64;
+        let (transferred, burned) = (etched + 1, etched + 2);
+        assert_eq!(balance_at(ADDRESS1, None, etched - 1).get(&rune), 0);
+        assert_eq!(balance_at(ADDRESS1, None, etched).get(&rune), 1000);
+        assert_eq!(balance_at(ADDRESS2, None, etched).get(&rune), 0);
+        assert_eq!(balance_at(ADDRESS1, None, transferred).get(&rune), 700);
+        assert_eq!(balance_at(ADDRESS2, None, transferred).get(&rune), 300);
+        assert_eq!(balance_at(ADDRESS2, Some(tag), transferred).get(&rune), 0);
+        assert_eq!(balance_at(ADDRESS2, None, burned).get(&rune), 0);
+        assert_eq!(balance_at(ADDRESS2, Some(tag), burned).get(&rune), 300);
+        assert_eq!(balance_at(ADDRESS1, None, burned).get(&rune), 700);
+    }
+    /// Etches, mints, protoburns and calls a protocol, leaving a mint for later
+    fn snapshot_chain() -> ChainBuilder {
+        let tag = TestMessageContext::protocol_tag();
+        let mut chain = ChainBuilder::new();
+        chain
+            .register::<TestMessageContext>()
+            .unwrap()
+            .etch("TESTTESTTEST", 1000, ADDRESS1)
+            .unwrap()
+            .etch_with(
+                Etching {
+                    divisibility: Some(2),
+                    premine: None,
+                    rune: Some(Rune::from_str("MINTABLERUNE").unwrap()),
+                    spacers: Some(1),
+                    symbol: Some('M'),
+                    terms: Some(Terms {
+                        amount: Some(100),
+                        cap: Some(2),
+                        height: (None, None),
+                        offset: (None, None),
+                    }),
+                    turbo: true,
+                },
+                ADDRESS2,
+            )
+            .unwrap()
+            .mint("MINTABLERUNE", ADDRESS1)
+            .unwrap()
+            .transfer(ADDRESS1, "TESTTESTTEST", 300, ADDRESS2)
+            .unwrap()
+            .protoburn(ADDRESS2, tag, ADDRESS2)
+            .unwrap()
+            .message(ADDRESS2, tag, vec![1], ADDRESS1)
+            .unwrap();
+        chain
+    }
+
+    /// A snapshot survives its file, seeds an empty index and indexing carries
+    /// on from it to the same state root as an index that never stopped
+    #[wasm_bindgen_test]
+    fn snapshot_test() {
+        clear();
+        let mut chain = snapshot_chain();
+        chain.mint("MINTABLERUNE", ADDRESS1).unwrap();
+        let root = commitment::state_root(chain.height() - 1).unwrap();
+
+        clear();
+        let tag = TestMessageContext::protocol_tag();
+        let mut chain = snapshot_chain();
+        let snapshot = export_snapshot(&chain.registry).unwrap();
+        assert_eq!(snapshot.height, chain.height() - 1);
+        assert_eq!(snapshot.runes.len(), 2);
+        let bytes = snapshot.to_bytes();
+        let read = Snapshot::from_bytes(&bytes).unwrap();
+        assert_eq!(read.content_hash(), snapshot.content_hash());
+        let mut corrupt = bytes.clone();
+        corrupt[bytes.len() / 2] ^= 1;
+        assert!(Snapshot::from_bytes(&corrupt).is_err());
+        let mut newer = bytes.clone();
+        newer[SNAPSHOT_MAGIC.len()] += 1;
+        assert!(Snapshot::from_bytes(&newer).is_err());
+        assert!(Snapshot::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(import_snapshot(&read).is_err());
+        // the position of an outpoint holding runes is that of its first rune
+        let positions = |chain: &ChainBuilder| {
+            chain
+                .unspent(ADDRESS1)
+                .iter()
+                .map(|outpoint| view::outpoint_to_outpoint_response(outpoint).unwrap())
+                .filter(|response| response.balances.entries.is_empty())
+                .map(|response| (response.height, response.txindex))
+                .collect::<Vec<(u32, u32)>>()
+        };
+        let indexed = positions(&chain);
+        assert!(!indexed.is_empty());
+
+        clear();
+        // a snapshot listing an outpoint twice doesn't survive the import, which
+        // leaves the index empty to import again
+        let mut doubled = read.clone();
+        doubled.outpoints.push(doubled.outpoints[0].clone());
+        assert!(import_snapshot(&doubled).is_err());
+        assert!(tables::TIP_HEIGHT.get().is_empty());
+        import_snapshot(&read).unwrap();
+        // seeded outpoints holding no base runes keep their index in the block
+        assert_eq!(positions(&chain), indexed);
+        // no height lists the seeded outpoints, conservation walks them apart
+        let report = check_conservation(&chain.registry).unwrap();
+        assert!(report.is_conserved(), "{:?}", report.violations);
+        assert_eq!(report.blocks, 0);
+        assert_eq!(report.outpoints, read.outpoints.len() as u64);
+        let test = chain.rune("TESTTESTTEST").unwrap();
+        let mintable = chain.rune("MINTABLERUNE").unwrap();
+        chain
+            .assert_address_balance(None, ADDRESS1, &test, 700)
+            .assert_address_balance(None, ADDRESS1, &mintable, 100)
+            .assert_address_balance(Some(tag), ADDRESS1, &test, 150)
+            .assert_runtime_balance(tag, &test, 150);
+        assert_eq!(balance_at(ADDRESS1, None, snapshot.height).get(&test), 700);
+        chain.mint("MINTABLERUNE", ADDRESS1).unwrap();
+        chain
+            .assert_address_balance(None, ADDRESS1, &mintable, 200)
+            .assert_address_balance(None, ADDRESS1, &test, 700);
+        assert_eq!(commitment::state_root(chain.height() - 1), Some(root));
+        chain.assert_conserved();
+    }
+
+    /// Burns into the storing protocol and runs its message, which writes its
+    /// storage
+    fn storing_chain() -> ChainBuilder {
+        let tag = StoringMessageContext::protocol_tag();
+        let mut chain = ChainBuilder::new();
+        chain
+            .register::<StoringMessageContext>()
+            .unwrap()
+            .etch("TESTTESTTEST", 1000, ADDRESS1)
+            .unwrap()
+            .protoburn(ADDRESS1, tag, ADDRESS2)
+            .unwrap()
+            .message(ADDRESS2, tag, vec![1], ADDRESS1)
+            .unwrap();
+        chain
+    }
+
+    /// What a handler keeps in its protocol's state travels in a snapshot, and
+    /// indexing carries on from the import to the same state root
+    #[wasm_bindgen_test]
+    fn snapshot_state_test() {
+        clear();
+        let tag = StoringMessageContext::protocol_tag();
+        let mut chain = storing_chain();
+        chain.message(ADDRESS1, tag, vec![1], ADDRESS2).unwrap();
+        let root = commitment::state_root(chain.height() - 1).unwrap();
+
+        clear();
+        let mut chain = storing_chain();
+        let snapshot = export_snapshot(&chain.registry).unwrap();
+        let state = &snapshot.protocols[0].state;
+        assert_eq!(state.len(), 50);
+        assert!(state.iter().all(|entry| entry.value == 1u64.to_bytes()));
+        let read = Snapshot::from_bytes(&snapshot.to_bytes()).unwrap();
+        assert_eq!(read, snapshot);
+
+        clear();
+        import_snapshot(&read).unwrap();
+        let slots = tables::RuneTable::for_protocol(tag).STATE;
+        assert_eq!(slots.select_value::<u32>(49).get_value::<u64>(), 1);
+        // the seeded state is exported again
+        assert_eq!(
+            export_snapshot(&chain.registry).unwrap().content_hash(),
+            snapshot.content_hash()
+        );
+        chain.message(ADDRESS1, tag, vec![1], ADDRESS2).unwrap();
+        assert_eq!(commitment::state_root(chain.height() - 1), Some(root));
+    }
 }
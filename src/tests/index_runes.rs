@@ -4,22 +4,23 @@ mod tests {
     use crate::proto::protorune::{RunesByHeightRequest, WalletRequest};
     use protorune::balance_sheet::load_sheet;
     use protorune_support::balance_sheet::{BalanceSheet, ProtoruneRuneId};
+    use protorune_support::builder::TransactionBuilder;
 
     use crate::test_helpers as helpers;
     use crate::test_helpers::{display_list_as_hex, display_vec_as_hex};
     use crate::Protorune;
     use crate::{message::MessageContextParcel, tables, view};
     use anyhow::Result;
-    use protorune_support::rune_transfer::RuneTransfer;
+    use protorune_support::rune_transfer::{MessageResult, RuneTransfer};
     use protorune_support::utils::consensus_encode;
 
     use bitcoin::consensus::serialize;
     use bitcoin::hashes::Hash;
-    use bitcoin::{OutPoint, Txid};
+    use bitcoin::{OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid, Witness};
     use hex;
 
     use metashrew::{clear, index_pointer::KeyValuePointer};
-    use ordinals::Rune;
+    use ordinals::{Etching, Rune, RuneId, Runestone, Terms};
 
     use protobuf::{Message, SpecialFields};
 
@@ -30,9 +31,9 @@ mod tests {
     struct MyMessageContext(());
 
     impl MessageContext for MyMessageContext {
-        fn handle(_parcel: &MessageContextParcel) -> Result<(Vec<RuneTransfer>, BalanceSheet)> {
+        fn handle(_parcel: &MessageContextParcel) -> Result<MessageResult> {
             let ar: Vec<RuneTransfer> = vec![];
-            Ok((ar, BalanceSheet::default()))
+            Ok((ar, BalanceSheet::default()).into())
         }
         fn protocol_tag() -> u128 {
             100
@@ -135,6 +136,7 @@ mod tests {
             wallet: "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
                 .as_bytes()
                 .to_vec(),
+            height: None,
             special_fields: SpecialFields::new(),
         })
         .write_to_bytes()
@@ -313,4 +315,139 @@ mod tests {
     fn correct_balance_sheet_equal_distribute_0() {
         edict_test(0, 3, 500, 500);
     }
+
+    /// Compacting a transaction leaves what the indexer allocates unchanged. Edicts
+    /// to the spread output give their amount to each output in turn until the
+    /// rune runs out, so two of them allocate apart from their merged sum
+    #[wasm_bindgen_test]
+    fn compact_keeps_spread_edicts_apart() {
+        let index = |compact: bool| {
+            clear();
+            let (block, config) = helpers::create_block_with_rune_tx();
+            let etching = block.txdata[0].clone();
+            let rune = RuneId::new(config.rune_etch_height, config.rune_etch_vout).unwrap();
+            let builder = TransactionBuilder::new()
+                .input(OutPoint::new(etching.txid(), 0))
+                .output(helpers::get_address(helpers::ADDRESS1).script_pubkey(), 546)
+                .output(helpers::get_address(helpers::ADDRESS1).script_pubkey(), 546);
+            let spread = builder.num_outputs();
+            let builder = builder.edict(rune, 400, spread).edict(rune, 400, spread);
+            let transfer = if compact { builder.compact() } else { builder }
+                .build()
+                .unwrap();
+            let _ = Protorune::index_block::<MyMessageContext>(
+                helpers::create_block_with_txs(vec![etching, transfer.clone()]),
+                config.rune_etch_height,
+            );
+            (0..2)
+                .map(|vout| {
+                    let outpoint = OutPoint::new(transfer.txid(), vout);
+                    load_sheet(
+                        &tables::RUNES
+                            .OUTPOINT_TO_RUNES
+                            .select(&consensus_encode(&outpoint).unwrap()),
+                    )
+                    .get(&ProtoruneRuneId::from(rune))
+                })
+                .collect::<Vec<u128>>()
+        };
+        assert_eq!(index(false), vec![600, 400]);
+        assert_eq!(index(true), vec![600, 400]);
+    }
+
+    /// A mint looks up its rune's terms by the name the etching number stands for
+    /// and credits the terms' amount until the cap runs out
+    #[wasm_bindgen_test]
+    fn mint_credits_amount_up_to_cap() {
+        clear();
+        let script_pubkey =
+            helpers::get_address("bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu").script_pubkey();
+        let transaction = |previous_output: OutPoint, runestone: Runestone| Transaction {
+            version: 1,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![
+                TxOut {
+                    value: 1,
+                    script_pubkey: script_pubkey.clone(),
+                },
+                TxOut {
+                    value: 0,
+                    script_pubkey: runestone.encipher(),
+                },
+            ],
+        };
+        let etching = transaction(
+            OutPoint::null(),
+            Runestone {
+                etching: Some(Etching {
+                    divisibility: Some(0),
+                    premine: None,
+                    rune: Some(Rune::from_str("MINTABLERUNE").unwrap()),
+                    spacers: None,
+                    symbol: None,
+                    terms: Some(Terms {
+                        amount: Some(100),
+                        cap: Some(2),
+                        height: (None, None),
+                        offset: (None, None),
+                    }),
+                    turbo: true,
+                }),
+                pointer: Some(0),
+                ..Default::default()
+            },
+        );
+        let _ = Protorune::index_block::<MyMessageContext>(
+            helpers::create_block_with_txs(vec![
+                helpers::create_coinbase_transaction(840000),
+                etching.clone(),
+            ]),
+            840000,
+        );
+        let rune = ProtoruneRuneId::new(840000, 1);
+        let mints = (0..3)
+            .map(|vout| {
+                transaction(
+                    OutPoint::new(etching.txid(), vout),
+                    Runestone {
+                        mint: Some(RuneId::new(840000, 1).unwrap()),
+                        pointer: Some(0),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect::<Vec<Transaction>>();
+        let mut txdata = vec![helpers::create_coinbase_transaction(840001)];
+        txdata.extend(mints.clone());
+        let _ = Protorune::index_block::<MyMessageContext>(
+            helpers::create_block_with_txs(txdata),
+            840001,
+        );
+        let minted = mints
+            .iter()
+            .map(|mint| {
+                load_sheet(
+                    &tables::RUNES
+                        .OUTPOINT_TO_RUNES
+                        .select(&consensus_encode(&OutPoint::new(mint.txid(), 0)).unwrap()),
+                )
+                .get(&rune)
+            })
+            .collect::<Vec<u128>>();
+        assert_eq!(minted, vec![100, 100, 0]);
+        let name = Rune::from_str("MINTABLERUNE").unwrap().to_string();
+        assert_eq!(
+            tables::RUNES
+                .MINTS_REMAINING
+                .select(&name.into_bytes())
+                .get_value::<u128>(),
+            0
+        );
+    }
 }
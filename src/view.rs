@@ -2,28 +2,48 @@ use protorune_support::balance_sheet::{BalanceSheet, ProtoruneRuneId};
 use crate::proto::protorune::{
     BalanceSheet as ProtoBalanceSheet,
     BalanceSheetItem,
+    ConservationResponse,
+    ConservationViolation,
     Outpoint,
+    OutpointProofResponse,
     OutpointResponse,
+    OutpointWithProtocol,
     Output,
+    ProtocolBalanceSheet,
     Rune,
     RuneId,
     //RunesByHeightRequest,
     RunesResponse,
+    SimulateRequest,
+    SimulateResponse,
+    SimulatedMessage,
+    SimulatedOutput,
+    StateRootRequest,
+    StateRootResponse,
     WalletResponse,
 };
-use protorune_support::utils::{consensus_decode};
-use crate::{balance_sheet::{load_sheet}, proto, tables};
+use protorune_support::commitment::RUNES_TAG;
+use protorune_support::utils::{consensus_decode, consensus_encode};
+use crate::{balance_sheet::{load_sheet}, proto, tables, Protorune};
+use crate::commitment;
+use crate::conservation::{check_conservation, Violation};
+use crate::message::MessageContext;
+use crate::protostone::{protostone_vout, Protostone};
+use crate::registry::ProtocolRegistry;
+use crate::snapshot::seeded_txindex;
+use crate::tables::RuneTable;
 use anyhow::{anyhow, Result};
 use bitcoin;
 //use bitcoin::consensus::Decodable;
 use bitcoin::hashes::Hash;
-use bitcoin::OutPoint;
+use bitcoin::{Block, OutPoint, Transaction};
 //use hex;
 use metashrew_support::byte_view::ByteView;
 //use metashrew::utils::{ consume_exact, consume_sized_int };
-use metashrew::index_pointer::KeyValuePointer;
+use metashrew::index_pointer::{AtomicPointer, KeyValuePointer};
+use ordinals::{Artifact, Runestone};
 use protobuf::{Message, MessageField, SpecialFields};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::Cursor;
 
 pub fn outpoint_to_bytes(outpoint: &OutPoint) -> Result<Vec<u8>> {
@@ -85,6 +105,28 @@ impl From<BalanceSheet> for ProtoBalanceSheet {
         }
     }
 }
+
+/// Height of the transaction of `outpoint` and its index in the block, from the
+/// base table, where heights and transaction ids are indexed. A transaction a
+/// snapshot import seeded is not listed under its height and has its index kept
+/// apart
+fn outpoint_position(outpoint: &OutPoint, outpoint_bytes: &Vec<u8>) -> Result<(u128, u128)> {
+    let height = tables::RUNES
+        .OUTPOINT_TO_HEIGHT
+        .select(outpoint_bytes)
+        .get_value::<u64>();
+    let txindex = tables::RUNES
+        .HEIGHT_TO_TRANSACTION_IDS
+        .select_value::<u64>(height)
+        .get_list()
+        .into_iter()
+        .position(|v| v.as_ref().to_vec() == outpoint.txid.as_byte_array().to_vec())
+        .map(|index| index as u32)
+        .or_else(|| seeded_txindex(&outpoint.txid))
+        .ok_or(anyhow!("txid not indexed in table"))?;
+    Ok((height.into(), txindex.into()))
+}
+
 pub fn protorune_outpoint_to_outpoint_response(
     outpoint: &OutPoint,
     protocol_id: u128,
@@ -96,24 +138,10 @@ pub fn protorune_outpoint_to_outpoint_response(
             .select(&outpoint_bytes),
     );
 
-    let mut height: u128 = tables::RuneTable::for_protocol(protocol_id)
-        .OUTPOINT_TO_HEIGHT
-        .select(&outpoint_bytes)
-        .get_value::<u64>()
-        .into();
-    let mut txindex: u128 = tables::RuneTable::for_protocol(protocol_id)
-        .HEIGHT_TO_TRANSACTION_IDS
-        .select_value::<u64>(height as u64)
-        .get_list()
-        .into_iter()
-        .position(|v| v.as_ref().to_vec() == outpoint.txid.as_byte_array().to_vec())
-        .ok_or("")
-        .map_err(|_| anyhow!("txid not indexed in table"))? as u128;
-
-    if let Some((rune_id, _)) = balance_sheet.clone().balances.iter().next() {
-        height = rune_id.block;
-        txindex = rune_id.tx;
-    }
+    let (height, txindex) = match balance_sheet.balances.keys().next() {
+        Some(rune_id) => (rune_id.block, rune_id.tx),
+        None => outpoint_position(outpoint, &outpoint_bytes)?,
+    };
     let decoded_output: Output = Output::parse_from_bytes(
         &tables::OUTPOINT_TO_OUTPUT
             .select(&outpoint_bytes)
@@ -134,24 +162,10 @@ pub fn outpoint_to_outpoint_response(outpoint: &OutPoint) -> Result<OutpointResp
     let outpoint_bytes = outpoint_to_bytes(outpoint)?;
     let balance_sheet: BalanceSheet =
         load_sheet(&tables::RUNES.OUTPOINT_TO_RUNES.select(&outpoint_bytes));
-    let mut height: u128 = tables::RUNES
-        .OUTPOINT_TO_HEIGHT
-        .select(&outpoint_bytes)
-        .get_value::<u64>()
-        .into();
-    let mut txindex: u128 = tables::RUNES
-        .HEIGHT_TO_TRANSACTION_IDS
-        .select_value::<u64>(height as u64)
-        .get_list()
-        .into_iter()
-        .position(|v| v.as_ref().to_vec() == outpoint.txid.as_byte_array().to_vec())
-        .ok_or("")
-        .map_err(|_| anyhow!("txid not indexed in table"))? as u128;
-
-    if let Some((rune_id, _)) = balance_sheet.clone().balances.iter().next() {
-        height = rune_id.block;
-        txindex = rune_id.tx;
-    }
+    let (height, txindex) = match balance_sheet.balances.keys().next() {
+        Some(rune_id) => (rune_id.block, rune_id.tx),
+        None => outpoint_position(outpoint, &outpoint_bytes)?,
+    };
     let decoded_output: Output = Output::parse_from_bytes(
         &tables::OUTPOINT_TO_OUTPUT
             .select(&outpoint_bytes)
@@ -168,6 +182,31 @@ pub fn outpoint_to_outpoint_response(outpoint: &OutPoint) -> Result<OutpointResp
     })
 }
 
+/// Whether `outpoint` was created by the block at `height` or before it, and not
+/// spent by then. Balances of an outpoint never change once written, so this is
+/// enough to read any outpoint's balances as of `height`
+pub fn held_at(outpoint: &OutPoint, outpoint_bytes: &[u8], height: u64) -> bool {
+    let created = tables::RUNES
+        .OUTPOINT_TO_HEIGHT
+        .select(&outpoint.txid.as_byte_array().to_vec())
+        .select_value::<u32>(outpoint.vout);
+    let spent = tables::OUTPOINT_SPENT_AT.select(&outpoint_bytes.to_vec());
+    !created.get().is_empty()
+        && created.get_value::<u64>() <= height
+        && (spent.get().is_empty() || spent.get_value::<u64>() > height)
+}
+
+fn total_balances(outpoints: &[OutpointResponse]) -> BalanceSheet {
+    BalanceSheet::concat(
+        outpoints
+            .iter()
+            .map(|outpoint| outpoint.balances.clone().unwrap_or_default().into())
+            .collect(),
+    )
+}
+
+/// Outpoints `wallet` was paid to with their runes, or with a `height` only those
+/// it held as of that block, their runes summed in `balances`
 pub fn runes_by_address(input: &Vec<u8>) -> Result<WalletResponse> {
     let mut result: WalletResponse = WalletResponse::new();
     if let Some(req) = proto::protorune::WalletRequest::parse_from_bytes(input).ok() {
@@ -189,17 +228,25 @@ pub fn runes_by_address(input: &Vec<u8>) -> Result<WalletResponse> {
                     }
                 };
                 let _address = tables::OUTPOINT_SPENDABLE_BY.select(&outpoint_bytes).get();
-                if req.wallet.len() == _address.len() {
+                if req.wallet.len() == _address.len()
+                    && req
+                        .height
+                        .is_none_or(|height| held_at(&v, &outpoint_bytes, height))
+                {
                     Some(outpoint_to_outpoint_response(&v))
                 } else {
                     None
                 }
             })
             .collect::<Result<Vec<OutpointResponse>>>()?;
+        if req.height.is_some() {
+            result.balances = MessageField::some(total_balances(&result.outpoints).into());
+        }
     }
     Ok(result)
 }
 
+/// [`runes_by_address`] for the protorunes of `protocol_tag`
 pub fn protorunes_by_address(input: &Vec<u8>) -> Result<WalletResponse> {
     let mut result: WalletResponse = WalletResponse::new();
     if let Some(req) = proto::protorune::ProtorunesWalletRequest::parse_from_bytes(input).ok() {
@@ -221,7 +268,11 @@ pub fn protorunes_by_address(input: &Vec<u8>) -> Result<WalletResponse> {
                     }
                 };
                 let _address = tables::OUTPOINT_SPENDABLE_BY.select(&outpoint_bytes).get();
-                if req.wallet.len() == _address.len() {
+                if req.wallet.len() == _address.len()
+                    && req
+                        .height
+                        .is_none_or(|height| held_at(&v, &outpoint_bytes, height))
+                {
                     Some(protorune_outpoint_to_outpoint_response(
                         &v,
                         u128::from_bytes(req.clone().protocol_tag),
@@ -231,6 +282,9 @@ pub fn protorunes_by_address(input: &Vec<u8>) -> Result<WalletResponse> {
                 }
             })
             .collect::<Result<Vec<OutpointResponse>>>()?;
+        if req.height.is_some() {
+            result.balances = MessageField::some(total_balances(&result.outpoints).into());
+        }
     }
     Ok(result)
 }
@@ -258,3 +312,270 @@ pub fn runes_by_height(input: &Vec<u8>) -> Result<RunesResponse> {
     }
     Ok(result)
 }
+
+/// Read-only queries a protocol answers next to its messages. Views read the
+/// index directly, a protocol's own state lives under
+/// `RuneTable::for_protocol(tag).STATE`
+pub trait ViewContext: MessageContext {
+    fn view(method: u32, input: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Answers a `ProtocolViewRequest` with the view handler the registry holds
+/// for its protocol tag
+pub fn protocol_view(registry: &ProtocolRegistry, input: &[u8]) -> Result<Vec<u8>> {
+    let req = proto::protorune::ProtocolViewRequest::parse_from_bytes(input)?;
+    let tag = u128::from_bytes(req.protocol_tag);
+    let view = registry
+        .entry(&tag)
+        .and_then(|entry| entry.view)
+        .ok_or(anyhow!("protocol {} has no views", tag))?;
+    view(req.method, &req.input)
+}
+
+/// Previews a `SimulateRequest` at its height, or at the block after the tip
+pub fn simulate(registry: &ProtocolRegistry, input: &[u8]) -> Result<SimulateResponse> {
+    let req = SimulateRequest::parse_from_bytes(input)?;
+    let transaction = consensus_decode::<Transaction>(&mut Cursor::new(req.transaction.clone()))?;
+    let height = req
+        .height
+        .unwrap_or_else(|| tables::TIP_HEIGHT.get_value::<u64>() + 1);
+    simulate_transaction(registry, &transaction, height)
+}
+
+/// Runs `transaction` through the runestone and protostone pipeline as the
+/// transaction after the coinbase of a block at `height`, under a checkpoint
+/// that is always rolled back. A transaction the indexer would reject comes
+/// back with the error and nothing else
+pub fn simulate_transaction(
+    registry: &ProtocolRegistry,
+    transaction: &Transaction,
+    height: u64,
+) -> Result<SimulateResponse> {
+    let mut response = SimulateResponse::new();
+    let runestone = match Runestone::decipher(transaction) {
+        Some(Artifact::Runestone(runestone)) => runestone,
+        Some(Artifact::Cenotaph(_)) => {
+            response.error = String::from("transaction is a cenotaph");
+            return Ok(response);
+        }
+        None => {
+            response.error = String::from("transaction has no runestone");
+            return Ok(response);
+        }
+    };
+    let txindex: u32 = 1;
+    let genesis = bitcoin::constants::genesis_block(bitcoin::Network::Bitcoin);
+    let block = Block {
+        header: genesis.header,
+        txdata: vec![genesis.txdata[0].clone(), transaction.clone()],
+    };
+    let protostones = Protostone::from_runestone(&runestone)?;
+    let mut tags = registry.tags();
+    tags.extend(protostones.iter().map(|stone| stone.protocol_tag));
+    tags.sort();
+    tags.dedup();
+    let mut atomic = AtomicPointer::default();
+    atomic.checkpoint();
+    // base runes can only be burned from the inputs, a mint or the etching
+    let mut candidates = transaction
+        .input
+        .iter()
+        .map(|input| {
+            Ok(load_sheet(
+                &atomic.derive(
+                    &tables::RUNES
+                        .OUTPOINT_TO_RUNES
+                        .select(&consensus_encode(&input.previous_output)?),
+                ),
+            ))
+        })
+        .collect::<Result<Vec<BalanceSheet>>>()?
+        .into_iter()
+        .flat_map(|sheet| sheet.balances.into_keys())
+        .collect::<HashSet<ProtoruneRuneId>>();
+    if let Some(mint) = runestone.mint {
+        candidates.insert(mint.into());
+    }
+    if runestone.etching.is_some() {
+        candidates.insert(ProtoruneRuneId::new(height as u128, txindex as u128));
+    }
+    let burned = |atomic: &AtomicPointer, tag: u128, rune: &ProtoruneRuneId| -> u128 {
+        let runeid: std::sync::Arc<Vec<u8>> = (*rune).into();
+        atomic
+            .derive(&RuneTable::for_protocol(tag).BURNED.select(&runeid))
+            .get_value::<u128>()
+    };
+    let mut burned_before = HashMap::<(u128, ProtoruneRuneId), u128>::new();
+    for tag in &tags {
+        for rune in &candidates {
+            burned_before.insert((*tag, *rune), burned(&atomic, *tag, rune));
+        }
+    }
+    let indexed = Protorune::get_runestone_output_index(transaction).and_then(|output_index| {
+        Protorune::index_runestone(
+            registry,
+            &mut atomic,
+            transaction,
+            &runestone,
+            height,
+            txindex,
+            &block,
+            output_index,
+        )
+    });
+    if let Err(e) = indexed {
+        atomic.rollback();
+        response.error = e.to_string();
+        return Ok(response);
+    }
+    let txid = transaction.txid();
+    let num_outputs = transaction.output.len() as u32;
+    for vout in 0..num_outputs {
+        let outpoint = consensus_encode(&OutPoint::new(txid, vout))?;
+        let mut output = SimulatedOutput::new();
+        output.vout = vout;
+        output.runes = MessageField::some(
+            load_sheet(&atomic.derive(&tables::RUNES.OUTPOINT_TO_RUNES.select(&outpoint))).into(),
+        );
+        for tag in &tags {
+            let sheet = load_sheet(
+                &atomic.derive(
+                    &RuneTable::for_protocol(*tag)
+                        .OUTPOINT_TO_RUNES
+                        .select(&outpoint),
+                ),
+            );
+            if sheet.balances.values().any(|v| *v != 0) {
+                output.protorunes.push(ProtocolBalanceSheet {
+                    protocol_tag: tag.to_bytes(),
+                    balances: MessageField::some(sheet.into()),
+                    special_fields: SpecialFields::new(),
+                });
+            }
+        }
+        response.outputs.push(output);
+    }
+    for tag in &tags {
+        let mut sheet = BalanceSheet::default();
+        for rune in &candidates {
+            let before = burned_before
+                .get(&(*tag, *rune))
+                .copied()
+                .unwrap_or_default();
+            let after = burned(&atomic, *tag, rune);
+            if after > before {
+                sheet.set(rune, after - before);
+            }
+        }
+        if !sheet.balances.is_empty() {
+            response.burns.push(ProtocolBalanceSheet {
+                protocol_tag: tag.to_bytes(),
+                balances: MessageField::some(sheet.into()),
+                special_fields: SpecialFields::new(),
+            });
+        }
+    }
+    for (i, stone) in protostones.iter().enumerate() {
+        if !stone.is_message() {
+            continue;
+        }
+        let vout = protostone_vout(num_outputs, i as u32);
+        let table = RuneTable::for_protocol(stone.protocol_tag);
+        let key = consensus_encode(&OutPoint::new(txid, vout))?;
+        response.messages.push(SimulatedMessage {
+            protocol_tag: stone.protocol_tag.to_bytes(),
+            vout,
+            outcome: atomic
+                .derive(&table.OUTCOMES.select(&key))
+                .get_value::<u8>() as u32,
+            events: atomic
+                .derive(&table.EVENTS.select(&key))
+                .get_list()
+                .into_iter()
+                .map(|event| event.as_ref().clone())
+                .collect(),
+            special_fields: SpecialFields::new(),
+        });
+    }
+    atomic.rollback();
+    Ok(response)
+}
+
+/// Walks the index for rune and protorune conservation, as a debug view
+pub fn conservation(registry: &ProtocolRegistry) -> Result<ConservationResponse> {
+    let report = check_conservation(registry)?;
+    let mut response = ConservationResponse::new();
+    response.blocks = report.blocks;
+    response.outpoints = report.outpoints;
+    response.runes = report.runes;
+    for violation in report.violations {
+        let (protocol_tag, rune, outpoints) = match &violation {
+            Violation::Unetched { rune, outpoint } => (None, rune, vec![*outpoint]),
+            Violation::Supply {
+                rune, outpoints, ..
+            } => (None, rune, outpoints.clone()),
+            Violation::Overissued {
+                protocol_tag,
+                rune,
+                outpoints,
+                ..
+            } => (Some(*protocol_tag), rune, outpoints.clone()),
+        };
+        response.violations.push(ConservationViolation {
+            description: violation.to_string(),
+            protocol_tag: protocol_tag.map(|tag| tag.to_bytes()).unwrap_or_default(),
+            rune: MessageField::some(RuneId {
+                height: rune.block as u32,
+                txindex: rune.tx as u32,
+                special_fields: SpecialFields::new(),
+            }),
+            outpoints: outpoints.iter().map(core_outpoint_to_proto).collect(),
+            special_fields: SpecialFields::new(),
+        });
+    }
+    Ok(response)
+}
+
+/// State root committed at the requested height, or at the tip
+pub fn state_root(input: &[u8]) -> Result<StateRootResponse> {
+    let req = StateRootRequest::parse_from_bytes(input)?;
+    let height = req
+        .height
+        .unwrap_or_else(|| tables::TIP_HEIGHT.get_value::<u64>());
+    let root = commitment::state_root(height)
+        .ok_or_else(|| anyhow!("no state root at height {}", height))?;
+    let mut response = StateRootResponse::new();
+    response.height = height;
+    response.root = root.to_vec();
+    Ok(response)
+}
+
+/// Balances of an `OutpointWithProtocol` with the proof of their inclusion in
+/// the state root of the block that created it, an empty protocol meaning base
+/// runes. It proves the outpoint was created with those balances, not that it
+/// is unspent
+pub fn outpoint_proof(input: &[u8]) -> Result<OutpointProofResponse> {
+    let req = OutpointWithProtocol::parse_from_bytes(input)?;
+    let outpoint = OutPoint::new(bitcoin::Txid::from_slice(&req.txid)?, req.vout);
+    let protocol_tag = match req.protocol.is_empty() {
+        true => RUNES_TAG,
+        false => u128::from_bytes(req.protocol),
+    };
+    let (sheet, proof) = commitment::outpoint_proof(protocol_tag, &outpoint)?;
+    let root = commitment::state_root(proof.height)
+        .ok_or_else(|| anyhow!("no state root at height {}", proof.height))?;
+    let mut response = OutpointProofResponse::new();
+    response.balances = MessageField::some(sheet.into());
+    response.height = proof.height;
+    response.previous_root = proof.previous.to_vec();
+    response.root = root.to_vec();
+    response.index = proof.proof.index;
+    response.leaves = proof.proof.leaves;
+    response.siblings = proof
+        .proof
+        .siblings
+        .iter()
+        .map(|sibling| sibling.to_vec())
+        .collect();
+    Ok(response)
+}